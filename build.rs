@@ -0,0 +1,108 @@
+// Script de compilation : verifie les cles de traduction.
+// Chaque cle litterale passee a t!(), tv!() ou tl!() dans src/ doit exister dans langs/en.json
+// (l'anglais sert de repli : une cle absente s'afficherait telle quelle a l'execution).
+// Les cles de en.json jamais citees dans le code sont signalees par un avertissement.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=langs/en.json");
+
+    let en = fs::read_to_string("langs/en.json").expect("langs/en.json illisible");
+    let en: serde_json::Value = serde_json::from_str(&en).expect("langs/en.json invalide");
+    let mut keys = BTreeSet::new();
+    flatten(&en, "", &mut keys);
+
+    let mut sources = Vec::new();
+    collect_sources(Path::new("src"), &mut sources);
+    sources.sort();
+
+    // Cle utilisee → premier emplacement (fichier:ligne)
+    let mut used: BTreeMap<String, String> = BTreeMap::new();
+    let mut all_code = String::new();
+    for path in &sources {
+        let content = fs::read_to_string(path).expect("source illisible");
+        // Les commentaires (exemples d'utilisation de t!()) ne comptent pas
+        let code: String = content
+            .lines()
+            .map(|line| if line.trim_start().starts_with("//") { "" } else { line })
+            .collect::<Vec<_>>()
+            .join("\n");
+        for (offset, key) in macro_keys(&code) {
+            let line = code[..offset].matches('\n').count() + 1;
+            used.entry(key).or_insert_with(|| format!("{}:{}", path.display(), line));
+        }
+        all_code.push_str(&code);
+    }
+
+    let missing: Vec<_> = used.iter().filter(|(key, _)| !keys.contains(*key)).collect();
+    for (key, location) in &missing {
+        println!("cargo:warning=cle de traduction absente de langs/en.json : {} ({})", key, location);
+    }
+    if !missing.is_empty() {
+        panic!("{} cle(s) de traduction absente(s) de langs/en.json", missing.len());
+    }
+
+    // Cles citees hors de t!() (tables de libelles, police manquante...) : utilisees aussi
+    for key in keys.iter().filter(|key| !all_code.contains(&format!("\"{}\"", key))) {
+        println!("cargo:warning=cle de traduction inutilisee : {}", key);
+    }
+}
+
+/// Aplatit en.json en cles pointees (meme regle que i18n::flatten_json)
+fn flatten(value: &serde_json::Value, prefix: &str, keys: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::Object(obj) => {
+            for (key, val) in obj {
+                let new_prefix = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(val, &new_prefix, keys);
+            }
+        }
+        serde_json::Value::String(_) => {
+            keys.insert(prefix.to_string());
+        }
+        _ => {}
+    }
+}
+
+fn collect_sources(dir: &Path, sources: &mut Vec<std::path::PathBuf>) {
+    for entry in fs::read_dir(dir).expect("repertoire src illisible").flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_sources(&path, sources);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            sources.push(path);
+        }
+    }
+}
+
+/// Cles litterales des appels t!("..."), tv!("...") et tl!("...") avec leur position dans le texte.
+/// Un premier argument non litteral (variable) n'est pas verifiable et est ignore.
+fn macro_keys(code: &str) -> Vec<(usize, String)> {
+    let mut keys = Vec::new();
+    let mut search = 0;
+    while let Some(found) = code[search..].find("!(") {
+        let bang = search + found;
+        search = bang + 2;
+        let name_start = code[..bang]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        if !matches!(&code[name_start..bang], "t" | "tv" | "tl") {
+            continue;
+        }
+        let rest = code[search..].trim_start();
+        if let Some(literal) = rest.strip_prefix('"') {
+            if let Some(end) = literal.find('"') {
+                keys.push((bang, literal[..end].to_string()));
+            }
+        }
+    }
+    keys
+}
@@ -0,0 +1,57 @@
+<#
+.SYNOPSIS
+    Client du canal de controle d'IronCloak sous Windows (tube nomme, une commande par ligne,
+    une reponse JSON par ligne).
+
+.EXAMPLE
+    .\Get-IronCloakStatus.ps1
+    .\Get-IronCloakStatus.ps1 -Command stats
+    .\Get-IronCloakStatus.ps1 -Command pause
+    (.\Get-IronCloakStatus.ps1).bootstrap
+#>
+[CmdletBinding()]
+param(
+    # Commande envoyee : status, stats, check, pause, resume, retry, log_level [filtre]
+    [string]$Command = "status",
+    # Nom du tube (control.pipe_name, sans le prefixe \\.\pipe\)
+    [string]$PipeName = "ironcloak",
+    # Fichier cookie (control.cookie_path) ; ignore avec -Token
+    [string]$CookiePath = "$env:LOCALAPPDATA\IronCloak\control.cookie",
+    # Jeton fixe (control.token)
+    [string]$Token,
+    # Delai de connexion au tube, en millisecondes
+    [int]$TimeoutMs = 5000
+)
+
+$ErrorActionPreference = "Stop"
+
+if (-not $Token -and (Test-Path $CookiePath)) {
+    $Token = (Get-Content -Raw $CookiePath).Trim()
+}
+
+$pipe = New-Object System.IO.Pipes.NamedPipeClientStream(".", $PipeName, [System.IO.Pipes.PipeDirection]::InOut)
+try {
+    $pipe.Connect($TimeoutMs)
+    $encoding = New-Object System.Text.UTF8Encoding($false)
+    $reader = New-Object System.IO.StreamReader($pipe, $encoding)
+    $writer = New-Object System.IO.StreamWriter($pipe, $encoding)
+    $writer.AutoFlush = $true
+
+    if ($Token) {
+        $writer.WriteLine("authenticate $Token")
+        $auth = $reader.ReadLine() | ConvertFrom-Json
+        if (-not $auth.ok) {
+            throw $auth.error
+        }
+    }
+
+    $writer.WriteLine($Command)
+    $response = $reader.ReadLine() | ConvertFrom-Json
+    if (-not $response.ok) {
+        throw $response.error
+    }
+    $response
+}
+finally {
+    $pipe.Dispose()
+}
@@ -0,0 +1,205 @@
+// Journal d'audit des connexions SOCKS, separe des journaux de traces.
+// Une ligne par connexion relayee (JSONL ou CSV) : debut, duree, octets dans chaque sens et
+// destination selon le niveau de confidentialite choisi (audit.mode) : complete, domaine
+// seul, empreinte salee, ou aucun journal.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{Local, SecondsFormat};
+use sha2::{Digest, Sha256};
+
+use crate::config::{AuditConfig, AuditFormat, AuditMode};
+
+/// Fichier du sel des empreintes, dans le repertoire de donnees de Tor : le meme sel d'un
+/// lancement a l'autre permet de rapprocher les destinations sans les reveler
+const SALT_FILE: &str = "audit-salt";
+
+static AUDIT: OnceLock<AuditLog> = OnceLock::new();
+
+struct AuditLog {
+    file: Mutex<File>,
+    mode: AuditMode,
+    format: AuditFormat,
+    salt: Vec<u8>,
+}
+
+/// Ouvre le journal d'audit (ajout en fin de fichier) ; sans effet si audit.mode = "off"
+pub fn init(config: &AuditConfig, data_dir: &Path) -> Result<()> {
+    if config.mode == AuditMode::Off {
+        return Ok(());
+    }
+    let path = config.path();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    if config.format == AuditFormat::Csv && file.metadata()?.len() == 0 {
+        writeln!(file, "time,conn,destination,port,duration_secs,bytes_up,bytes_down")?;
+    }
+    let salt = if config.mode == AuditMode::Hashed {
+        load_salt(data_dir)?
+    } else {
+        Vec::new()
+    };
+
+    let _ = AUDIT.set(AuditLog {
+        file: Mutex::new(file),
+        mode: config.mode,
+        format: config.format,
+        salt,
+    });
+    tracing::info!("{}", crate::tl!("audit.opened", path = path.display(), mode = config.mode.as_str()));
+    Ok(())
+}
+
+/// Enregistre une connexion terminee (sans effet si le journal d'audit n'est pas ouvert)
+pub fn record(conn_id: u64, host: &str, port: u16, duration: Duration, up: u64, down: u64) {
+    let Some(audit) = AUDIT.get() else {
+        return;
+    };
+
+    let end = Local::now();
+    let start = end - chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero());
+    let time = start.to_rfc3339_opts(SecondsFormat::Secs, false);
+    let destination = match audit.mode {
+        AuditMode::Full | AuditMode::Off => host.to_string(),
+        AuditMode::Domain => domain_only(host).to_string(),
+        AuditMode::Hashed => hash_host(host, &audit.salt),
+    };
+    // Le port n'est conserve qu'en mode complet
+    let port = (audit.mode == AuditMode::Full).then_some(port);
+    let secs = (duration.as_millis() as f64) / 1000.0;
+
+    let line = match audit.format {
+        AuditFormat::Jsonl => serde_json::json!({
+            "time": time,
+            "conn": conn_id,
+            "destination": destination,
+            "port": port,
+            "duration_secs": secs,
+            "bytes_up": up,
+            "bytes_down": down,
+        })
+        .to_string(),
+        AuditFormat::Csv => format!(
+            "{},{},{},{},{},{},{}",
+            time,
+            conn_id,
+            csv_field(&destination),
+            port.map(|p| p.to_string()).unwrap_or_default(),
+            secs,
+            up,
+            down
+        ),
+    };
+
+    let mut file = audit.file.lock().unwrap();
+    if let Err(e) = writeln!(file, "{}", line) {
+        tracing::warn!("{}", crate::tl!("audit.write_failed", error = e));
+    }
+}
+
+/// Libelles de second niveau courants sous les domaines de pays (co.uk, com.br, ac.jp...)
+const COUNTRY_SLDS: &[&str] = &[
+    "ac", "co", "com", "edu", "go", "gob", "gouv", "gov", "ltd", "mil", "ne", "net", "nom", "or", "org", "plc", "sch",
+];
+
+/// Domaine enregistrable approximatif (sans liste des suffixes publics) : les deux derniers
+/// libelles, trois sous un domaine de second niveau de pays connu (example.co.uk) ; IP
+/// inchangees. Dans le doute, le domaine est raccourci plutot que de garder un sous-domaine.
+pub fn domain_only(host: &str) -> &str {
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+    let labels: Vec<&str> = host.trim_end_matches('.').rsplit('.').collect();
+    let keep = match labels.as_slice() {
+        [tld, second, _, ..]
+            if tld.len() == 2 && COUNTRY_SLDS.iter().any(|sld| second.eq_ignore_ascii_case(sld)) =>
+        {
+            3
+        }
+        _ => 2,
+    };
+    if labels.len() <= keep {
+        return host;
+    }
+    let cut: usize = labels[..keep].iter().map(|label| label.len() + 1).sum();
+    &host[host.trim_end_matches('.').len() + 1 - cut..]
+}
+
+/// Empreinte salee d'un hote (SHA-256 tronque, 32 caracteres hexadecimaux)
+pub fn hash_host(host: &str, salt: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(host.to_ascii_lowercase().as_bytes());
+    data_encoding::HEXLOWER.encode(&hasher.finalize()[..16])
+}
+
+/// Lit le sel des empreintes, ou le cree au premier lancement
+fn load_salt(data_dir: &Path) -> Result<Vec<u8>> {
+    let path = data_dir.join(SALT_FILE);
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        if let Ok(salt) = data_encoding::HEXLOWER.decode(content.trim().as_bytes()) {
+            if salt.len() >= 16 {
+                return Ok(salt);
+            }
+        }
+    }
+    let salt: [u8; 32] = rand::random();
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(&path, data_encoding::HEXLOWER.encode(&salt))
+        .with_context(|| path.display().to_string())?;
+    Ok(salt.to_vec())
+}
+
+/// Champ CSV entre guillemets s'il contient un separateur, un guillemet ou un saut de ligne
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_only_keeps_registrable_domain() {
+        assert_eq!(domain_only("example.com"), "example.com");
+        assert_eq!(domain_only("www.example.com"), "example.com");
+        assert_eq!(domain_only("a.b.c.example.org"), "example.org");
+        assert_eq!(domain_only("localhost"), "localhost");
+        assert_eq!(domain_only("www.example.com."), "example.com.");
+    }
+
+    #[test]
+    fn domain_only_drops_subdomains_of_short_names() {
+        assert_eq!(domain_only("www.abc.de"), "abc.de");
+        assert_eq!(domain_only("mail.web.de"), "web.de");
+        assert_eq!(domain_only("login.gmx.de"), "gmx.de");
+        assert_eq!(domain_only("a.b.gmx.de"), "gmx.de");
+        assert_eq!(domain_only("www.bbc.uk"), "bbc.uk");
+    }
+
+    #[test]
+    fn domain_only_keeps_country_second_level_domains() {
+        assert_eq!(domain_only("www.example.co.uk"), "example.co.uk");
+        assert_eq!(domain_only("example.co.uk"), "example.co.uk");
+        assert_eq!(domain_only("login.shop.com.br"), "shop.com.br");
+        assert_eq!(domain_only("a.b.univ.ac.jp"), "univ.ac.jp");
+        assert_eq!(domain_only("WWW.Example.CO.UK"), "Example.CO.UK");
+    }
+
+    #[test]
+    fn domain_only_leaves_ip_addresses() {
+        assert_eq!(domain_only("192.0.2.1"), "192.0.2.1");
+        assert_eq!(domain_only("2001:db8::1"), "2001:db8::1");
+    }
+}
@@ -0,0 +1,130 @@
+// Lancement automatique d'IronCloak a l'ouverture de session.
+// Windows : valeur "IronCloak" de la cle HKCU\Software\Microsoft\Windows\CurrentVersion\Run.
+// Linux : fichier XDG ~/.config/autostart/ironcloak.desktop.
+// La commande enregistree reprend le fichier de configuration et le repertoire de travail
+// courants (les chemins relatifs de la configuration en dependent).
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Arguments de la commande enregistree : executable, configuration, repertoire de travail
+fn launch_args(config_path: &Path) -> Result<(PathBuf, PathBuf, PathBuf)> {
+    let exe = std::env::current_exe().context(crate::t!("autostart.no_exe"))?;
+    let workdir = std::env::current_dir()?;
+    let config = workdir.join(config_path);
+    Ok((exe, config, workdir))
+}
+
+#[cfg(windows)]
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+#[cfg(windows)]
+const RUN_VALUE: &str = "IronCloak";
+
+/// Indique si le lancement automatique est enregistre
+#[cfg(windows)]
+pub fn is_enabled() -> bool {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(RUN_KEY)
+        .and_then(|key| key.get_value::<String, _>(RUN_VALUE))
+        .is_ok()
+}
+
+/// Enregistre ou retire le lancement automatique
+#[cfg(windows)]
+pub fn set_enabled(enabled: bool, config_path: &Path) -> Result<()> {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+        .create_subkey(RUN_KEY)
+        .context(crate::t!("autostart.update_failed"))?;
+    if enabled {
+        let (exe, config, workdir) = launch_args(config_path)?;
+        let command = format!(
+            "\"{}\" --config \"{}\" --workdir \"{}\"",
+            exe.display(),
+            config.display(),
+            workdir.display()
+        );
+        key.set_value(RUN_VALUE, &command)
+            .context(crate::t!("autostart.update_failed"))?;
+    } else if is_enabled() {
+        key.delete_value(RUN_VALUE)
+            .context(crate::t!("autostart.update_failed"))?;
+    }
+    Ok(())
+}
+
+/// Chemin du fichier .desktop ($XDG_CONFIG_HOME/autostart, ~/.config/autostart par defaut)
+#[cfg(not(windows))]
+fn desktop_file() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("autostart").join("ironcloak.desktop"))
+}
+
+/// Indique si le lancement automatique est enregistre
+#[cfg(not(windows))]
+pub fn is_enabled() -> bool {
+    desktop_file().is_some_and(|path| path.is_file())
+}
+
+/// Enregistre ou retire le lancement automatique
+#[cfg(not(windows))]
+pub fn set_enabled(enabled: bool, config_path: &Path) -> Result<()> {
+    let path = desktop_file().context(crate::t!("autostart.no_home"))?;
+    if !enabled {
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| crate::t!("autostart.write_failed", path = path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let (exe, config, workdir) = launch_args(config_path)?;
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=IronCloak\n\
+         Comment=SOCKS5 proxy routing traffic through Tor\n\
+         Exec={} --config {} --workdir {}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        desktop_quote(&exe),
+        desktop_quote(&config),
+        desktop_quote(&workdir)
+    );
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| crate::t!("autostart.write_failed", path = dir.display()))?;
+    }
+    std::fs::write(&path, entry)
+        .with_context(|| crate::t!("autostart.write_failed", path = path.display()))?;
+    Ok(())
+}
+
+/// Argument de la cle Exec entre guillemets (specification Desktop Entry)
+#[cfg(not(windows))]
+fn desktop_quote(path: &Path) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.display().to_string().chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            // Echappement de chaine puis de guillemets : quatre barres obliques
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,249 @@
+// Generateur de charge "ironcloak bench" : ouvre des connexions SOCKS simultanees a travers
+// l'instance en cours d'execution vers une destination, puis rapporte les percentiles des
+// latences (handshake SOCKS, ouverture du flux Tor) et du debit, pour ajuster les tampons et
+// les limites ([proxy.tcp], [proxy.stream]).
+// Sans chemin HTTP, chaque connexion est fermee des l'ouverture du flux ; avec --http-path,
+// une requete GET (HTTP/1.0, en clair) est envoyee et la reponse lue jusqu'a la fin.
+// Pas de point d'echo local : les relais de sortie refusent les adresses privees.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+
+use crate::config::IronCloakConfig;
+
+/// Options de la sous-commande "bench"
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    /// Destination des connexions (hote:port), joint a travers Tor
+    #[arg(long, default_value = "check.torproject.org:80")]
+    target: String,
+    /// Nombre total de connexions
+    #[arg(long, default_value_t = 50)]
+    requests: usize,
+    /// Connexions ouvertes simultanement
+    #[arg(long, default_value_t = 10)]
+    concurrency: usize,
+    /// Chemin telecharge en HTTP sur chaque connexion (mesure du debit)
+    #[arg(long, value_name = "PATH")]
+    http_path: Option<String>,
+    /// Delai maximal par connexion (secondes)
+    #[arg(long, default_value_t = 60)]
+    timeout_secs: u64,
+}
+
+/// Mesures d'une connexion reussie
+struct Sample {
+    handshake: Duration,
+    connect: Duration,
+    /// Octets recus et duree du transfert (requete HTTP uniquement)
+    bytes: u64,
+    transfer: Duration,
+}
+
+/// Lance le banc de charge sur un runtime dedie et retourne le rapport a afficher
+pub fn run(config: &IronCloakConfig, args: &BenchArgs) -> Result<String> {
+    let (host, port) = args
+        .target
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host.trim_matches(['[', ']']).to_string(), port.parse::<u16>().ok()?)))
+        .filter(|(host, _)| !host.is_empty() && host.len() <= 255)
+        .with_context(|| crate::t!("bench.invalid_target", target = &args.target))?;
+    let proxy = match local_host(&config.proxy.listen_addr) {
+        host if host.contains(':') && !host.starts_with('[') => format!("[{}]:{}", host, config.proxy.listen_port),
+        host => format!("{}:{}", host, config.proxy.listen_port),
+    };
+    let concurrency = args.concurrency.clamp(1, args.requests.max(1));
+    println!(
+        "{}",
+        crate::t!(
+            "bench.start",
+            proxy = &proxy,
+            target = &args.target,
+            requests = args.requests,
+            concurrency = concurrency
+        )
+    );
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let started = Instant::now();
+    let results = runtime.block_on(async {
+        let limit = Arc::new(Semaphore::new(concurrency));
+        let target = Arc::new((host, port));
+        let proxy = Arc::new(proxy);
+        let mut tasks = Vec::with_capacity(args.requests);
+        for _ in 0..args.requests {
+            let limit = Arc::clone(&limit);
+            let target = Arc::clone(&target);
+            let proxy = Arc::clone(&proxy);
+            let http_path = args.http_path.clone();
+            let proxy_protocol = config.proxy.proxy_protocol;
+            let timeout = Duration::from_secs(args.timeout_secs);
+            tasks.push(tokio::spawn(async move {
+                let _permit = limit.acquire().await.expect("semaphore du banc de charge");
+                let attempt = bench_connection(&proxy, &target.0, target.1, http_path.as_deref(), proxy_protocol);
+                match tokio::time::timeout(timeout, attempt).await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow::anyhow!("{}", crate::t!("bench.timeout"))),
+                }
+            }));
+        }
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            results.push(task.await.map_err(anyhow::Error::from).and_then(|result| result));
+        }
+        results
+    });
+    Ok(report(results, started.elapsed()))
+}
+
+/// Une connexion : handshake SOCKS5, ouverture du flux, puis transfert HTTP eventuel
+async fn bench_connection(
+    proxy: &str,
+    host: &str,
+    port: u16,
+    http_path: Option<&str>,
+    proxy_protocol: bool,
+) -> Result<Sample> {
+    let start = Instant::now();
+    let mut stream = TcpStream::connect(proxy)
+        .await
+        .with_context(|| crate::t!("bench.proxy_unreachable", proxy = proxy))?;
+    stream.set_nodelay(true)?;
+    if proxy_protocol {
+        // En-tete PROXY sans adresse : le proxy retient celle de la connexion
+        stream.write_all(b"PROXY UNKNOWN\r\n").await?;
+    }
+
+    // Methode "sans authentification"
+    stream.write_all(&[0x05, 0x01, 0x00]).await?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice != [0x05, 0x00] {
+        bail!("{}", crate::t!("bench.socks_refused", code = choice[1]));
+    }
+    let handshake = start.elapsed();
+
+    // CONNECT par nom de domaine : la resolution se fait a la sortie
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        bail!("{}", crate::t!("bench.socks_refused", code = reply[1]));
+    }
+    let addr_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        _ => usize::from(stream.read_u8().await?),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await?;
+    let connect = start.elapsed() - handshake;
+
+    let mut bytes = 0;
+    let mut transfer = Duration::ZERO;
+    if let Some(path) = http_path {
+        let transfer_start = Instant::now();
+        let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n", path, host);
+        stream.write_all(request.as_bytes()).await?;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            bytes += n as u64;
+        }
+        transfer = transfer_start.elapsed();
+    }
+    Ok(Sample { handshake, connect, bytes, transfer })
+}
+
+/// Rapport : reussites et echecs, percentiles des latences et du debit
+fn report(results: Vec<Result<Sample>>, elapsed: Duration) -> String {
+    let total = results.len();
+    let mut samples = Vec::new();
+    let mut first_error = None;
+    for result in results {
+        match result {
+            Ok(sample) => samples.push(sample),
+            Err(e) => {
+                first_error.get_or_insert_with(|| format!("{:#}", e));
+            }
+        }
+    }
+
+    let secs = elapsed.as_secs_f64();
+    let mut lines = vec![crate::t!(
+        "bench.summary",
+        ok = samples.len(),
+        failed = total - samples.len(),
+        secs = format!("{:.1}", secs),
+        rate = format!("{:.2}", samples.len() as f64 / secs.max(f64::EPSILON))
+    )];
+    if let Some(error) = first_error {
+        lines.push(crate::t!("bench.first_error", error = error));
+    }
+    if samples.is_empty() {
+        return lines.join("\n");
+    }
+
+    for (name, mut values) in [
+        (crate::t!("bench.handshake"), samples.iter().map(|s| s.handshake).collect::<Vec<_>>()),
+        (crate::t!("bench.connect"), samples.iter().map(|s| s.connect).collect()),
+    ] {
+        values.sort();
+        let ms = |p: f64| format!("{:.0}", percentile(&values, p).as_secs_f64() * 1000.0);
+        lines.push(crate::t!(
+            "bench.latency",
+            name = name,
+            p50 = ms(0.50),
+            p90 = ms(0.90),
+            p99 = ms(0.99),
+            max = ms(1.0)
+        ));
+    }
+
+    // Debit par connexion (Mbit/s) et debit cumule sur la duree du banc
+    let mut rates: Vec<f64> = samples
+        .iter()
+        .filter(|s| s.bytes > 0 && !s.transfer.is_zero())
+        .map(|s| s.bytes as f64 * 8.0 / s.transfer.as_secs_f64() / 1_000_000.0)
+        .collect();
+    if !rates.is_empty() {
+        rates.sort_by(f64::total_cmp);
+        let bytes: u64 = samples.iter().map(|s| s.bytes).sum();
+        let mbps = |p: f64| format!("{:.2}", percentile(&rates, p));
+        lines.push(crate::t!(
+            "bench.throughput",
+            p10 = mbps(0.10),
+            p50 = mbps(0.50),
+            p90 = mbps(0.90),
+            total = format!("{:.2}", bytes as f64 * 8.0 / secs.max(f64::EPSILON) / 1_000_000.0),
+            bytes = bytes
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Valeur au rang `p` (0.0 a 1.0) d'une serie triee non vide
+fn percentile<T: Copy>(sorted: &[T], p: f64) -> T {
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+/// Adresse a joindre pour une ecoute sur toutes les interfaces
+pub fn local_host(listen_addr: &str) -> &str {
+    match listen_addr {
+        "0.0.0.0" => "127.0.0.1",
+        "::" | "[::]" => "::1",
+        addr => addr,
+    }
+}
@@ -0,0 +1,260 @@
+// Import de ponts Tor : lignes "Bridge" d'un torrc ou chaines de ponts de Tor Browser
+// (bridges.torproject.org), converties en configuration IronCloak ([tor.bridges]).
+// Les lignes "ClientTransportPlugin" deviennent des transports enfichables ; la syntaxe de
+// chaque pont est verifiee avant d'ecrire quoi que ce soit dans la configuration.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+
+use anyhow::Result;
+
+use crate::config::IronCloakConfig;
+
+/// Ponts et transports lus dans un texte importe
+#[derive(Debug, Default)]
+pub struct ImportedBridges {
+    /// Lignes de pont normalisees, sans le mot-cle "Bridge"
+    pub lines: Vec<String>,
+    /// Transport enfichable → commande (executable et arguments)
+    pub transports: BTreeMap<String, String>,
+}
+
+impl ImportedBridges {
+    /// Transports utilises par les ponts importes sans executable connu (ni dans le texte
+    /// importe, ni deja dans la configuration)
+    pub fn missing_transports(&self, config: &IronCloakConfig) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .lines
+            .iter()
+            .filter_map(|line| transport_of(line))
+            .filter(|name| !self.transports.contains_key(*name) && !config.tor.bridges.transports.contains_key(*name))
+            .map(str::to_string)
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    /// Ajoute les ponts (sans doublon) et les transports (sans remplacer ceux deja
+    /// configures) a la configuration, et active les ponts. Retourne le nombre de ponts ajoutes.
+    pub fn merge_into(self, config: &mut IronCloakConfig) -> usize {
+        let bridges = &mut config.tor.bridges;
+        let mut added = 0;
+        for line in self.lines {
+            if !bridges.lines.contains(&line) {
+                bridges.lines.push(line);
+                added += 1;
+            }
+        }
+        for (name, command) in self.transports {
+            bridges.transports.entry(name).or_insert(command);
+        }
+        bridges.enabled = true;
+        added
+    }
+}
+
+/// Lit des ponts au format torrc ("Bridge obfs4 ...", "ClientTransportPlugin ...", "UseBridges 1")
+/// ou Tor Browser (une ligne par pont, sans mot-cle). Lignes vides et commentaires ignores.
+pub fn parse(text: &str) -> Result<ImportedBridges> {
+    let mut imported = ImportedBridges::default();
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let result = if keyword.eq_ignore_ascii_case("UseBridges") {
+            Ok(())
+        } else if keyword.eq_ignore_ascii_case("ClientTransportPlugin") {
+            parse_transport_plugin(rest, &mut imported.transports)
+        } else {
+            let bridge = if keyword.eq_ignore_ascii_case("Bridge") { rest } else { line };
+            validate_bridge(bridge).map(|normalized| imported.lines.push(normalized))
+        };
+        if let Err(reason) = result {
+            anyhow::bail!("{}", crate::t!("bridges.invalid_line", line = index + 1, reason = reason));
+        }
+    }
+    if imported.lines.is_empty() {
+        anyhow::bail!("{}", crate::t!("bridges.none_found"));
+    }
+    Ok(imported)
+}
+
+/// "obfs4,meek_lite exec /usr/bin/lyrebird -enableLogging" : une commande par transport
+fn parse_transport_plugin(rest: &str, transports: &mut BTreeMap<String, String>) -> Result<(), String> {
+    let mut parts = rest.split_whitespace();
+    let names = parts.next().unwrap_or_default();
+    if parts.next() != Some("exec") {
+        return Err(crate::t!("bridges.plugin_not_exec"));
+    }
+    let command = parts.collect::<Vec<_>>().join(" ");
+    if command.is_empty() {
+        return Err(crate::t!("bridges.plugin_not_exec"));
+    }
+    for name in names.split(',') {
+        if !is_transport_name(name) {
+            return Err(crate::t!("bridges.bad_transport", name = name));
+        }
+        transports.insert(name.to_string(), command.clone());
+    }
+    Ok(())
+}
+
+/// Verifie une ligne de pont "[transport] adresse:port empreinte [cle=valeur ...]" et la
+/// retourne normalisee (espaces simples)
+fn validate_bridge(line: &str) -> Result<String, String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let mut rest = tokens.as_slice();
+
+    // Transport enfichable facultatif (absent pour un pont "vanilla")
+    let transport = match rest.first() {
+        Some(first) if first.parse::<SocketAddr>().is_err() => {
+            if !is_transport_name(first) {
+                return Err(crate::t!("bridges.bad_transport", name = first));
+            }
+            rest = &rest[1..];
+            Some(*first)
+        }
+        _ => None,
+    };
+
+    match rest.first() {
+        Some(addr) if addr.parse::<SocketAddr>().is_ok() => rest = &rest[1..],
+        Some(addr) => return Err(crate::t!("bridges.bad_address", addr = addr)),
+        None => return Err(crate::t!("bridges.bad_address", addr = "")),
+    }
+
+    // Empreinte RSA du pont (40 caracteres hexadecimaux), indispensable a arti
+    match rest.first() {
+        Some(fingerprint) if is_fingerprint(fingerprint) => rest = &rest[1..],
+        _ => return Err(crate::t!("bridges.missing_fingerprint")),
+    }
+
+    // Parametres du transport ; un pont sans transport n'en accepte aucun
+    for arg in rest {
+        if transport.is_none() || !arg.contains('=') {
+            return Err(crate::t!("bridges.bad_argument", arg = arg));
+        }
+    }
+    Ok(tokens.join(" "))
+}
+
+/// Transport d'une ligne de pont deja validee (None pour un pont sans transport)
+fn transport_of(line: &str) -> Option<&str> {
+    line.split_whitespace().next().filter(|first| first.parse::<SocketAddr>().is_err())
+}
+
+/// Nom de transport au sens de Tor : lettre ou "_", puis lettres, chiffres ou "_"
+fn is_transport_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_fingerprint(value: &str) -> bool {
+    value.len() == 40 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "0123456789ABCDEF0123456789ABCDEF01234567";
+
+    fn obfs4_line() -> String {
+        format!("obfs4 192.0.2.10:443 {} cert=AAAA iat-mode=0", FINGERPRINT)
+    }
+
+    #[test]
+    fn parses_torrc_block() {
+        crate::i18n::init("en");
+        let text = format!(
+            "# ponts\nUseBridges 1\nClientTransportPlugin obfs4,meek_lite exec /usr/bin/lyrebird -enableLogging\n\
+             Bridge  obfs4   192.0.2.10:443 {} cert=AAAA iat-mode=0\n\nbridge 198.51.100.7:9001 {}\n",
+            FINGERPRINT, FINGERPRINT
+        );
+        let imported = parse(&text).unwrap();
+        assert_eq!(imported.lines, vec![obfs4_line(), format!("198.51.100.7:9001 {}", FINGERPRINT)]);
+        assert_eq!(imported.transports.get("obfs4").unwrap(), "/usr/bin/lyrebird -enableLogging");
+        assert_eq!(imported.transports.get("meek_lite").unwrap(), "/usr/bin/lyrebird -enableLogging");
+    }
+
+    #[test]
+    fn parses_tor_browser_lines() {
+        crate::i18n::init("en");
+        let text = format!("{}\n  webtunnel [2001:db8::1]:443 {} url=https://example.com/path\n", obfs4_line(), FINGERPRINT);
+        let imported = parse(&text).unwrap();
+        assert_eq!(imported.lines.len(), 2);
+        assert_eq!(transport_of(&imported.lines[1]), Some("webtunnel"));
+        assert!(imported.transports.is_empty());
+    }
+
+    #[test]
+    fn validate_bridge_accepts_vanilla_and_transports() {
+        crate::i18n::init("en");
+        let vanilla = format!("192.0.2.1:9001 {}", FINGERPRINT);
+        assert_eq!(validate_bridge(&vanilla).unwrap(), vanilla);
+        assert_eq!(transport_of(&vanilla), None);
+        assert_eq!(validate_bridge(&format!("obfs4  192.0.2.10:443\t{}  cert=AAAA iat-mode=0", FINGERPRINT)).unwrap(), obfs4_line());
+    }
+
+    #[test]
+    fn validate_bridge_rejects_malformed_lines() {
+        crate::i18n::init("en");
+        // Empreinte absente, trop courte ou non hexadecimale
+        assert_eq!(validate_bridge("obfs4 192.0.2.10:443 cert=AAAA"), Err(crate::t!("bridges.missing_fingerprint")));
+        assert!(validate_bridge(&format!("192.0.2.1:9001 {}", &FINGERPRINT[1..])).is_err());
+        assert!(validate_bridge(&format!("192.0.2.1:9001 {}", FINGERPRINT.replace('A', "G"))).is_err());
+        // Adresse sans port ou absente
+        assert_eq!(
+            validate_bridge(&format!("obfs4 192.0.2.10 {}", FINGERPRINT)),
+            Err(crate::t!("bridges.bad_address", addr = "192.0.2.10"))
+        );
+        assert!(validate_bridge("obfs4").is_err());
+        // Nom de transport invalide
+        assert!(validate_bridge(&format!("obfs-4 192.0.2.10:443 {}", FINGERPRINT)).is_err());
+        // Parametres sans transport, ou sans "="
+        assert!(validate_bridge(&format!("192.0.2.1:9001 {} cert=AAAA", FINGERPRINT)).is_err());
+        assert!(validate_bridge(&format!("obfs4 192.0.2.10:443 {} cert", FINGERPRINT)).is_err());
+    }
+
+    #[test]
+    fn parse_reports_line_number() {
+        crate::i18n::init("en");
+        let text = format!("{}\n# commentaire\nBridge obfs4 192.0.2.10:443\n", obfs4_line());
+        let error = parse(&text).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            crate::t!("bridges.invalid_line", line = 3, reason = crate::t!("bridges.missing_fingerprint"))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bad_plugins_and_empty_text() {
+        crate::i18n::init("en");
+        assert!(parse(&format!("ClientTransportPlugin obfs4 socks5 127.0.0.1:1080\n{}", obfs4_line())).is_err());
+        assert!(parse(&format!("ClientTransportPlugin obfs4 exec\n{}", obfs4_line())).is_err());
+        assert!(parse(&format!("ClientTransportPlugin obfs-4 exec /usr/bin/lyrebird\n{}", obfs4_line())).is_err());
+        assert_eq!(parse("# rien\nUseBridges 1\n").unwrap_err().to_string(), crate::t!("bridges.none_found"));
+    }
+
+    #[test]
+    fn merge_reports_missing_transports_and_skips_duplicates() {
+        crate::i18n::init("en");
+        let mut config = IronCloakConfig::default();
+        let text = format!("{}\nsnowflake 192.0.2.3:80 {} url=https://example.com/", obfs4_line(), FINGERPRINT);
+        let imported = parse(&text).unwrap();
+        assert_eq!(imported.missing_transports(&config), vec!["obfs4".to_string(), "snowflake".to_string()]);
+
+        config.tor.bridges.transports.insert("obfs4".to_string(), "/usr/bin/lyrebird".to_string());
+        assert_eq!(imported.missing_transports(&config), vec!["snowflake".to_string()]);
+
+        assert_eq!(imported.merge_into(&mut config), 2);
+        assert!(config.tor.bridges.enabled);
+        assert_eq!(parse(&obfs4_line()).unwrap().merge_into(&mut config), 0);
+        assert_eq!(config.tor.bridges.lines.len(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+// Lancement d'un navigateur deja configure pour passer par IronCloak.
+// Firefox : profil temporaire dont le user.js force le proxy SOCKS5 et le DNS distant.
+// Chromium/Chrome/Edge : profil temporaire et options de ligne de commande equivalentes.
+// Le profil est un repertoire neuf a chaque lancement (nom aleatoire, 0700 sous Unix) : un
+// autre utilisateur ne peut ni le preparer a l'avance ni lire l'historique et les cookies. Il
+// est supprime a la fermeture du navigateur (s'IronCloak tourne encore).
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+/// Famille de navigateur (determine la maniere de configurer le proxy)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Firefox,
+    Chromium,
+}
+
+/// Navigateurs recherches, par ordre de preference (nom de l'executable dans le PATH)
+#[cfg(not(windows))]
+const CANDIDATES: &[(&str, Kind)] = &[
+    ("firefox", Kind::Firefox),
+    ("firefox-esr", Kind::Firefox),
+    ("librewolf", Kind::Firefox),
+    ("chromium", Kind::Chromium),
+    ("chromium-browser", Kind::Chromium),
+    ("google-chrome", Kind::Chromium),
+    ("brave-browser", Kind::Chromium),
+];
+
+/// Navigateurs recherches, par ordre de preference (chemin relatif aux dossiers d'installation)
+#[cfg(windows)]
+const CANDIDATES: &[(&str, Kind)] = &[
+    ("Mozilla Firefox\\firefox.exe", Kind::Firefox),
+    ("Google\\Chrome\\Application\\chrome.exe", Kind::Chromium),
+    ("Microsoft\\Edge\\Application\\msedge.exe", Kind::Chromium),
+];
+
+/// Lance le premier navigateur trouve avec le proxy SOCKS5 `host:port`.
+/// Retourne le chemin de l'executable lance.
+pub fn launch(host: &str, port: u16) -> Result<PathBuf> {
+    let (exe, kind) = find_browser().context(crate::t!("browser.not_found"))?;
+    // Creation exclusive : un chemin deja present n'est jamais reutilise
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(match kind {
+        Kind::Firefox => "ironcloak-firefox-",
+        Kind::Chromium => "ironcloak-chromium-",
+    });
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o700));
+    }
+    let profile_dir = builder
+        .tempdir()
+        .with_context(|| crate::t!("browser.profile_failed", path = std::env::temp_dir().display()))?;
+    let profile = profile_dir.path().to_path_buf();
+
+    let mut command = Command::new(&exe);
+    match kind {
+        Kind::Firefox => {
+            write_firefox_prefs(&profile, host, port)
+                .with_context(|| crate::t!("browser.profile_failed", path = profile.display()))?;
+            command.arg("-no-remote").arg("-profile").arg(&profile);
+        }
+        Kind::Chromium => {
+            command
+                .arg(format!("--user-data-dir={}", profile.display()))
+                .arg(format!("--proxy-server=socks5://{}:{}", host, port))
+                // Aucune resolution DNS locale : tout passe par le proxy
+                .arg(format!("--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE {}", host))
+                .arg("--no-first-run")
+                .arg("--no-default-browser-check");
+        }
+    }
+    command.arg("https://check.torproject.org/");
+    crate::killswitch::confine_child(&mut command);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| crate::t!("browser.launch_failed", browser = exe.display()))?;
+    tracing::info!("{}", crate::tl!("browser.launched", browser = exe.display(), port = port));
+
+    // Profil supprime a la fermeture du navigateur
+    std::thread::spawn(move || {
+        let _ = child.wait();
+        if let Err(e) = profile_dir.close() {
+            tracing::warn!("{}", crate::tl!("browser.profile_cleanup_failed", path = profile.display(), error = e));
+        }
+    });
+    Ok(exe)
+}
+
+/// Recherche un navigateur installe
+#[cfg(not(windows))]
+fn find_browser() -> Option<(PathBuf, Kind)> {
+    let path = std::env::var_os("PATH")?;
+    let dirs: Vec<PathBuf> = std::env::split_paths(&path).collect();
+    CANDIDATES.iter().find_map(|(name, kind)| {
+        dirs.iter()
+            .map(|dir| dir.join(name))
+            .find(|exe| exe.is_file())
+            .map(|exe| (exe, *kind))
+    })
+}
+
+/// Recherche un navigateur installe
+#[cfg(windows)]
+fn find_browser() -> Option<(PathBuf, Kind)> {
+    let roots: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from)
+        .collect();
+    CANDIDATES.iter().find_map(|(rel, kind)| {
+        roots.iter()
+            .map(|root| root.join(rel))
+            .find(|exe| exe.is_file())
+            .map(|exe| (exe, *kind))
+    })
+}
+
+/// Ecrit le user.js du profil Firefox : proxy SOCKS5, DNS distant, pas de fuite WebRTC/DoH
+fn write_firefox_prefs(profile: &Path, host: &str, port: u16) -> std::io::Result<()> {
+    let prefs = format!(
+        "user_pref(\"network.proxy.type\", 1);\n\
+         user_pref(\"network.proxy.socks\", \"{host}\");\n\
+         user_pref(\"network.proxy.socks_port\", {port});\n\
+         user_pref(\"network.proxy.socks_version\", 5);\n\
+         user_pref(\"network.proxy.socks_remote_dns\", true);\n\
+         user_pref(\"network.proxy.no_proxies_on\", \"\");\n\
+         user_pref(\"network.trr.mode\", 5);\n\
+         user_pref(\"media.peerconnection.enabled\", false);\n\
+         user_pref(\"browser.shell.checkDefaultBrowser\", false);\n"
+    );
+    std::fs::write(profile.join("user.js"), prefs)
+}
@@ -0,0 +1,96 @@
+// Tampons de copie du relais SOCKS ↔ Tor ([proxy.buffers]).
+// Chaque connexion relayee utilise deux tampons (un par sens) ; avec des centaines de
+// connexions simultanees, leur allocation a chaque ouverture se voit dans les profils. Les
+// tampons liberes sont conserves dans une reserve commune (buffers.pool_size au plus) et repris
+// par les connexions suivantes.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config::BufferConfig;
+
+/// Taille minimale d'un tampon
+const MIN_BUFFER_SIZE: usize = 1024;
+
+/// Reserve de tampons de taille fixe, partagee par les connexions d'un port d'ecoute
+#[derive(Debug)]
+pub struct BufferPool {
+    free: Mutex<Vec<Box<[u8]>>>,
+    buffer_size: usize,
+    pool_size: usize,
+}
+
+impl BufferPool {
+    pub fn new(config: &BufferConfig) -> Arc<Self> {
+        Arc::new(Self {
+            free: Mutex::new(Vec::new()),
+            buffer_size: config.buffer_size.max(MIN_BUFFER_SIZE),
+            pool_size: config.pool_size,
+        })
+    }
+
+    /// Tampon de la reserve, ou neuf si elle est vide ; rendu a la reserve a sa liberation
+    pub fn get(self: &Arc<Self>) -> PooledBuffer {
+        let reused = self.free.lock().unwrap().pop();
+        let data = reused.unwrap_or_else(|| vec![0u8; self.buffer_size].into_boxed_slice());
+        PooledBuffer { data: Some(data), pool: Arc::clone(self) }
+    }
+
+    fn put(&self, data: Box<[u8]>) {
+        let mut free = self.free.lock().unwrap();
+        if free.len() < self.pool_size {
+            free.push(data);
+        }
+    }
+}
+
+/// Tampon emprunte a la reserve
+pub struct PooledBuffer {
+    data: Option<Box<[u8]>>,
+    pool: Arc<BufferPool>,
+}
+
+impl Deref for PooledBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.data.as_deref().unwrap_or_default()
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.data.as_deref_mut().unwrap_or_default()
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            self.pool.put(data);
+        }
+    }
+}
+
+/// Copie `reader` dans `writer` jusqu'a la fin du flux avec un tampon de la reserve, comme
+/// tokio::io::copy ; chaque bloc est envoye aussitot (flush). Retourne le nombre d'octets copies.
+pub async fn copy<R, W>(reader: &mut R, writer: &mut W, pool: &Arc<BufferPool>) -> std::io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut buf = pool.get();
+    let mut copied = 0;
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            writer.flush().await?;
+            return Ok(copied);
+        }
+        writer.write_all(&buf[..n]).await?;
+        writer.flush().await?;
+        copied += n as u64;
+    }
+}
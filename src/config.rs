@@ -3,7 +3,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 /// Configuration racine de l'application
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -14,6 +15,40 @@ pub struct IronCloakConfig {
     pub tor: TorConfig,
     #[serde(default)]
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default)]
+    pub kill_switch: KillSwitchConfig,
+    #[serde(default)]
+    pub pac: PacConfig,
+    #[serde(default)]
+    pub gui: GuiConfig,
+    #[serde(default)]
+    pub updates: UpdatesConfig,
+    #[serde(default)]
+    pub otel: OtelConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub rules: RulesConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub quota: QuotaConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    /// Ports d'ecoute SOCKS supplementaires, chacun avec son propre client Tor ([[listeners]])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listeners: Vec<ListenerConfig>,
+    /// Services onion heberges (tableaux [[onion_services]])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub onion_services: Vec<OnionServiceConfig>,
 }
 
 /// Configuration du proxy SOCKS5
@@ -25,13 +60,590 @@ pub struct ProxyConfig {
     pub listen_port: u16,
     #[serde(default = "default_true")]
     pub dns_reject_ip: bool,
+    /// Famille d'adresses IP demandee aux relais de sortie
+    #[serde(default)]
+    pub ip_family: IpFamily,
+    /// La mise en pause ferme aussi les connexions actives (sinon elles continuent)
+    #[serde(default)]
+    pub pause_closes_connections: bool,
+    /// Circuits distincts par hote de destination (IsolateDestAddr de Tor)
+    #[serde(default)]
+    pub isolate_dest_addr: bool,
+    /// Circuits distincts par port de destination (IsolateDestPort de Tor)
+    #[serde(default)]
+    pub isolate_dest_port: bool,
+    /// Circuits distincts par adresse IP du client SOCKS (IsolateClientAddr de Tor)
+    #[serde(default)]
+    pub isolate_client_addr: bool,
+    /// Connexions precedees d'un en-tete PROXY v1/v2 (derriere HAProxy, sslh...)
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    /// Reponses SOCKS avec BND.ADDR/BND.PORT a 0.0.0.0:0 au lieu de l'adresse locale de la
+    /// connexion (n'indique pas au client l'adresse d'ecoute)
+    #[serde(default)]
+    pub zero_bind_addr: bool,
+    /// Autorise l'ecoute hors de la boucle locale sans authentification ni liste de clients :
+    /// proxy ouvert a tout le reseau local (refuse au demarrage sinon)
+    #[serde(default)]
+    pub i_know_what_i_am_doing: bool,
+    /// Reglages des sockets TCP des clients SOCKS ([proxy.tcp])
+    #[serde(default)]
+    pub tcp: TcpConfig,
+    /// Reglages de l'ouverture des flux Tor ([proxy.stream])
+    #[serde(default)]
+    pub stream: StreamConfig,
+    /// Nouvelles tentatives sur un autre circuit apres un echec ([proxy.retry])
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Tampons de copie du relais ([proxy.buffers])
+    #[serde(default)]
+    pub buffers: BufferConfig,
+    /// Connexions acceptees pendant l'amorcage ([proxy.bootstrap_queue])
+    #[serde(default)]
+    pub bootstrap_queue: BootstrapQueueConfig,
+    /// Requetes recues pendant la reconstruction des circuits ([proxy.rebuild])
+    #[serde(default)]
+    pub rebuild: RebuildConfig,
+}
+
+/// Regles appliquees aux requetes SOCKS avant la connexion Tor ([rules])
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RulesConfig {
+    /// Correspondances locales d'hotes ([rules.hosts]) : nom demande → IP fixe ou autre nom,
+    /// comme un fichier hosts limite au trafic du proxy
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, String>,
+}
+
+/// Options des sockets TCP du serveur SOCKS (ecoute et connexions acceptees)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TcpConfig {
+    /// Sondes keepalive TCP sur les connexions clientes : un relais inactif n'est pas oublie
+    /// par les equipements NAT, et un client disparu est detecte
+    #[serde(default)]
+    pub keepalive: bool,
+    /// Inactivite avant la premiere sonde, en secondes
+    #[serde(default = "default_keepalive_time_secs")]
+    pub keepalive_time_secs: u64,
+    /// Intervalle entre deux sondes sans reponse, en secondes
+    #[serde(default = "default_keepalive_interval_secs")]
+    pub keepalive_interval_secs: u64,
+    /// Desactive l'algorithme de Nagle (TCP_NODELAY) : petits paquets envoyes sans attendre
+    #[serde(default)]
+    pub nodelay: bool,
+    /// Taille de la file des connexions en attente d'acceptation (listen backlog)
+    #[serde(default = "default_listen_backlog")]
+    pub backlog: u32,
+    /// Delai pour terminer le handshake SOCKS, en secondes (0 : aucun). Au-dela, la connexion
+    /// est fermee : un client qui ne finit jamais sa requete n'occupe pas une tache indefiniment
+    #[serde(default = "default_handshake_timeout_secs")]
+    pub handshake_timeout_secs: u64,
+    /// Duree maximale, en secondes, d'une ecriture bloquee dans un sens du relais (le client ou
+    /// le flux Tor ne lit plus) ; au-dela, la connexion est fermee (0 : aucune)
+    #[serde(default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u64,
+}
+
+/// Nouvelles tentatives de connexion sur un circuit neuf, avant de signaler l'echec au client
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetryConfig {
+    /// Nombre de nouvelles tentatives apres un echec reessayable (0 : aucune)
+    #[serde(default = "default_retry_attempts")]
+    pub attempts: u8,
+    /// Echecs reessayes
+    #[serde(default)]
+    pub on: RetryOn,
+}
+
+/// Requetes SOCKS recues pendant la reconstruction des circuits : apres une nouvelle identite
+/// (jusqu'au premier circuit neuf) ou pendant une coupure du reseau
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RebuildConfig {
+    #[serde(default)]
+    pub policy: RebuildPolicy,
+    /// Attente maximale d'une requete retenue (policy = "hold"), en secondes
+    #[serde(default = "default_rebuild_hold_secs")]
+    pub hold_secs: u64,
+}
+
+/// Traitement des requetes pendant la reconstruction des circuits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildPolicy {
+    /// Aucun traitement particulier : la requete court sa chance dans le delai habituel
+    Off,
+    /// Requete retenue jusqu'a ce que les circuits soient prets (hold_secs au plus)
+    #[default]
+    Hold,
+    /// Requete refusee aussitot avec un code SOCKS temporaire, que le client peut reessayer
+    FailFast,
+}
+
+/// Echecs de connexion pour lesquels un autre circuit est essaye
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryOn {
+    /// Circuit effondre, delai du reseau Tor depasse, flux ferme par le circuit
+    Circuit,
+    /// Idem, plus les refus du relais de sortie (politique de sortie, delai de connexion)
+    #[default]
+    CircuitAndExit,
 }
 
-/// Configuration du client Tor (repertoire de donnees)
+/// File des connexions recues pendant l'amorcage : le port principal est ouvert avant la fin de
+/// l'amorcage et les requetes SOCKS attendent le client Tor
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BootstrapQueueConfig {
+    /// Connexions en attente au plus, les suivantes sont refusees (0 : port ouvert seulement
+    /// une fois l'amorcage termine)
+    #[serde(default = "default_bootstrap_queue_size")]
+    pub size: usize,
+    /// Attente maximale d'une connexion, en secondes
+    #[serde(default = "default_bootstrap_queue_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Tampons de copie du relais, reutilises d'une connexion a l'autre
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BufferConfig {
+    /// Taille d'un tampon, en octets (deux par connexion relayee)
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+    /// Tampons liberes conserves pour les connexions suivantes (0 : allocation a chaque
+    /// connexion)
+    #[serde(default = "default_buffer_pool_size")]
+    pub pool_size: usize,
+}
+
+/// Ouverture des flux Tor (preferences transmises a arti pour chaque requete SOCKS)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamConfig {
+    /// Donnees optimistes : le client SOCKS recoit sa reponse et peut envoyer ses donnees sans
+    /// attendre la confirmation du relais de sortie (un aller-retour de moins, erreurs de
+    /// connexion signalees par la fermeture du flux)
+    #[serde(default)]
+    pub optimistic_data: bool,
+    /// Tentatives de connexion lancees en parallele, les supplementaires sur des circuits
+    /// neufs ; la premiere qui aboutit est gardee (1 : une seule tentative)
+    #[serde(default = "default_parallel_connects")]
+    pub parallel_connects: u8,
+}
+
+/// Famille d'adresses IP des flux (cibles resolues par le relais de sortie)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpFamily {
+    /// Comportement par defaut d'arti (IPv4 et IPv6, IPv4 prefere)
+    #[default]
+    Default,
+    Ipv4Only,
+    Ipv4Preferred,
+    Ipv6Preferred,
+    /// Exige IPv6 : les cibles uniquement IPv4 echouent
+    Ipv6Only,
+}
+
+/// Configuration du client Tor (repertoire de donnees, securite des chemins)
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TorConfig {
     #[serde(default = "default_data_dir")]
     pub data_dir: String,
+    /// Remplissage des canaux contre l'analyse de trafic : "normal", "reduced", "off"
+    #[serde(default)]
+    pub padding: PaddingMode,
+    /// Etat d'arti (gardes, cles des services onion) chiffre au repos avec une phrase secrete
+    /// demandee au demarrage
+    #[serde(default)]
+    pub encrypt_state: bool,
+    #[serde(default)]
+    pub security: TorSecurityConfig,
+    #[serde(default)]
+    pub timeouts: TorTimeoutsConfig,
+    #[serde(default)]
+    pub firewall: TorFirewallConfig,
+    #[serde(default)]
+    pub bridges: TorBridgesConfig,
+    /// Cles privees d'autorisation client pour les services onion restreints :
+    /// adresse .onion → "descriptor:x25519:CLE_PRIVEE_BASE32" (section [tor.onion_auth])
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub onion_auth: BTreeMap<String, String>,
+    /// Dossier de fichiers *.auth_private au format C Tor (ClientOnionAuthDir)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub onion_auth_dir: Option<String>,
+}
+
+/// Ponts (section [tor.bridges]) : entree dans le reseau Tor par des relais non publies,
+/// pour les reseaux qui bloquent les relais connus
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TorBridgesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Lignes de pont au format torrc, sans le mot-cle "Bridge"
+    /// ("obfs4 192.0.2.1:443 EMPREINTE cert=... iat-mode=0")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<String>,
+    /// Transports enfichables : nom → commande du client (executable puis arguments),
+    /// comme ClientTransportPlugin ("obfs4" = "/usr/bin/lyrebird")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transports: BTreeMap<String, String>,
+    /// Demande de ponts a BridgeDB ([tor.bridges.moat])
+    #[serde(default)]
+    pub moat: MoatConfig,
+}
+
+/// API "moat" de BridgeDB, interrogee hors Tor (bouton "Obtenir des ponts")
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MoatConfig {
+    /// Adresse de l'API (HTTPS)
+    #[serde(default = "default_moat_url")]
+    pub url: String,
+    /// Domaine frontal (domain fronting) : seul domaine visible du reseau, l'hote de `url`
+    /// (servi par le meme CDN) passant dans l'en-tete Host chiffre. Obligatoire : sans lui,
+    /// BridgeDB n'est pas contacte.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front: Option<String>,
+    /// Transport des ponts demandes
+    #[serde(default = "default_moat_transport")]
+    pub transport: String,
+}
+
+/// Pare-feu restrictif (section [tor.firewall]) : equivalent de FascistFirewall,
+/// limite les connexions sortantes de Tor aux ports autorises par le reseau local.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TorFirewallConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Ports joignables vers les relais (defaut : 80 et 443)
+    #[serde(default = "default_reachable_ports")]
+    pub reachable_ports: Vec<u16>,
+}
+
+/// Delais de construction des circuits et d'ouverture des flux (section [tor.timeouts]).
+/// Les valeurs absentes laissent arti utiliser ses valeurs par defaut.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TorTimeoutsConfig {
+    /// Delai plancher de construction d'un circuit, en secondes
+    /// (par defaut arti l'estime a partir des temps de construction observes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_build_secs: Option<u64>,
+    /// Delai maximal pour obtenir un circuit utilisable, en secondes (defaut arti : 60)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_request_secs: Option<u64>,
+    /// Delai d'ouverture d'un flux sur un circuit deja construit, en secondes (defaut arti : 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_connect_secs: Option<u64>,
+    /// Delai total accorde a une requete SOCKS pour obtenir son flux Tor, en secondes
+    #[serde(default = "default_stream_attach_secs")]
+    pub stream_attach_secs: u64,
+}
+
+/// Durcissement des chemins Tor (section [tor.security])
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TorSecurityConfig {
+    /// Protection vanguards des circuits vers les services onion : "off", "lite", "full"
+    #[serde(default)]
+    pub vanguards: VanguardsMode,
+}
+
+/// Niveau de protection vanguards (voir la proposition Tor 292)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VanguardsMode {
+    /// Desactive : chemins standards
+    Off,
+    /// Vanguards-lite (defaut d'arti) : gardes de deuxieme niveau a longue duree de vie
+    #[default]
+    Lite,
+    /// Vanguards complets : gardes de deuxieme et troisieme niveaux
+    Full,
+}
+
+/// Niveau de remplissage (padding) des canaux vers les relais
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaddingMode {
+    /// Defaut d'arti : cellules de remplissage selon le consensus
+    #[default]
+    Normal,
+    /// Remplissage reduit (connexions mobiles ou facturees au volume), moins de protection
+    Reduced,
+    /// Aucun remplissage
+    Off,
+}
+
+/// Port d'ecoute SOCKS supplementaire. Ses flux ne partagent jamais de circuit avec ceux des
+/// autres ports : client Tor isole (meme etat) ou, avec data_dir, client entierement distinct
+/// (etat et gardes propres)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ListenerConfig {
+    /// Adresse d'ecoute (defaut : proxy.listen_addr)
+    #[serde(default)]
+    pub listen_addr: Option<String>,
+    pub port: u16,
+    /// Repertoire de donnees Tor propre a ce port
+    #[serde(default)]
+    pub data_dir: Option<String>,
+    /// Connexions precedees d'un en-tete PROXY v1/v2
+    #[serde(default)]
+    pub proxy_protocol: bool,
+}
+
+/// Service onion heberge : relaie un port virtuel .onion vers une cible locale
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OnionServiceConfig {
+    /// Surnom du service, identifie ses cles dans le keystore d'arti
+    pub nickname: String,
+    /// Port virtuel expose sur l'adresse .onion
+    #[serde(default = "default_onion_port")]
+    pub port: u16,
+    /// Cible locale des flux entrants (ex : "127.0.0.1:8080")
+    pub target: String,
+    /// Clients autorises (decouverte restreinte) : surnom → cle publique
+    /// "descriptor:x25519:BASE32". Si vide, le service est decouvrable par tous.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub authorized_clients: BTreeMap<String, String>,
+}
+
+/// Socket de controle local (section [control])
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ControlConfig {
+    /// Active le socket de controle (desactive par defaut)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Chemin du socket Unix
+    #[serde(default = "default_control_socket")]
+    pub socket_path: String,
+    /// Nom du tube nomme (Windows)
+    #[serde(default = "default_control_pipe")]
+    pub pipe_name: String,
+    /// Authentification par cookie : secret aleatoire ecrit a chaque demarrage dans un fichier
+    /// lisible du seul proprietaire (sans jeton ni cookie, aucune authentification)
+    #[serde(default = "default_true")]
+    pub cookie_auth: bool,
+    /// Fichier cookie (defaut : chemin du socket suivi de ".cookie")
+    #[serde(default)]
+    pub cookie_path: Option<String>,
+    /// Jeton fixe attendu des clients, prioritaire sur le cookie (scripts, supervision)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Service D-Bus org.ironcloak.Proxy1 sur le bus de session (Linux), independant du socket
+    #[serde(default)]
+    pub dbus: bool,
+}
+
+/// Kill switch systeme : bloque tout trafic sortant hors IronCloak tant qu'il est actif
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KillSwitchConfig {
+    /// Installe les regles de pare-feu au demarrage (desactive par defaut, Linux/nftables)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Groupe dedie, groupe effectif du processus IronCloak : seuls ses sockets sortent
+    #[serde(default = "default_kill_switch_group")]
+    pub group: String,
+}
+
+/// Serveur PAC (proxy auto-config) local pour configurer les navigateurs
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PacConfig {
+    /// Sert le fichier PAC sur 127.0.0.1 (desactive par defaut)
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_pac_port")]
+    pub port: u16,
+    /// Motifs d'hotes (shExpMatch) accedes directement, hors Tor
+    #[serde(default)]
+    pub bypass: Vec<String>,
+}
+
+/// Preferences de l'interface graphique
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GuiConfig {
+    #[serde(default)]
+    pub theme: GuiTheme,
+    /// Facteur d'echelle de l'interface (1.0 = taille native, utile en HiDPI)
+    #[serde(default = "default_gui_scale")]
+    pub scale: f32,
+    /// Langue de la fenetre et de la zone de notification (defaut : celle des journaux)
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Empreinte Argon2 (format PHC) du mot de passe des reglages : la fenetre s'ouvre
+    /// verrouillee, statut visible mais modifications refusees (kiosque, poste familial)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_password: Option<String>,
+    /// Action d'un simple clic gauche sur l'icone de notification
+    #[serde(default)]
+    pub tray_click_action: TrayClickAction,
+}
+
+/// Action d'un simple clic gauche sur l'icone de notification ; le double-clic ouvre toujours
+/// la fenetre, et un clic aussi en cas d'erreur (pour en voir le detail)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayClickAction {
+    /// Ouvre la fenetre de configuration
+    #[default]
+    OpenWindow,
+    TogglePause,
+    NewIdentity,
+}
+
+/// Theme de la fenetre : clair, sombre ou celui du systeme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuiTheme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+/// Verification des nouvelles versions (toujours a travers Tor)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UpdatesConfig {
+    /// Verification au demarrage puis periodique
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Intervalle entre deux verifications (heures)
+    #[serde(default = "default_update_interval_hours")]
+    pub interval_hours: u64,
+    /// API des releases (format GitHub : champ "tag_name")
+    #[serde(default = "default_release_url")]
+    pub release_url: String,
+}
+
+/// Indicateur de sante des circuits : mesure periodique de la latence a travers Tor ([health])
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HealthConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Intervalle entre deux mesures (secondes)
+    #[serde(default = "default_health_interval_secs")]
+    pub interval_secs: u64,
+    /// Latence a partir de laquelle l'indicateur passe au jaune (millisecondes)
+    #[serde(default = "default_health_slow_ms")]
+    pub slow_ms: u64,
+    /// Latence a partir de laquelle l'indicateur passe au rouge (millisecondes)
+    #[serde(default = "default_health_bad_ms")]
+    pub bad_ms: u64,
+}
+
+/// Detection de la perte du reseau ([network]) : client Tor mis en sommeil pendant la coupure,
+/// amorcage relance automatiquement au retour
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NetworkConfig {
+    #[serde(default = "default_true")]
+    pub offline_detection: bool,
+    /// Intervalle entre deux verifications de la table de routage (secondes)
+    #[serde(default = "default_network_check_secs")]
+    pub check_secs: u64,
+    /// Mesures de sante consecutives en echec (circuits inutilisables) valant perte du reseau ;
+    /// 0 pour s'en tenir a la table de routage
+    #[serde(default = "default_offline_after_failures")]
+    pub offline_after_failures: u32,
+    /// Apres un echec de l'amorcage, recherche d'un portail captif par une requete HTTP en clair
+    /// (hors Tor) vers la page de detection de Firefox
+    #[serde(default = "default_true")]
+    pub captive_portal_check: bool,
+}
+
+/// Quota de donnees relayees (les deux sens) : pause automatique au depassement ([quota])
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct QuotaConfig {
+    /// Quota quotidien en Mio (remis a zero a minuit, heure locale)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_mb: Option<u64>,
+    /// Quota mensuel en Mio (remis a zero le premier du mois)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_mb: Option<u64>,
+}
+
+/// Durcissement memoire des secrets ([security])
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SecurityConfig {
+    /// Secrets verrouilles en memoire et effaces apres usage, vidages memoire desactives
+    #[serde(default)]
+    pub harden_memory: bool,
+}
+
+/// Bac a sable Linux du backend, Landlock et seccomp ([sandbox]) ; desactivable pour le
+/// debogage
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SandboxConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// Masquage des hotes de destination dans les traces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Redaction {
+    /// Hotes affiches en entier
+    #[default]
+    Off,
+    /// Empreinte courte de l'hote, stable pendant l'execution
+    Hash,
+    /// Sous-domaines masques (*.example.com)
+    Truncate,
+}
+
+/// Journal d'audit des connexions (destination, duree, octets), separe des traces
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AuditConfig {
+    #[serde(default)]
+    pub mode: AuditMode,
+    #[serde(default)]
+    pub format: AuditFormat,
+    /// Fichier du journal (defaut : audit.jsonl ou audit.csv dans le repertoire courant)
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Historique en memoire des connexions fermees (onglet "Historique") ; destinations masquees
+/// comme dans les traces (logging.redact_destinations). La trace sur disque est le journal
+/// d'audit ([audit]), avec son propre niveau de confidentialite.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HistoryConfig {
+    /// Nombre de connexions conservees (0 : pas d'historique)
+    #[serde(default = "default_history_size")]
+    pub size: usize,
+}
+
+/// Niveau de confidentialite du journal d'audit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditMode {
+    /// Aucun journal d'audit
+    #[default]
+    Off,
+    /// Hote et port de destination
+    Full,
+    /// Domaine enregistrable seul (www.example.com → example.com), sans le port
+    Domain,
+    /// Empreinte salee de l'hote, sans le port
+    Hashed,
+}
+
+/// Format des lignes du journal d'audit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFormat {
+    /// Un objet JSON par ligne
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+/// Export OpenTelemetry (binaire compile avec la fonctionnalite "otel")
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OtelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Adresse du collecteur OTLP/HTTP (sans /v1/traces ni /v1/metrics)
+    #[serde(default = "default_otel_endpoint")]
+    pub endpoint: String,
+    /// Nom du service dans les traces (service.name)
+    #[serde(default = "default_otel_service_name")]
+    pub service_name: String,
 }
 
 /// Configuration du logging (niveau, repertoire, langue)
@@ -41,9 +653,44 @@ pub struct LoggingConfig {
     pub level: String,
     #[serde(default = "default_log_dir")]
     pub log_dir: String,
-    /// Langue des messages de trace : "en", "fr", "es" (defaut : "en")
+    /// Langue des journaux, code d'une langue de i18n::languages() (defaut : "en")
     #[serde(default)]
     pub language: Option<String>,
+    /// Taille maximale d'un fichier de journal (Mo) avant de passer au suivant (defaut : illimitee)
+    #[serde(default)]
+    pub max_file_mb: Option<u64>,
+    /// Compression gzip des fichiers de journal termines
+    #[serde(default)]
+    pub compress: bool,
+    /// Duree de conservation des journaux (jours, defaut : illimitee)
+    #[serde(default)]
+    pub keep_days: Option<u64>,
+    /// Taille totale maximale des journaux (Mo), les plus anciens supprimes en premier
+    #[serde(default)]
+    pub max_total_mb: Option<u64>,
+    /// Ecriture des fichiers de journal dans log_dir
+    #[serde(default = "default_true")]
+    pub files: bool,
+    /// Envoi des traces au journal systeme (syslog/journald, Linux)
+    #[serde(default)]
+    pub syslog: bool,
+    /// Envoi des avertissements et erreurs au journal d'evenements (Windows)
+    #[serde(default)]
+    pub event_log: bool,
+    /// Masquage des destinations dans les traces (les conn_id restent pour la correlation)
+    #[serde(default)]
+    pub redact_destinations: Redaction,
+    /// Niveaux par module (cible tracing → niveau), ex : arti_client = "warn",
+    /// "ironcloak::socks" = "debug" ; prioritaires sur `level`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub levels: BTreeMap<String, String>,
+}
+
+/// Adresse d'ecoute limitee a la machine locale (127.0.0.0/8, ::1, "localhost")
+fn is_loopback_addr(addr: &str) -> bool {
+    let addr = addr.trim_start_matches('[').trim_end_matches(']');
+    addr.eq_ignore_ascii_case("localhost")
+        || addr.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
 }
 
 fn default_listen_addr() -> String {
@@ -54,6 +701,54 @@ fn default_listen_port() -> u16 {
     9150
 }
 
+fn default_keepalive_time_secs() -> u64 {
+    60
+}
+
+fn default_keepalive_interval_secs() -> u64 {
+    15
+}
+
+fn default_listen_backlog() -> u32 {
+    1024
+}
+
+fn default_handshake_timeout_secs() -> u64 {
+    30
+}
+
+fn default_stall_timeout_secs() -> u64 {
+    120
+}
+
+fn default_rebuild_hold_secs() -> u64 {
+    15
+}
+
+fn default_bootstrap_queue_size() -> usize {
+    64
+}
+
+fn default_bootstrap_queue_timeout_secs() -> u64 {
+    120
+}
+
+fn default_buffer_size() -> usize {
+    16 * 1024
+}
+
+fn default_buffer_pool_size() -> usize {
+    256
+}
+
+fn default_retry_attempts() -> u8 {
+    1
+}
+
+fn default_parallel_connects() -> u8 {
+    1
+}
+
 fn default_true() -> bool {
     true
 }
@@ -62,6 +757,86 @@ fn default_data_dir() -> String {
     "./data/arti".to_string()
 }
 
+fn default_reachable_ports() -> Vec<u16> {
+    vec![80, 443]
+}
+
+fn default_stream_attach_secs() -> u64 {
+    60
+}
+
+fn default_onion_port() -> u16 {
+    80
+}
+
+fn default_control_socket() -> String {
+    "./ironcloak.sock".to_string()
+}
+
+fn default_control_pipe() -> String {
+    r"\\.\pipe\ironcloak".to_string()
+}
+
+fn default_kill_switch_group() -> String {
+    "ironcloak".to_string()
+}
+
+fn default_pac_port() -> u16 {
+    9151
+}
+
+fn default_gui_scale() -> f32 {
+    1.0
+}
+
+fn default_history_size() -> usize {
+    500
+}
+
+fn default_update_interval_hours() -> u64 {
+    24
+}
+
+fn default_moat_url() -> String {
+    "https://bridges.torproject.org/moat".to_string()
+}
+
+fn default_moat_transport() -> String {
+    "obfs4".to_string()
+}
+
+fn default_health_interval_secs() -> u64 {
+    120
+}
+
+fn default_health_slow_ms() -> u64 {
+    1500
+}
+
+fn default_health_bad_ms() -> u64 {
+    4000
+}
+
+fn default_network_check_secs() -> u64 {
+    5
+}
+
+fn default_offline_after_failures() -> u32 {
+    3
+}
+
+fn default_release_url() -> String {
+    "https://api.github.com/repos/Le-Stef/IronCloak/releases/latest".to_string()
+}
+
+fn default_otel_endpoint() -> String {
+    "http://127.0.0.1:4318".to_string()
+}
+
+fn default_otel_service_name() -> String {
+    "ironcloak".to_string()
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -76,6 +851,79 @@ impl Default for ProxyConfig {
             listen_addr: default_listen_addr(),
             listen_port: default_listen_port(),
             dns_reject_ip: default_true(),
+            ip_family: IpFamily::default(),
+            pause_closes_connections: false,
+            isolate_dest_addr: false,
+            isolate_dest_port: false,
+            isolate_client_addr: false,
+            proxy_protocol: false,
+            zero_bind_addr: false,
+            i_know_what_i_am_doing: false,
+            tcp: TcpConfig::default(),
+            stream: StreamConfig::default(),
+            retry: RetryConfig::default(),
+            buffers: BufferConfig::default(),
+            bootstrap_queue: BootstrapQueueConfig::default(),
+            rebuild: RebuildConfig::default(),
+        }
+    }
+}
+
+impl Default for RebuildConfig {
+    fn default() -> Self {
+        Self {
+            policy: RebuildPolicy::default(),
+            hold_secs: default_rebuild_hold_secs(),
+        }
+    }
+}
+
+impl Default for BootstrapQueueConfig {
+    fn default() -> Self {
+        Self {
+            size: default_bootstrap_queue_size(),
+            timeout_secs: default_bootstrap_queue_timeout_secs(),
+        }
+    }
+}
+
+impl Default for BufferConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: default_buffer_size(),
+            pool_size: default_buffer_pool_size(),
+        }
+    }
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            optimistic_data: false,
+            parallel_connects: default_parallel_connects(),
+        }
+    }
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        Self {
+            keepalive: false,
+            keepalive_time_secs: default_keepalive_time_secs(),
+            keepalive_interval_secs: default_keepalive_interval_secs(),
+            nodelay: false,
+            backlog: default_listen_backlog(),
+            handshake_timeout_secs: default_handshake_timeout_secs(),
+            stall_timeout_secs: default_stall_timeout_secs(),
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: default_retry_attempts(),
+            on: RetryOn::default(),
         }
     }
 }
@@ -84,6 +932,54 @@ impl Default for TorConfig {
     fn default() -> Self {
         Self {
             data_dir: default_data_dir(),
+            padding: PaddingMode::default(),
+            encrypt_state: false,
+            security: TorSecurityConfig::default(),
+            timeouts: TorTimeoutsConfig::default(),
+            firewall: TorFirewallConfig::default(),
+            bridges: TorBridgesConfig::default(),
+            onion_auth: BTreeMap::new(),
+            onion_auth_dir: None,
+        }
+    }
+}
+
+impl Default for MoatConfig {
+    fn default() -> Self {
+        Self {
+            url: default_moat_url(),
+            front: None,
+            transport: default_moat_transport(),
+        }
+    }
+}
+
+impl Default for TorFirewallConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reachable_ports: default_reachable_ports(),
+        }
+    }
+}
+
+impl Default for TorTimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            circuit_build_secs: None,
+            circuit_request_secs: None,
+            stream_connect_secs: None,
+            stream_attach_secs: default_stream_attach_secs(),
+        }
+    }
+}
+
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_otel_endpoint(),
+            service_name: default_otel_service_name(),
         }
     }
 }
@@ -94,10 +990,167 @@ impl Default for LoggingConfig {
             level: default_log_level(),
             log_dir: default_log_dir(),
             language: None,
+            max_file_mb: None,
+            compress: false,
+            keep_days: None,
+            max_total_mb: None,
+            files: true,
+            syslog: false,
+            event_log: false,
+            redact_destinations: Redaction::Off,
+            levels: BTreeMap::new(),
+        }
+    }
+}
+
+impl AuditMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditMode::Off => "off",
+            AuditMode::Full => "full",
+            AuditMode::Domain => "domain",
+            AuditMode::Hashed => "hashed",
+        }
+    }
+}
+
+impl ControlConfig {
+    /// Fichier cookie, a cote du socket a defaut de chemin configure ; sous Windows, dans le
+    /// repertoire de donnees de l'utilisateur (%LOCALAPPDATA%\IronCloak), pas dans le
+    /// repertoire de travail
+    pub fn cookie_path(&self) -> PathBuf {
+        if let Some(path) = &self.cookie_path {
+            return PathBuf::from(path);
+        }
+        #[cfg(windows)]
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            return PathBuf::from(local).join("IronCloak").join("control.cookie");
+        }
+        PathBuf::from(format!("{}.cookie", self.socket_path))
+    }
+}
+
+impl AuditConfig {
+    /// Fichier du journal d'audit, extension selon le format a defaut de chemin configure
+    pub fn path(&self) -> PathBuf {
+        match &self.path {
+            Some(path) => PathBuf::from(path),
+            None => match self.format {
+                AuditFormat::Jsonl => PathBuf::from("audit.jsonl"),
+                AuditFormat::Csv => PathBuf::from("audit.csv"),
+            },
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Directives du filtre de traces : niveau global puis niveaux par module
+    /// (ex : "info,arti_client=warn,ironcloak::socks=debug")
+    pub fn filter_directives(&self) -> String {
+        let mut directives = self.level.clone();
+        for (target, level) in &self.levels {
+            directives.push_str(&format!(",{}={}", target, level));
+        }
+        directives
+    }
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            socket_path: default_control_socket(),
+            pipe_name: default_control_pipe(),
+            cookie_auth: true,
+            cookie_path: None,
+            token: None,
+            dbus: false,
+        }
+    }
+}
+
+impl Default for KillSwitchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            group: default_kill_switch_group(),
+        }
+    }
+}
+
+impl Default for PacConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_pac_port(),
+            bypass: Vec::new(),
+        }
+    }
+}
+
+impl Default for GuiConfig {
+    fn default() -> Self {
+        Self {
+            theme: GuiTheme::default(),
+            scale: default_gui_scale(),
+            language: None,
+            lock_password: None,
+            tray_click_action: TrayClickAction::default(),
+        }
+    }
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            interval_hours: default_update_interval_hours(),
+            release_url: default_release_url(),
         }
     }
 }
 
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self { size: default_history_size() }
+    }
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self { enabled: default_true() }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            interval_secs: default_health_interval_secs(),
+            slow_ms: default_health_slow_ms(),
+            bad_ms: default_health_bad_ms(),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            offline_detection: default_true(),
+            check_secs: default_network_check_secs(),
+            offline_after_failures: default_offline_after_failures(),
+            captive_portal_check: default_true(),
+        }
+    }
+}
+
+impl QuotaConfig {
+    /// Au moins un quota defini
+    pub fn is_enabled(&self) -> bool {
+        self.daily_mb.is_some() || self.monthly_mb.is_some()
+    }
+}
+
 impl IronCloakConfig {
     /// Sauvegarde la configuration dans un fichier TOML.
     pub fn save(&self, path: &Path) -> Result<()> {
@@ -114,16 +1167,44 @@ impl IronCloakConfig {
         if path.exists() {
             let content = std::fs::read_to_string(path)
                 .with_context(|| {
-                    crate::i18n::get_with_args("config.read_failed", &[&path.display().to_string()])
+                    crate::t!("config.read_failed", path = path.display())
                 })?;
             let config: IronCloakConfig = toml::from_str(&content)
                 .with_context(|| crate::t!("config.parse_failed").to_string())?;
             Ok(config)
         } else {
-            tracing::warn!("{}", crate::t!("config.file_not_found", path.display()));
+            tracing::warn!("{}", crate::tl!("config.file_not_found", path = path.display()));
             Ok(Self::default())
         }
     }
+
+    /// Refuse une ecoute SOCKS hors de la boucle locale (proxy.listen_addr ou [[listeners]]) :
+    /// sans authentification ni liste de clients, n'importe quelle machine du reseau pourrait
+    /// s'en servir. `proxy.i_know_what_i_am_doing = true` leve le refus.
+    pub fn check_exposure(&self) -> Result<()> {
+        if self.proxy.i_know_what_i_am_doing {
+            return Ok(());
+        }
+        let addrs = std::iter::once(self.proxy.listen_addr.as_str())
+            .chain(self.listeners.iter().filter_map(|l| l.listen_addr.as_deref()));
+        for addr in addrs {
+            if !is_loopback_addr(addr) {
+                anyhow::bail!("{}", crate::t!("config.open_proxy_refused", addr = addr));
+            }
+        }
+        Ok(())
+    }
+
+    /// Langue des journaux (`logging.language`, anglais par defaut)
+    pub fn log_language(&self) -> &str {
+        self.logging.language.as_deref().unwrap_or("en")
+    }
+
+    /// Langue de l'interface (`gui.language`) ; a defaut celle des journaux, comme avant
+    /// la separation des deux reglages
+    pub fn gui_language(&self) -> &str {
+        self.gui.language.as_deref().unwrap_or_else(|| self.log_language())
+    }
 }
 
 impl Default for IronCloakConfig {
@@ -132,6 +1213,22 @@ impl Default for IronCloakConfig {
             proxy: ProxyConfig::default(),
             tor: TorConfig::default(),
             logging: LoggingConfig::default(),
+            control: ControlConfig::default(),
+            kill_switch: KillSwitchConfig::default(),
+            pac: PacConfig::default(),
+            gui: GuiConfig::default(),
+            updates: UpdatesConfig::default(),
+            otel: OtelConfig::default(),
+            audit: AuditConfig::default(),
+            history: HistoryConfig::default(),
+            rules: RulesConfig::default(),
+            health: HealthConfig::default(),
+            network: NetworkConfig::default(),
+            quota: QuotaConfig::default(),
+            security: SecurityConfig::default(),
+            sandbox: SandboxConfig::default(),
+            listeners: Vec::new(),
+            onion_services: Vec::new(),
         }
     }
 }
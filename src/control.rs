@@ -0,0 +1,472 @@
+// Socket de controle local pour piloter IronCloak sans l'interface graphique.
+// Protocole texte : une commande par ligne, une reponse JSON par ligne.
+// Commandes : "status" (etat du proxy), "stats" (octets relayes, flux par pays de sortie,
+// domaines les plus gourmands, handshakes SOCKS expires),
+// "check" (verification Tor de bout en bout),
+// "pause" / "resume" (refus ou reprise des nouvelles connexions SOCKS),
+// "retry" (relance de l'etape en echec : dechiffrement de l'etat, amorcage ou ecoute),
+// "log_level" (filtre des traces en vigueur), "log_level <directives>" (le remplace sans
+// redemarrer, ex : "log_level debug" ; non enregistre dans la configuration),
+// "events" (flux d'evenements en temps reel pour les tableaux de bord : ouverture et fermeture
+// des connexions, progression de l'amorcage, echantillons de debit ; une ligne JSON par
+// evenement jusqu'a la deconnexion du client, qui ne peut plus envoyer de commande).
+// Authentification (comme le CookieAuthentication de Tor) : la premiere commande doit etre
+// "authenticate <secret>", avec le jeton configure ou le contenu du fichier cookie (regenere a
+// chaque demarrage, en 0600) ; tout autre client est deconnecte.
+// Unix : socket Unix en 0600. Windows : tube nomme (control.pipe_name), refuse aux clients
+// distants, avec le meme protocole ; scripts/Get-IronCloakStatus.ps1 en est un client PowerShell.
+// Le socket et le cookie sont crees par bind() avant le bac a sable du backend : ses regles
+// Landlock n'ont pas a ouvrir leur repertoire en ecriture.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::config::IronCloakConfig;
+use crate::gui::state::{AppState, StateEvent};
+use crate::security::SecretBytes;
+
+/// Canal de controle ouvert par bind(), servi par run_control_server()
+pub struct ControlListener {
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    #[cfg(windows)]
+    pipe_name: String,
+    secret: Option<Arc<SecretBytes>>,
+}
+
+/// Cree le socket de controle et le cookie d'authentification (hors runtime tokio)
+#[cfg(unix)]
+pub fn bind(config: &IronCloakConfig) -> Result<ControlListener> {
+    use anyhow::Context;
+
+    let path = std::path::Path::new(&config.control.socket_path);
+
+    // Supprimer un socket orphelin laisse par une execution precedente
+    let _ = std::fs::remove_file(path);
+    // Socket cree directement en 0600 : masque de creation restrictif le temps du bind, sans
+    // instant ou un autre utilisateur pourrait s'y connecter avant un chmod. Le masque est
+    // global au processus : un fichier cree au meme moment par un autre thread serait
+    // seulement plus restreint.
+    let previous = unsafe { libc::umask(0o177) };
+    let bound = std::os::unix::net::UnixListener::bind(path);
+    unsafe { libc::umask(previous) };
+    let listener = bound.with_context(|| crate::t!("control.bind_failed", path = path.display()))?;
+    listener.set_nonblocking(true)?;
+
+    tracing::info!("{}", crate::tl!("control.listening", path = path.display()));
+    let secret = load_secret(&config.control)?.map(Arc::new);
+    Ok(ControlListener { listener, secret })
+}
+
+/// Traite les clients du socket de controle jusqu'a l'arret
+#[cfg(unix)]
+pub async fn run_control_server(control: ControlListener, state: Arc<AppState>) -> Result<()> {
+    let listener = tokio::net::UnixListener::from_std(control.listener)?;
+    let secret = control.secret;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = Arc::clone(&state);
+        let secret = secret.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, state, secret).await {
+                tracing::debug!("{}", crate::tl!("control.connection_error", error = e));
+            }
+        });
+    }
+}
+
+/// Cree le cookie d'authentification ; le tube nomme, lie au runtime tokio, est cree par
+/// run_control_server()
+#[cfg(windows)]
+pub fn bind(config: &IronCloakConfig) -> Result<ControlListener> {
+    let secret = load_secret(&config.control)?.map(Arc::new);
+    Ok(ControlListener { pipe_name: config.control.pipe_name.clone(), secret })
+}
+
+/// Traite les clients du tube nomme jusqu'a l'arret : une instance du tube par client, la
+/// suivante creee des qu'un client est connecte.
+#[cfg(windows)]
+pub async fn run_control_server(control: ControlListener, state: Arc<AppState>) -> Result<()> {
+    use anyhow::Context;
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = &control.pipe_name;
+    let secret = control.secret;
+    // Premiere instance exclusive : echoue si un autre processus possede deja ce tube
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(name)
+        .with_context(|| crate::t!("control.bind_failed", path = name))?;
+
+    tracing::info!("{}", crate::tl!("control.listening", path = name));
+
+    loop {
+        server.connect().await?;
+        let client = server;
+        server = ServerOptions::new()
+            .reject_remote_clients(true)
+            .create(name)
+            .with_context(|| crate::t!("control.bind_failed", path = name))?;
+        let state = Arc::clone(&state);
+        let secret = secret.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(client, state, secret).await {
+                tracing::debug!("{}", crate::tl!("control.connection_error", error = e));
+            }
+        });
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn bind(_config: &IronCloakConfig) -> Result<ControlListener> {
+    anyhow::bail!("{}", crate::t!("control.unsupported"))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub async fn run_control_server(_control: ControlListener, _state: Arc<AppState>) -> Result<()> {
+    Ok(())
+}
+
+/// Secret attendu des clients : jeton configure, sinon cookie aleatoire ecrit sur le disque.
+/// None sans authentification.
+#[cfg(any(unix, windows))]
+fn load_secret(config: &crate::config::ControlConfig) -> Result<Option<SecretBytes>> {
+    use anyhow::Context;
+    use std::io::Write;
+
+    if let Some(token) = &config.token {
+        return Ok(Some(SecretBytes::from_slice(token.trim().as_bytes())));
+    }
+    if !config.cookie_auth {
+        tracing::warn!("{}", crate::tl!("control.auth_disabled"));
+        return Ok(None);
+    }
+
+    // Secret en hexadecimal, pour etre passe tel quel a "authenticate"
+    let random = SecretBytes::new(rand::random::<[u8; 32]>().to_vec());
+    let mut cookie = SecretBytes::zeroed(random.len() * 2);
+    for (i, byte) in random.iter().enumerate() {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        cookie[2 * i] = HEX[usize::from(byte >> 4)];
+        cookie[2 * i + 1] = HEX[usize::from(byte & 0x0f)];
+    }
+
+    // Fichier recree (jamais un ancien fichier aux droits plus larges), lisible du seul
+    // proprietaire
+    let path = config.cookie_path();
+    let _ = std::fs::remove_file(&path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    create_private_file(&path)
+        .and_then(|mut file| file.write_all(&cookie))
+        .with_context(|| crate::t!("control.cookie_failed", path = path.display()))?;
+    tracing::info!("{}", crate::tl!("control.cookie_written", path = path.display()));
+    Ok(Some(cookie))
+}
+
+/// Cree un fichier neuf (echec s'il existe) accessible au seul utilisateur courant : mode 0600
+#[cfg(unix)]
+fn create_private_file(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+/// Cree un fichier neuf (echec s'il existe) accessible au seul utilisateur courant : DACL
+/// protegee (sans heritage du repertoire) donnant tous les droits au seul proprietaire,
+/// appliquee des la creation
+#[cfg(windows)]
+fn create_private_file(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+
+    use winapi::shared::sddl::{ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1};
+    use winapi::um::fileapi::{CreateFileW, CREATE_NEW};
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::minwinbase::SECURITY_ATTRIBUTES;
+    use winapi::um::winbase::LocalFree;
+    use winapi::um::winnt::{FILE_ATTRIBUTE_NORMAL, GENERIC_WRITE};
+
+    let sddl: Vec<u16> = "D:P(A;;FA;;;OW)".encode_utf16().chain(Some(0)).collect();
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut descriptor = std::ptr::null_mut();
+    unsafe {
+        if ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            u32::from(SDDL_REVISION_1),
+            &mut descriptor,
+            std::ptr::null_mut(),
+        ) == 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor,
+            bInheritHandle: 0,
+        };
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            GENERIC_WRITE,
+            0,
+            &mut attributes,
+            CREATE_NEW,
+            FILE_ATTRIBUTE_NORMAL,
+            std::ptr::null_mut(),
+        );
+        let error = std::io::Error::last_os_error();
+        LocalFree(descriptor);
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(error);
+        }
+        Ok(std::fs::File::from_raw_handle(handle as _))
+    }
+}
+
+/// Longueur maximale d'une ligne de commande : un client qui n'envoie jamais de fin de ligne
+/// est deconnecte au lieu de faire grossir la memoire du backend
+const MAX_LINE_LENGTH: u64 = 4096;
+
+/// Lit les commandes d'un client ligne par ligne et repond en JSON
+async fn handle_connection<S>(stream: S, state: Arc<AppState>, secret: Option<Arc<SecretBytes>>) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let mut authenticated = secret.is_none();
+
+    loop {
+        let mut raw = zeroize::Zeroizing::new(Vec::new());
+        let read = (&mut reader).take(MAX_LINE_LENGTH + 1).read_until(b'\n', &mut raw).await?;
+        if read == 0 {
+            break;
+        }
+        if !raw.ends_with(b"\n") && read as u64 > MAX_LINE_LENGTH {
+            tracing::warn!("{}", crate::tl!("control.line_too_long", max = MAX_LINE_LENGTH));
+            write_response(&mut writer, &error_response(crate::t!("control.line_too_long", max = MAX_LINE_LENGTH)))
+                .await?;
+            return Ok(());
+        }
+        let line = std::str::from_utf8(&raw).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let command = line.trim();
+        if command.is_empty() {
+            continue;
+        }
+        // Le secret n'est jamais journalise. Verbe exact : "authenticateXYZ" n'en est pas un
+        let (verb, provided) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        if verb == "authenticate" {
+            let accepted = authenticated
+                || secret.as_ref().is_some_and(|secret| constant_time_eq(secret, provided.trim().as_bytes()));
+            if !accepted {
+                tracing::warn!("{}", crate::tl!("control.auth_failed"));
+                write_response(&mut writer, &error_response(crate::t!("control.auth_failed"))).await?;
+                return Ok(());
+            }
+            authenticated = true;
+            write_response(&mut writer, &json!({ "ok": true })).await?;
+            continue;
+        }
+        if !authenticated {
+            tracing::warn!("{}", crate::tl!("control.auth_required"));
+            write_response(&mut writer, &error_response(crate::t!("control.auth_required"))).await?;
+            return Ok(());
+        }
+        tracing::debug!("{}", crate::tl!("control.command", command = command));
+        if command == "events" {
+            return stream_events(&mut writer, &state).await;
+        }
+        let response = execute(command, &state).await;
+        write_response(&mut writer, &response).await?;
+    }
+    Ok(())
+}
+
+/// Pousse les evenements au client jusqu'a sa deconnexion (echec d'ecriture) ou l'arret
+async fn stream_events<W: AsyncWrite + Unpin>(writer: &mut W, state: &AppState) -> Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    // Abonnement avant l'acquittement : aucun evenement perdu entre les deux
+    let mut events = state.subscribe_events();
+    let (up, down) = state.throughput();
+    write_response(
+        writer,
+        &json!({
+            "ok": true,
+            "bootstrap": state.bootstrap_percent(),
+            "active_connections": state.active_connections(),
+            "bytes_per_sec_up": up,
+            "bytes_per_sec_down": down,
+        }),
+    )
+    .await?;
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event_json(&event),
+            // Client trop lent : les evenements les plus anciens sont perdus, et signales
+            Err(RecvError::Lagged(skipped)) => json!({ "event": "lagged", "skipped": skipped }),
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        write_response(writer, &event).await?;
+    }
+}
+
+fn event_json(event: &StateEvent) -> Value {
+    let time = chrono::Local::now().to_rfc3339();
+    match event {
+        StateEvent::ConnectionOpened { conn_id, host, port } => json!({
+            "event": "connection_opened",
+            "time": time,
+            "conn": conn_id,
+            "host": host,
+            "port": port,
+        }),
+        StateEvent::ConnectionClosed(entry) => json!({
+            "event": "connection_closed",
+            "time": entry.closed.to_rfc3339(),
+            "conn": entry.conn_id,
+            "host": entry.host,
+            "port": entry.port,
+            "duration_ms": entry.duration.as_millis() as u64,
+            "bytes_up": entry.up,
+            "bytes_down": entry.down,
+            "outcome": entry.outcome,
+        }),
+        StateEvent::Bootstrap(percent) => json!({
+            "event": "bootstrap",
+            "time": time,
+            "percent": percent,
+        }),
+        StateEvent::Bandwidth(up, down) => json!({
+            "event": "bandwidth",
+            "time": time,
+            "bytes_per_sec_up": up,
+            "bytes_per_sec_down": down,
+        }),
+    }
+}
+
+async fn write_response<W: AsyncWrite + Unpin>(writer: &mut W, response: &Value) -> Result<()> {
+    writer.write_all(format!("{}\n", response).as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Comparaison en temps constant (pas d'indice sur le secret par le temps de reponse)
+fn constant_time_eq(expected: &[u8], provided: &[u8]) -> bool {
+    if expected.len() != provided.len() {
+        return false;
+    }
+    expected.iter().zip(provided).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Execute une commande de controle et retourne la reponse JSON
+async fn execute(command: &str, state: &Arc<AppState>) -> Value {
+    match command {
+        "status" => {
+            let (up, down) = state.throughput();
+            json!({
+                "ok": true,
+                "connected": state.is_connected(),
+                "state": state.status().as_str(),
+                "error": state.last_error().map(|error| json!({
+                    "stage": error.stage.as_str(),
+                    "message": error.message,
+                })),
+                "awaiting_passphrase": state.get_passphrase_prompt().is_some(),
+                "bootstrap": state.bootstrap_percent(),
+                "paused": state.is_paused(),
+                "port": state.get_port(),
+                "active_connections": state.active_connections(),
+                "bytes_per_sec_up": up,
+                "bytes_per_sec_down": down,
+                "health": state.get_health().map(|sample| json!({
+                    "level": sample.level.as_str(),
+                    "latency_ms": sample.latency.map(|latency| latency.as_millis() as u64),
+                })),
+                "quota_reached": state.get_quota_reached().map(|period| period.as_str()),
+                "resources": resources_json(),
+            })
+        }
+        "stats" => {
+            let countries: serde_json::Map<String, Value> =
+                state.exit_country_stats().into_iter().map(|(cc, n)| (cc, json!(n))).collect();
+            let destinations: Vec<Value> = state
+                .top_destinations(crate::gui::state::TOP_DESTINATIONS)
+                .into_iter()
+                .map(|(domain, stats)| {
+                    json!({
+                        "domain": domain,
+                        "connections": stats.connections,
+                        "bytes_up": stats.up,
+                        "bytes_down": stats.down,
+                    })
+                })
+                .collect();
+            let (up, down) = state.traffic_totals();
+            json!({
+                "ok": true,
+                "bytes_up": up,
+                "bytes_down": down,
+                "exit_countries": countries,
+                "top_destinations": destinations,
+                "handshake_timeouts": state.handshake_timeouts(),
+            })
+        }
+        "check" => {
+            let Some(backend) = state.get_backend() else {
+                return error_response(crate::t!("control.tor_not_ready"));
+            };
+            match crate::probe::check_tor(&backend.tor_client).await {
+                Ok(check) => json!({
+                    "ok": true,
+                    "is_tor": check.is_tor,
+                    "ip": check.ip,
+                    "country": check.country,
+                }),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "pause" | "resume" => {
+            state.set_paused(command == "pause");
+            json!({ "ok": true, "paused": state.is_paused() })
+        }
+        "retry" => match state.last_error() {
+            Some(error) => {
+                state.request_retry();
+                json!({ "ok": true, "stage": error.stage.as_str() })
+            }
+            None => error_response(crate::t!("control.nothing_to_retry")),
+        },
+        "log_level" => json!({ "ok": true, "filter": crate::logging::current_filter() }),
+        other if other.starts_with("log_level ") => {
+            let directives = other["log_level ".len()..].trim();
+            match crate::logging::set_filter(directives) {
+                Ok(()) => json!({ "ok": true, "filter": crate::logging::current_filter() }),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        other => error_response(crate::t!("control.unknown_command", command = other)),
+    }
+}
+
+/// Ressources du processus (commande "status") ; les taches sont celles de ce runtime
+fn resources_json() -> Value {
+    let usage = crate::resources::snapshot(Some(&tokio::runtime::Handle::current()));
+    json!({
+        "memory_bytes": usage.memory_bytes,
+        "open_handles": usage.open_handles,
+        "threads": usage.threads,
+        "tasks": usage.tasks,
+    })
+}
+
+fn error_response(message: String) -> Value {
+    json!({ "ok": false, "error": message })
+}
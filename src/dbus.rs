@@ -0,0 +1,137 @@
+// Service D-Bus sur le bus de session (control.dbus, Linux uniquement) : interface
+// org.ironcloak.Proxy1 sur /org/ironcloak/Proxy, nom org.ironcloak.Proxy.
+// Proprietes (avec PropertiesChanged) : Status, Connected, Paused, Bootstrap.
+// Signal Connected a chaque connexion au reseau Tor (fin de l'amorcage, retour du reseau).
+// Methodes : NewIdentity, Pause, Resume.
+// Pour les extensions GNOME et les scripts :
+//   busctl --user call org.ironcloak.Proxy /org/ironcloak/Proxy org.ironcloak.Proxy1 NewIdentity
+
+use std::sync::Arc;
+
+use crate::gui::state::AppState;
+
+#[cfg(target_os = "linux")]
+pub use linux::run_dbus_service;
+
+/// Autres plateformes : pas de bus de session
+#[cfg(not(target_os = "linux"))]
+pub async fn run_dbus_service(_state: Arc<AppState>) {
+    tracing::warn!("{}", crate::tl!("dbus.unsupported"));
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use anyhow::Result;
+    use zbus::object_server::SignalEmitter;
+
+    use super::{AppState, Arc};
+
+    const BUS_NAME: &str = "org.ironcloak.Proxy";
+    const OBJECT_PATH: &str = "/org/ironcloak/Proxy";
+
+    struct ProxyInterface {
+        state: Arc<AppState>,
+    }
+
+    #[zbus::interface(name = "org.ironcloak.Proxy1")]
+    impl ProxyInterface {
+        /// bootstrapping | connected | paused | failed | offline
+        #[zbus(property)]
+        fn status(&self) -> String {
+            self.state.status().as_str().to_string()
+        }
+
+        /// Connecte au reseau Tor, en pause ou non
+        #[zbus(property)]
+        fn connected(&self) -> bool {
+            is_online(&self.state)
+        }
+
+        #[zbus(property)]
+        fn paused(&self) -> bool {
+            self.state.is_paused()
+        }
+
+        /// Progression de l'amorcage (0 a 100)
+        #[zbus(property)]
+        fn bootstrap(&self) -> u8 {
+            self.state.bootstrap_percent()
+        }
+
+        fn new_identity(&self) {
+            self.state.new_identity();
+        }
+
+        fn pause(&self) {
+            self.state.set_paused(true);
+        }
+
+        fn resume(&self) {
+            self.state.set_paused(false);
+        }
+
+        /// Connexion au reseau Tor etablie
+        #[zbus(signal, name = "Connected")]
+        async fn connected_signal(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+    }
+
+    fn is_online(state: &AppState) -> bool {
+        state.is_connected() && !state.is_offline()
+    }
+
+    /// Publie l'interface puis relaie les changements d'etat jusqu'a l'arret. Sans bus de
+    /// session (serveur, service systeme), un avertissement et rien d'autre.
+    pub async fn run_dbus_service(state: Arc<AppState>) {
+        if let Err(e) = serve(Arc::clone(&state)).await {
+            tracing::warn!("{}", crate::tl!("dbus.unavailable", error = format!("{:#}", e)));
+        }
+    }
+
+    async fn serve(state: Arc<AppState>) -> Result<()> {
+        let interface = ProxyInterface { state: Arc::clone(&state) };
+        let connection = zbus::connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, interface)?
+            .build()
+            .await?;
+        tracing::info!("{}", crate::tl!("dbus.registered", name = BUS_NAME));
+
+        let interface = connection.object_server().interface::<_, ProxyInterface>(OBJECT_PATH).await?;
+        let emitter = interface.signal_emitter();
+        let mut changes = state.subscribe_changes();
+        let snapshot =
+            |state: &AppState| (state.status(), is_online(state), state.is_paused(), state.bootstrap_percent());
+        let mut last = snapshot(&state);
+        loop {
+            tokio::select! {
+                changed = changes.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                }
+                _ = state.wait_for_quit() => return Ok(()),
+            }
+            let current = snapshot(&state);
+            if current == last {
+                continue;
+            }
+            let iface = interface.get().await;
+            if current.0 != last.0 {
+                iface.status_changed(emitter).await?;
+            }
+            if current.1 != last.1 {
+                iface.connected_changed(emitter).await?;
+                if current.1 {
+                    ProxyInterface::connected_signal(emitter).await?;
+                }
+            }
+            if current.2 != last.2 {
+                iface.paused_changed(emitter).await?;
+            }
+            if current.3 != last.3 {
+                iface.bootstrap_changed(emitter).await?;
+            }
+            last = current;
+        }
+    }
+}
@@ -1,6 +1,6 @@
 // Module GUI — dispatch selon la plateforme.
 // Windows : icone systray + fenetre egui a la demande
-// Linux : fenetre egui directement
+// Linux : fenetre egui, puis icone StatusNotifierItem (GNOME/KDE) si disponible
 
 pub mod state;
 pub mod window;
@@ -8,8 +8,22 @@ pub mod window;
 #[cfg(windows)]
 pub mod tray;
 
+#[cfg(target_os = "linux")]
+pub mod sni;
+
 use std::sync::Arc;
-use state::AppState;
+use state::{AppState, ProxyStatus};
+#[cfg(any(windows, target_os = "linux"))]
+use state::TrayCommand;
+
+#[cfg(any(windows, target_os = "linux"))]
+use crate::config::IronCloakConfig;
+
+// Icones PNG embarquees dans le binaire
+#[cfg(any(windows, target_os = "linux"))]
+const ICON_ON_PNG: &[u8] = include_bytes!("../../icon_256_on.png");
+#[cfg(any(windows, target_os = "linux"))]
+const ICON_OFF_PNG: &[u8] = include_bytes!("../../icon_256_off.png");
 
 /// Lance l'interface graphique appropriee selon la plateforme.
 /// Cette fonction est bloquante et doit etre appelee sur le thread principal.
@@ -19,8 +33,248 @@ pub fn run_gui(state: Arc<AppState>) {
         tray::run_tray(state);
     }
 
-    #[cfg(not(windows))]
+    #[cfg(target_os = "linux")]
+    {
+        sni::run_tray(state);
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
     {
         window::run_window(state);
     }
 }
+
+/// Execute une commande du menu systray (hors "Configurer", geree par l'appelant)
+#[cfg(any(windows, target_os = "linux"))]
+pub fn execute_command(state: &AppState, command: TrayCommand) {
+    match command {
+        TrayCommand::Configure => {}
+        TrayCommand::NewIdentity => state.new_identity(),
+        TrayCommand::TogglePause => {
+            state.toggle_pause();
+        }
+        TrayCommand::OpenLogs => {
+            // Chemin absolu : l'explorateur interprete mal les chemins relatifs "./logs"
+            let config = IronCloakConfig::load(&state.config_path).unwrap_or_default();
+            let log_dir = std::env::current_dir()
+                .map(|dir| dir.join(&config.logging.log_dir))
+                .unwrap_or_else(|_| config.logging.log_dir.clone().into());
+            if let Err(e) = open_folder(&log_dir) {
+                tracing::warn!("{}", crate::tl!("gui.open_logs_failed", error = e));
+            }
+        }
+        TrayCommand::CopyAddress => {
+            let url = format!("socks5://127.0.0.1:{}", state.get_port());
+            if let Err(e) = copy_to_clipboard(url) {
+                tracing::warn!("{}", crate::tl!("gui.copy_failed", error = e));
+            }
+        }
+        TrayCommand::Quit => state.request_quit(),
+    }
+}
+
+/// Commande d'un simple clic gauche sur l'icone (gui.tray_click_action) ; en cas d'erreur,
+/// toujours la fenetre, pour en voir le detail
+#[cfg(any(windows, target_os = "linux"))]
+pub fn click_command(state: &AppState) -> TrayCommand {
+    use crate::config::TrayClickAction;
+
+    if state.status() == ProxyStatus::Failed {
+        return TrayCommand::Configure;
+    }
+    match state.tray_click_action() {
+        TrayClickAction::OpenWindow => TrayCommand::Configure,
+        TrayClickAction::TogglePause => TrayCommand::TogglePause,
+        TrayClickAction::NewIdentity => TrayCommand::NewIdentity,
+    }
+}
+
+/// Icone de la zone de notification pour un etat : icone "on" une fois connecte, "off" sinon,
+/// marquee d'une pastille dans le coin inferieur droit (amorcage orange, erreur rouge, hors
+/// ligne gris, pause grise a deux barres)
+#[cfg(any(windows, target_os = "linux"))]
+pub fn status_icon(status: ProxyStatus) -> image::RgbaImage {
+    let png = match status {
+        ProxyStatus::Connected | ProxyStatus::Paused => ICON_ON_PNG,
+        ProxyStatus::Bootstrapping | ProxyStatus::Failed | ProxyStatus::Offline => ICON_OFF_PNG,
+    };
+    let mut img = image::load_from_memory(png)
+        .expect("Erreur de decodage de l'icone PNG")
+        .into_rgba8();
+    let badge = match status {
+        ProxyStatus::Connected => return img,
+        ProxyStatus::Bootstrapping => image::Rgba([240, 160, 0, 255]),
+        ProxyStatus::Paused => image::Rgba([120, 120, 120, 255]),
+        ProxyStatus::Failed => image::Rgba([220, 0, 0, 255]),
+        ProxyStatus::Offline => image::Rgba([90, 90, 90, 255]),
+    };
+    let white = image::Rgba([255, 255, 255, 255]);
+
+    // Disque d'un tiers de la largeur, bord blanc pour rester lisible sur tout fond
+    let (w, h) = img.dimensions();
+    let radius = w.min(h) as f32 / 6.0;
+    let (cx, cy) = (w as f32 - radius - 1.0, h as f32 - radius - 1.0);
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance > radius {
+            continue;
+        }
+        let pause_bar = status == ProxyStatus::Paused
+            && dy.abs() < radius * 0.45
+            && (dx.abs() - radius * 0.25).abs() < radius * 0.12;
+        *pixel = if distance > radius * 0.85 || pause_bar { white } else { badge };
+    }
+    img
+}
+
+/// Etat du proxy pour le menu de l'icone de notification et la fenetre
+pub fn status_text(state: &AppState) -> String {
+    match state.status() {
+        ProxyStatus::Bootstrapping => crate::t!("gui.bootstrapping", percent = state.bootstrap_percent()),
+        ProxyStatus::Connected => crate::t!("gui.connected"),
+        ProxyStatus::Paused => crate::t!("gui.paused"),
+        ProxyStatus::Failed => crate::t!("gui.failed"),
+        ProxyStatus::Offline => crate::t!("gui.offline"),
+    }
+}
+
+/// Infobulle de l'icone de notification : progression de l'amorcage ou erreur, puis port,
+/// etat, nombre de connexions actives et pays de sortie
+#[cfg(any(windows, target_os = "linux"))]
+pub fn tray_tooltip(state: &AppState) -> String {
+    let status = match state.status() {
+        ProxyStatus::Bootstrapping | ProxyStatus::Offline => return format!("IronCloak — {}", status_text(state)),
+        ProxyStatus::Failed => return format!("IronCloak — {}", crate::t!("gui.failed_hint")),
+        ProxyStatus::Paused => crate::t!("gui.paused"),
+        ProxyStatus::Connected => crate::t!("gui.connected_active", count = state.active_connections()),
+    };
+    let mut tooltip = format!("IronCloak :{} — {}", state.get_port(), status);
+    if let Some(cc) = state.current_exit().and_then(|exit| exit.country) {
+        tooltip.push_str(" — ");
+        tooltip.push_str(&crate::t!("gui.exit_country", country = cc));
+    }
+    tooltip
+}
+
+/// Cause de l'echec du backend pour l'infobulle (None hors echec)
+#[cfg(any(windows, target_os = "linux"))]
+pub fn error_text(state: &AppState) -> Option<String> {
+    state.last_error().map(|error| error.message)
+}
+
+/// Debit montant et descendant pour l'infobulle (None tant que Tor n'est pas pret)
+#[cfg(any(windows, target_os = "linux"))]
+pub fn throughput_text(state: &AppState) -> Option<String> {
+    if !state.is_connected() {
+        return None;
+    }
+    let (up, down) = state.throughput();
+    Some(crate::t!("gui.throughput", up = format_rate(up), down = format_rate(down)))
+}
+
+/// Latence et niveau de l'indicateur de sante (None tant qu'aucune mesure n'a ete faite)
+pub fn health_text(state: &AppState) -> Option<String> {
+    if !state.is_connected() {
+        return None;
+    }
+    let sample = state.get_health()?;
+    let level = match sample.level {
+        state::HealthLevel::Good => crate::t!("gui.health_good"),
+        state::HealthLevel::Slow => crate::t!("gui.health_slow"),
+        state::HealthLevel::Bad => crate::t!("gui.health_bad"),
+    };
+    Some(match sample.latency {
+        Some(latency) => crate::t!("gui.latency", ms = latency.as_millis(), level = level),
+        None => crate::t!("gui.latency_failed", level = level),
+    })
+}
+
+/// Avertissement de quota de donnees atteint (None sous le quota)
+pub fn quota_text(state: &AppState) -> Option<String> {
+    Some(match state.get_quota_reached()? {
+        state::QuotaPeriod::Daily => crate::t!("gui.quota_reached_daily"),
+        state::QuotaPeriod::Monthly => crate::t!("gui.quota_reached_monthly"),
+    })
+}
+
+/// Debit lisible ("12.3 KB/s"), unites traduites
+#[cfg(any(windows, target_os = "linux"))]
+fn format_rate(bytes_per_sec: u64) -> String {
+    format_units(bytes_per_sec, &crate::t!("gui.rate_units"))
+}
+
+/// Quantite lisible ("12.3 MB"), unites traduites
+#[cfg(any(windows, target_os = "linux"))]
+fn format_bytes(bytes: u64) -> String {
+    format_units(bytes, &crate::t!("gui.byte_units"))
+}
+
+/// Valeur en octets dans la plus grande unite de la liste `units` (separees par des virgules,
+/// par puissances de 1024)
+#[cfg(any(windows, target_os = "linux"))]
+fn format_units(bytes: u64, units: &str) -> String {
+    let units: Vec<&str> = units.split(',').collect();
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < units.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let suffix = units.get(unit).copied().unwrap_or_default();
+    if unit == 0 {
+        format!("{} {}", bytes, suffix)
+    } else {
+        format!("{:.1} {}", value, suffix)
+    }
+}
+
+/// Ouvre un dossier dans le gestionnaire de fichiers du systeme
+#[cfg(any(windows, target_os = "linux"))]
+fn open_folder(path: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let opener = "explorer";
+    #[cfg(target_os = "linux")]
+    let opener = "xdg-open";
+
+    let mut command = std::process::Command::new(opener);
+    command.arg(path);
+    crate::killswitch::confine_child(&mut command);
+    command.spawn().map(|_| ())
+}
+
+/// Ouvre une page dans le navigateur par defaut du systeme, hors Tor (portail captif).
+/// Seul programme lance sans crate::killswitch::confine_child : avec le kill switch actif, la
+/// page de connexion du portail doit pouvoir sortir en clair.
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let opener = "explorer";
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let opener = "xdg-open";
+
+    std::process::Command::new(opener).arg(url).spawn().map(|_| ())
+}
+
+/// Copie un texte dans le presse-papiers
+#[cfg(windows)]
+fn copy_to_clipboard(text: String) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new()?.set_text(text)
+}
+
+/// Copie un texte dans le presse-papiers.
+/// Sous X11/Wayland le contenu n'existe que tant que son proprietaire le sert :
+/// un thread dedie le garde jusqu'a ce qu'une autre application prenne le relais.
+#[cfg(target_os = "linux")]
+fn copy_to_clipboard(text: String) -> Result<(), arboard::Error> {
+    use arboard::SetExtLinux;
+
+    let mut clipboard = arboard::Clipboard::new()?;
+    std::thread::spawn(move || {
+        if let Err(e) = clipboard.set().wait().text(text) {
+            tracing::warn!("{}", crate::tl!("gui.copy_failed", error = e));
+        }
+    });
+    Ok(())
+}
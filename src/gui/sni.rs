@@ -0,0 +1,218 @@
+// Icone de zone de notification Linux (StatusNotifierItem via ksni : KDE, GNOME avec extension).
+// Meme menu que le systray Windows ; un clic sur l'icone ouvre la fenetre de configuration, met
+// en pause/reprend ou demande une nouvelle identite selon gui.tray_click_action.
+// Fermer la fenetre laisse IronCloak tourner dans la zone de notification.
+// Sans implementation StatusNotifierItem, on revient au comportement fenetre seule.
+
+#![cfg(target_os = "linux")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use ksni::blocking::TrayMethods;
+
+use crate::gui::{
+    click_command, error_text, execute_command, health_text, quota_text, status_icon, status_text, throughput_text,
+    tray_tooltip,
+};
+use crate::gui::state::{AppState, ProxyStatus, TrayCommand};
+
+/// Etats du proxy ayant chacun leur icone
+const STATUSES: [ProxyStatus; 5] = [
+    ProxyStatus::Bootstrapping,
+    ProxyStatus::Connected,
+    ProxyStatus::Paused,
+    ProxyStatus::Failed,
+    ProxyStatus::Offline,
+];
+
+/// Icone ksni d'un etat du proxy (ARGB32, octets dans l'ordre reseau)
+fn load_icon(status: ProxyStatus) -> ksni::Icon {
+    let img = status_icon(status);
+    let (w, h) = img.dimensions();
+    let mut data = img.into_raw();
+    for pixel in data.chunks_exact_mut(4) {
+        // RGBA → ARGB
+        pixel.rotate_right(1);
+    }
+    ksni::Icon {
+        width: w as i32,
+        height: h as i32,
+        data,
+    }
+}
+
+/// Etat expose au service StatusNotifierItem (relu a chaque mise a jour)
+struct IronCloakTray {
+    state: Arc<AppState>,
+    /// Demande d'ouverture de la fenetre, traitee par le thread principal
+    open_config: Arc<AtomicBool>,
+    /// Icones decodees une fois pour toutes, par etat
+    icons: Vec<(ProxyStatus, ksni::Icon)>,
+}
+
+impl IronCloakTray {
+    /// Entree de menu executant une commande
+    fn command_item(label: String, command: TrayCommand) -> ksni::MenuItem<Self> {
+        ksni::menu::StandardItem {
+            label,
+            activate: Box::new(move |tray: &mut Self| {
+                if command == TrayCommand::Configure {
+                    tray.open_config.store(true, Ordering::Relaxed);
+                    tray.state.notify_changed();
+                } else {
+                    execute_command(&tray.state, command);
+                }
+            }),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl ksni::Tray for IronCloakTray {
+    fn id(&self) -> String {
+        "ironcloak".into()
+    }
+
+    fn title(&self) -> String {
+        crate::t!("gui.window_title")
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        let status = self.state.status();
+        self.icons
+            .iter()
+            .filter(|(s, _)| *s == status)
+            .map(|(_, icon)| icon.clone())
+            .collect()
+    }
+
+    /// Erreur : l'hote peut signaler l'icone (clignotement, mise en avant)
+    fn status(&self) -> ksni::Status {
+        match self.state.status() {
+            ProxyStatus::Failed => ksni::Status::NeedsAttention,
+            _ => ksni::Status::Active,
+        }
+    }
+
+    /// Infobulle : amorcage ou erreur, puis etat, connexions actives, pays de sortie, debit,
+    /// latence et quota atteint, comme sous Windows
+    fn tool_tip(&self) -> ksni::ToolTip {
+        let title = tray_tooltip(&self.state);
+        let description = error_text(&self.state)
+            .into_iter()
+            .chain(throughput_text(&self.state))
+            .chain(health_text(&self.state))
+            .chain(quota_text(&self.state))
+            .chain(self.state.update_available().map(|version| crate::t!("gui.update_available", version = version)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        ksni::ToolTip {
+            title,
+            description,
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        match click_command(&self.state) {
+            TrayCommand::Configure => {
+                self.open_config.store(true, Ordering::Relaxed);
+                self.state.notify_changed();
+            }
+            command => execute_command(&self.state, command),
+        }
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        let pause_label = if self.state.is_paused() {
+            crate::t!("gui.resume")
+        } else {
+            crate::t!("gui.pause")
+        };
+        vec![
+            ksni::menu::StandardItem {
+                label: status_text(&self.state),
+                enabled: false,
+                ..Default::default()
+            }
+            .into(),
+            ksni::MenuItem::Separator,
+            Self::command_item(crate::t!("gui.configure"), TrayCommand::Configure),
+            Self::command_item(crate::t!("gui.new_identity"), TrayCommand::NewIdentity),
+            Self::command_item(pause_label, TrayCommand::TogglePause),
+            Self::command_item(crate::t!("gui.open_logs"), TrayCommand::OpenLogs),
+            Self::command_item(crate::t!("gui.copy_address"), TrayCommand::CopyAddress),
+            ksni::MenuItem::Separator,
+            Self::command_item(crate::t!("gui.quit"), TrayCommand::Quit),
+        ]
+    }
+}
+
+/// Affiche la fenetre puis reste dans la zone de notification jusqu'a la demande de fermeture.
+/// Bloquant ; sans zone de notification, fermer la fenetre quitte l'application.
+pub fn run_tray(state: Arc<AppState>) {
+    let open_config = Arc::new(AtomicBool::new(false));
+    let tray = IronCloakTray {
+        state: Arc::clone(&state),
+        open_config: Arc::clone(&open_config),
+        icons: STATUSES.into_iter().map(|status| (status, load_icon(status))).collect(),
+    };
+
+    let handle = match tray.spawn() {
+        Ok(handle) => handle,
+        Err(e) => {
+            tracing::warn!("{}", crate::tl!("gui.tray_unavailable", error = e));
+            crate::gui::window::run_window(state);
+            return;
+        }
+    };
+
+    state.set_tray_active(true);
+
+    // La fenetre reste ouverte au demarrage, comme sans zone de notification
+    crate::gui::window::run_window(Arc::clone(&state));
+
+    // Reveil a chaque changement d'etat (AppState::notify_changed), sans scrutation
+    let mut changes = state.subscribe_changes();
+    let mut last_seen = None;
+    let mut portal_shown = false;
+    loop {
+        if state.should_quit() {
+            break;
+        }
+
+        // Portail captif detecte fenetre fermee : elle se rouvre pour proposer la page de connexion
+        let portal = state.captive_portal().is_some();
+        let show_portal = portal && !portal_shown;
+        portal_shown = portal;
+
+        if open_config.swap(false, Ordering::Relaxed) || show_portal {
+            crate::gui::window::run_window(Arc::clone(&state));
+            continue;
+        }
+
+        // Rafraichir l'icone, l'infobulle et le menu quand l'etat change
+        let seen = (
+            state.status(),
+            status_text(&state),
+            tray_tooltip(&state),
+            error_text(&state),
+            throughput_text(&state),
+            health_text(&state),
+            quota_text(&state),
+            state.update_available(),
+        );
+        if last_seen.as_ref() != Some(&seen) {
+            handle.update(|_| {});
+            last_seen = Some(seen);
+        }
+
+        if futures::executor::block_on(changes.changed()).is_err() {
+            break;
+        }
+    }
+
+    handle.shutdown().wait();
+}
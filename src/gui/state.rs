@@ -1,49 +1,548 @@
 // Etat partage entre le thread GUI et le thread tokio.
-// Utilise des types atomiques pour la synchronisation sans verrou.
+// Utilise des types atomiques pour la synchronisation sans verrou, et des canaux watch pour
+// reveiller les boucles en attente (arret, changements affiches par les zones de notification).
 
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use arti_client::{IsolationToken, TorClient};
+use tokio::sync::{broadcast, watch};
+use tor_rtcompat::PreferredRuntime;
+use zeroize::Zeroizing;
+
+use crate::config::TrayClickAction;
+use crate::probe::{SpeedTest, TorCheck};
+use crate::tor::{CircuitInfo, RelayInfo};
+
+/// Duree au-dela de laquelle un circuit sans nouveau flux n'est plus affiche
+/// (aligne sur la duree de vie par defaut des circuits d'arti)
+const CIRCUIT_STALE_AFTER: Duration = Duration::from_secs(600);
+
+/// Nombre de jetons d'isolation par cle conserves ; au-dela, la table repart de zero
+/// (les destinations suivantes obtiennent des circuits neufs)
+const MAX_DEST_ISOLATION_TOKENS: usize = 10_000;
+
+/// Evenements en attente par abonne ; un abonne trop lent perd les plus anciens
+const EVENT_CAPACITY: usize = 256;
+
+/// Cle d'isolation des flux : adresse du client, hote et/ou port de destination, selon
+/// proxy.isolate_client_addr / isolate_dest_addr / isolate_dest_port
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IsolationKey {
+    pub client: Option<IpAddr>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+}
+
+/// Circuit observe lors de l'attachement d'un flux SOCKS
+#[derive(Debug, Clone)]
+pub struct CircuitEntry {
+    pub info: CircuitInfo,
+    /// Premier flux attache a ce circuit (sert a calculer l'age)
+    pub first_seen: Instant,
+    /// Dernier flux attache a ce circuit
+    pub last_used: Instant,
+    /// Nombre de flux attaches depuis la premiere observation
+    pub streams: u64,
+}
+
+/// Connexion SOCKS active et circuit auquel son flux Tor est attache
+#[derive(Debug, Clone)]
+pub struct ConnectionEntry {
+    pub conn_id: u64,
+    pub host: String,
+    pub port: u16,
+    /// Identifiant du circuit (None si le circuit n'a pas pu etre determine)
+    pub circuit_id: Option<String>,
+    /// Relais de sortie du circuit
+    pub exit: Option<RelayInfo>,
+    pub started: Instant,
+}
+
+/// Connexion SOCKS fermee (onglet "Historique")
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub conn_id: u64,
+    /// Hote masque selon logging.redact_destinations
+    pub host: String,
+    pub port: u16,
+    pub closed: chrono::DateTime<chrono::Local>,
+    pub duration: Duration,
+    pub up: u64,
+    pub down: u64,
+    /// completed | relay_error | closed_by_pause | error
+    pub outcome: &'static str,
+}
+
+/// Evenement pousse aux abonnes du socket de controle (commande "events")
+#[derive(Debug, Clone)]
+pub enum StateEvent {
+    /// Requete SOCKS recue, destination masquee selon logging.redact_destinations
+    ConnectionOpened { conn_id: u64, host: String, port: u16 },
+    ConnectionClosed(HistoryEntry),
+    Bootstrap(u8),
+    /// Debit mesure sur le dernier intervalle, en octets par seconde (montant, descendant)
+    Bandwidth(u64, u64),
+}
+
+/// Trafic cumule vers un domaine de destination (onglet "Statistiques")
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DestinationStats {
+    pub connections: u64,
+    pub up: u64,
+    pub down: u64,
+}
+
+impl DestinationStats {
+    pub fn total(&self) -> u64 {
+        self.up + self.down
+    }
+}
+
+/// Nombre maximal de domaines suivis : au-dela, le moins gourmand est oublie
+const MAX_DESTINATIONS: usize = 1000;
+
+/// Domaines affiches dans l'onglet "Statistiques" et rapportes par la commande "stats"
+pub const TOP_DESTINATIONS: usize = 20;
+
+/// Service onion heberge et publie (onglet "Onion")
+#[derive(Debug, Clone)]
+pub struct OnionServiceStatus {
+    pub nickname: String,
+    /// Adresse .onion complete
+    pub address: String,
+    /// Port virtuel sur l'adresse .onion
+    pub port: u16,
+    /// Cible locale des flux
+    pub target: String,
+}
+
+/// Acces au runtime tokio et au client Tor depuis l'interface graphique.
+/// Renseigne par le backend une fois l'amorcage termine.
+#[derive(Clone)]
+pub struct Backend {
+    pub runtime: tokio::runtime::Handle,
+    pub tor_client: Arc<TorClient<PreferredRuntime>>,
+}
+
+/// Etat du proxy affiche par l'icone de notification et la fenetre
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyStatus {
+    /// Amorcage de Tor (ou dechiffrement de l'etat) en cours
+    Bootstrapping,
+    Connected,
+    /// Connecte, nouvelles connexions refusees
+    Paused,
+    /// Echec du dechiffrement de l'etat, de l'amorcage ou de l'ecoute SOCKS
+    Failed,
+    /// Reseau perdu : client Tor en sommeil jusqu'a son retour
+    Offline,
+}
+
+impl ProxyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyStatus::Bootstrapping => "bootstrapping",
+            ProxyStatus::Connected => "connected",
+            ProxyStatus::Paused => "paused",
+            ProxyStatus::Failed => "failed",
+            ProxyStatus::Offline => "offline",
+        }
+    }
+}
+
+/// Etape du backend en echec, relancee par le bouton "Reessayer"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailedStage {
+    /// Dechiffrement de l'etat (tor.encrypt_state)
+    Unlock,
+    Bootstrap,
+    /// Ecoute du port SOCKS principal
+    Listen,
+}
+
+impl FailedStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailedStage::Unlock => "unlock",
+            FailedStage::Bootstrap => "bootstrap",
+            FailedStage::Listen => "listen",
+        }
+    }
+}
+
+/// Derniere erreur du backend (message deja traduit)
+#[derive(Debug, Clone)]
+pub struct BackendError {
+    pub stage: FailedStage,
+    pub message: String,
+}
+
+/// Commandes du menu systray (identifiants de menu associes dans `AppState`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayCommand {
+    Configure,
+    NewIdentity,
+    TogglePause,
+    OpenLogs,
+    CopyAddress,
+    Quit,
+}
+
+/// Resultat de la derniere verification des mises a jour
+#[derive(Debug, Clone)]
+pub enum UpdateState {
+    Running,
+    UpToDate,
+    /// Version plus recente disponible
+    Available(String),
+    Failed(String),
+}
+
+/// Etat du test "Verifier Tor"
+#[derive(Debug, Clone)]
+pub enum TorCheckState {
+    Running,
+    Done(TorCheck),
+    Failed(String),
+}
+
+/// Etat du test de debit
+#[derive(Debug, Clone)]
+pub enum SpeedTestState {
+    Running,
+    Done(SpeedTest),
+    Failed(String),
+}
+
+/// Niveau de l'indicateur de sante des circuits (vert, jaune, rouge)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthLevel {
+    Good,
+    Slow,
+    /// Latence au-dela de health.bad_ms, ou pas de reponse
+    Bad,
+}
+
+impl HealthLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthLevel::Good => "good",
+            HealthLevel::Slow => "slow",
+            HealthLevel::Bad => "bad",
+        }
+    }
+}
+
+/// Derniere mesure de latence des circuits
+#[derive(Debug, Clone, Copy)]
+pub struct HealthSample {
+    /// Temps d'ouverture d'un flux (None : pas de reponse dans le delai)
+    pub latency: Option<Duration>,
+    pub level: HealthLevel,
+}
+
+/// Periode d'un quota de donnees depasse (quota.daily_mb, quota.monthly_mb)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    Daily,
+    Monthly,
+}
+
+impl QuotaPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaPeriod::Daily => "daily",
+            QuotaPeriod::Monthly => "monthly",
+        }
+    }
+}
+
+/// Demande de ponts a BridgeDB (bouton "Obtenir des ponts")
+#[derive(Debug, Clone)]
+pub enum MoatState {
+    /// Captcha en cours de telechargement
+    Fetching,
+    /// Captcha a resoudre
+    Challenge(crate::moat::Challenge),
+    /// Solution envoyee, en attente des ponts
+    Checking,
+    /// Ponts attribues, a ajouter a la configuration
+    Done(Vec<String>),
+    Failed(String),
+}
+
+/// Phrase secrete de l'etat chiffre (tor.encrypt_state) attendue par le backend
+#[derive(Debug, Clone)]
+pub struct PassphrasePrompt {
+    /// Aucune archive chiffree : la phrase secrete est choisie (saisie a confirmer)
+    pub first_time: bool,
+    /// Echec de la saisie precedente (phrase incorrecte, archive illisible)
+    pub error: Option<String>,
+}
 
 /// Etat global de l'application partage entre les threads
 pub struct AppState {
-    /// Indique si le client Tor est connecte et pret
-    pub connected: AtomicBool,
+    /// Etat du backend (ProxyStatus sans la pause, suivie a part)
+    pub status: AtomicU8,
+    /// Progression de l'amorcage Tor, en pourcentage
+    pub bootstrap: AtomicU8,
+    /// Reseau perdu (network.offline_detection)
+    pub offline: AtomicBool,
+    /// Mesures de sante consecutives sans reponse
+    pub health_failures: AtomicU32,
+    /// Nouvelle identite demandee, premier circuit neuf pas encore construit (proxy.rebuild)
+    pub rebuilding: AtomicBool,
+    /// Page de connexion d'un portail captif detecte apres un echec de l'amorcage
+    pub captive_portal: Mutex<Option<String>>,
+    /// Cause de l'etat ProxyStatus::Failed, et signal de nouvelle tentative
+    pub last_error: Mutex<Option<BackendError>>,
+    pub retry: tokio::sync::Notify,
     /// Port d'ecoute actuel du serveur SOCKS5
     pub port: AtomicU16,
     /// Port en attente (sera applique au prochain redemarrage), 0 = pas de changement
     pub pending_port: AtomicU16,
     /// Signal de demande d'arret de l'application
-    pub quit: AtomicBool,
+    pub quit: watch::Sender<bool>,
+    /// Compteur incremente a chaque changement d'etat visible dans la zone de notification
+    /// (connexion, amorcage, pause, connexions actives, debit, mise a jour, arret)
+    pub changed: watch::Sender<u64>,
+    /// Evenements en temps reel (connexions, amorcage, debit) pour les tableaux de bord externes
+    pub events: broadcast::Sender<StateEvent>,
     /// Chemin vers le fichier de configuration
     pub config_path: PathBuf,
     /// Langue courante de l'application
     pub language: Mutex<String>,
-    /// ID du menu item "Quitter" du systray (stocke comme String pour la portabilite)
+    /// IDs des entrees du menu systray et commande associee (stockes comme String pour la portabilite)
     /// Permet a la fenetre egui de traiter les evenements menu pendant qu'elle est ouverte
-    pub tray_quit_menu_id: Mutex<Option<String>>,
+    pub tray_menu: Mutex<Vec<(String, TrayCommand)>>,
+    /// Icone de zone de notification active : fermer la fenetre laisse IronCloak tourner
+    pub tray_active: AtomicBool,
+    /// Action d'un simple clic sur l'icone (gui.tray_click_action, appliquee sans redemarrage)
+    pub tray_click_action: Mutex<TrayClickAction>,
+    /// Proxy en pause : les nouvelles connexions SOCKS sont refusees
+    pub paused: AtomicBool,
+    /// Fermer aussi les connexions actives lors de la mise en pause (proxy.pause_closes_connections)
+    pub pause_closes_connections: AtomicBool,
+    /// Signal de fermeture des connexions SOCKS actives
+    pub close_connections: tokio::sync::Notify,
+    /// Jeton d'isolation des flux SOCKS, renouvele a chaque "nouvelle identite"
+    pub isolation: Mutex<IsolationToken>,
+    /// Jetons d'isolation par client et/ou destination (proxy.isolate_*)
+    pub keyed_isolation: Mutex<HashMap<IsolationKey, IsolationToken>>,
+    /// Circuits recemment utilises par les flux SOCKS (onglet "Circuits")
+    pub circuits: Mutex<Vec<CircuitEntry>>,
+    /// Connexions SOCKS actives (onglet "Connexions")
+    pub connections: Mutex<Vec<ConnectionEntry>>,
+    /// Connexions fermees, de la plus ancienne a la plus recente (onglet "Historique")
+    pub history: Mutex<VecDeque<HistoryEntry>>,
+    /// Taille maximale de l'historique (history.size)
+    pub history_size: AtomicUsize,
+    /// Flux etablis depuis le demarrage par pays du relais de sortie (onglet "Statistiques")
+    pub exit_countries: Mutex<HashMap<String, u64>>,
+    /// Trafic par domaine de destination, masque selon logging.redact_destinations
+    pub destinations: Mutex<HashMap<String, DestinationStats>>,
+    /// Octets relayes depuis le demarrage (client → Tor, Tor → client)
+    pub bytes_up: AtomicU64,
+    pub bytes_down: AtomicU64,
+    /// Debit mesure sur le dernier intervalle, en octets par seconde
+    pub rate_up: AtomicU64,
+    pub rate_down: AtomicU64,
+    /// Connexions fermees faute de handshake SOCKS termine dans le delai
+    pub handshake_timeouts: AtomicU64,
+    /// Services onion heberges et publies
+    pub onion_services: Mutex<Vec<OnionServiceStatus>>,
+    /// Runtime et client Tor (None tant que l'amorcage n'est pas termine)
+    pub backend: Mutex<Option<Backend>>,
+    /// Dernier test "Verifier Tor"
+    pub tor_check: Mutex<Option<TorCheckState>>,
+    /// Dernier test de debit
+    pub speed_test: Mutex<Option<SpeedTestState>>,
+    /// Derniere verification des mises a jour
+    pub update: Mutex<Option<UpdateState>>,
+    /// Derniere mesure de latence des circuits (None avant la premiere mesure)
+    pub health: Mutex<Option<HealthSample>>,
+    /// Quota de donnees atteint pour la periode en cours (None : sous le quota ou sans quota)
+    pub quota_reached: Mutex<Option<QuotaPeriod>>,
+    /// Demande de ponts a BridgeDB en cours ou terminee
+    pub moat: Mutex<Option<MoatState>>,
+    /// Saisie de la phrase secrete de l'etat chiffre demandee a la fenetre, et reponse
+    pub passphrase_prompt: Mutex<Option<PassphrasePrompt>>,
+    pub passphrase: Mutex<Option<Zeroizing<String>>>,
+    pub passphrase_ready: tokio::sync::Notify,
 }
 
 impl AppState {
     /// Cree un nouvel etat avec le port initial et le chemin de config
     pub fn new(port: u16, config_path: PathBuf, language: String) -> Self {
         Self {
-            connected: AtomicBool::new(false),
+            status: AtomicU8::new(ProxyStatus::Bootstrapping as u8),
+            bootstrap: AtomicU8::new(0),
+            offline: AtomicBool::new(false),
+            health_failures: AtomicU32::new(0),
+            rebuilding: AtomicBool::new(false),
+            captive_portal: Mutex::new(None),
+            last_error: Mutex::new(None),
+            retry: tokio::sync::Notify::new(),
             port: AtomicU16::new(port),
             pending_port: AtomicU16::new(0),
-            quit: AtomicBool::new(false),
+            quit: watch::Sender::new(false),
+            changed: watch::Sender::new(0),
+            events: broadcast::Sender::new(EVENT_CAPACITY),
             config_path,
             language: Mutex::new(language),
-            tray_quit_menu_id: Mutex::new(None),
+            tray_menu: Mutex::new(Vec::new()),
+            tray_active: AtomicBool::new(false),
+            tray_click_action: Mutex::new(TrayClickAction::default()),
+            paused: AtomicBool::new(false),
+            pause_closes_connections: AtomicBool::new(false),
+            close_connections: tokio::sync::Notify::new(),
+            isolation: Mutex::new(IsolationToken::new()),
+            keyed_isolation: Mutex::new(HashMap::new()),
+            circuits: Mutex::new(Vec::new()),
+            connections: Mutex::new(Vec::new()),
+            history: Mutex::new(VecDeque::new()),
+            history_size: AtomicUsize::new(0),
+            exit_countries: Mutex::new(HashMap::new()),
+            destinations: Mutex::new(HashMap::new()),
+            bytes_up: AtomicU64::new(0),
+            bytes_down: AtomicU64::new(0),
+            rate_up: AtomicU64::new(0),
+            rate_down: AtomicU64::new(0),
+            handshake_timeouts: AtomicU64::new(0),
+            onion_services: Mutex::new(Vec::new()),
+            backend: Mutex::new(None),
+            tor_check: Mutex::new(None),
+            speed_test: Mutex::new(None),
+            update: Mutex::new(None),
+            health: Mutex::new(None),
+            quota_reached: Mutex::new(None),
+            moat: Mutex::new(None),
+            passphrase_prompt: Mutex::new(None),
+            passphrase: Mutex::new(None),
+            passphrase_ready: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Etat courant : la perte du reseau masque tous les autres, la pause seulement l'etat
+    /// connecte
+    pub fn status(&self) -> ProxyStatus {
+        if self.is_offline() {
+            return ProxyStatus::Offline;
+        }
+        match self.status.load(Ordering::Relaxed) {
+            s if s == ProxyStatus::Connected as u8 && self.is_paused() => ProxyStatus::Paused,
+            s if s == ProxyStatus::Connected as u8 => ProxyStatus::Connected,
+            s if s == ProxyStatus::Failed as u8 => ProxyStatus::Failed,
+            _ => ProxyStatus::Bootstrapping,
         }
     }
 
+    /// Client Tor pret (pause comprise)
     pub fn is_connected(&self) -> bool {
-        self.connected.load(Ordering::Relaxed)
+        self.status.load(Ordering::Relaxed) == ProxyStatus::Connected as u8
+    }
+
+    /// Etat du backend ; la pause passe par set_paused()
+    pub fn set_status(&self, status: ProxyStatus) {
+        if self.status.swap(status as u8, Ordering::Relaxed) != status as u8 {
+            self.notify_changed();
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        if self.offline.swap(offline, Ordering::Relaxed) != offline {
+            self.notify_changed();
+        }
+    }
+
+    /// Circuits en reconstruction : nouvelle identite en cours ou reseau perdu
+    pub fn is_rebuilding(&self) -> bool {
+        self.rebuilding.load(Ordering::Relaxed) || self.is_offline()
+    }
+
+    /// Fin (ou debut) de la reconstruction apres une nouvelle identite
+    pub fn set_rebuilding(&self, rebuilding: bool) {
+        if self.rebuilding.swap(rebuilding, Ordering::Relaxed) != rebuilding {
+            self.notify_changed();
+        }
+    }
+
+    /// Attend des circuits utilisables (reseau present, pas de reconstruction en cours)
+    pub async fn wait_until_rebuilt(&self) {
+        let mut changes = self.subscribe_changes();
+        while self.is_rebuilding() {
+            let _ = changes.changed().await;
+        }
+    }
+
+    /// Attend la perte du reseau (interrompt un amorcage voue a l'echec)
+    pub async fn wait_for_offline(&self) {
+        let mut changes = self.subscribe_changes();
+        while !self.is_offline() {
+            let _ = changes.changed().await;
+        }
+    }
+
+    pub fn captive_portal(&self) -> Option<String> {
+        self.captive_portal.lock().unwrap().clone()
+    }
+
+    /// Propose (ou retire) l'ouverture de la page de connexion d'un portail captif
+    pub fn set_captive_portal(&self, url: Option<String>) {
+        *self.captive_portal.lock().unwrap() = url;
+        self.notify_changed();
+    }
+
+    /// Echec d'une etape du backend : affiche dans la fenetre et l'infobulle jusqu'a la
+    /// nouvelle tentative
+    pub fn set_failed(&self, stage: FailedStage, message: String) {
+        *self.last_error.lock().unwrap() = Some(BackendError { stage, message });
+        self.set_status(ProxyStatus::Failed);
+    }
+
+    pub fn last_error(&self) -> Option<BackendError> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Relance l'etape en echec (bouton "Reessayer") ; sans effet hors echec
+    pub fn request_retry(&self) {
+        let Some(error) = self.last_error.lock().unwrap().take() else {
+            return;
+        };
+        tracing::info!("{}", crate::tl!("app.retry", stage = error.stage.as_str()));
+        self.set_status(match error.stage {
+            FailedStage::Listen => ProxyStatus::Connected,
+            FailedStage::Unlock | FailedStage::Bootstrap => ProxyStatus::Bootstrapping,
+        });
+        self.retry.notify_one();
+    }
+
+    /// Attend la nouvelle tentative apres set_failed() ; false si l'arret est demande avant
+    pub async fn wait_for_retry(&self) -> bool {
+        tokio::select! {
+            _ = self.retry.notified() => true,
+            _ = self.wait_for_quit() => false,
+        }
+    }
+
+    pub fn bootstrap_percent(&self) -> u8 {
+        self.bootstrap.load(Ordering::Relaxed)
     }
 
-    pub fn set_connected(&self, val: bool) {
-        self.connected.store(val, Ordering::Relaxed);
+    pub fn set_bootstrap_percent(&self, percent: u8) {
+        if self.bootstrap.swap(percent, Ordering::Relaxed) != percent {
+            self.publish(StateEvent::Bootstrap(percent));
+            self.notify_changed();
+        }
     }
 
     pub fn get_port(&self) -> u16 {
@@ -67,18 +566,523 @@ impl AppState {
     }
 
     pub fn should_quit(&self) -> bool {
-        self.quit.load(Ordering::Relaxed)
+        *self.quit.borrow()
     }
 
     pub fn request_quit(&self) {
-        self.quit.store(true, Ordering::Relaxed);
+        self.quit.send_replace(true);
+        self.notify_changed();
+    }
+
+    /// Attend la demande d'arret (depuis l'interface graphique ou la zone de notification)
+    pub async fn wait_for_quit(&self) {
+        let _ = self.quit.subscribe().wait_for(|quit| *quit).await;
+    }
+
+    /// Signale un changement d'etat aux boucles des zones de notification
+    pub fn notify_changed(&self) {
+        self.changed.send_modify(|count| *count = count.wrapping_add(1));
+    }
+
+    /// Recepteur des changements d'etat (voir notify_changed)
+    pub fn subscribe_changes(&self) -> watch::Receiver<u64> {
+        self.changed.subscribe()
+    }
+
+    /// Diffuse un evenement ; sans abonne, il est simplement perdu
+    pub fn publish(&self, event: StateEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Recepteur des evenements (voir publish)
+    pub fn subscribe_events(&self) -> broadcast::Receiver<StateEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn set_tray_menu(&self, items: Vec<(String, TrayCommand)>) {
+        *self.tray_menu.lock().unwrap() = items;
+    }
+
+    pub fn is_tray_active(&self) -> bool {
+        self.tray_active.load(Ordering::Relaxed)
+    }
+
+    pub fn set_tray_active(&self, val: bool) {
+        self.tray_active.store(val, Ordering::Relaxed);
+    }
+
+    pub fn tray_click_action(&self) -> TrayClickAction {
+        *self.tray_click_action.lock().unwrap()
+    }
+
+    pub fn set_tray_click_action(&self, action: TrayClickAction) {
+        *self.tray_click_action.lock().unwrap() = action;
+    }
+
+    /// Commande associee a un identifiant d'entree du menu systray
+    pub fn tray_command(&self, menu_id: &str) -> Option<TrayCommand> {
+        self.tray_menu
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, _)| id == menu_id)
+            .map(|(_, command)| *command)
     }
 
-    pub fn set_tray_quit_menu_id(&self, id: String) {
-        *self.tray_quit_menu_id.lock().unwrap() = Some(id);
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
     }
 
-    pub fn get_tray_quit_menu_id(&self) -> Option<String> {
-        self.tray_quit_menu_id.lock().unwrap().clone()
+    /// Met le proxy en pause (nouvelles connexions refusees, connexions actives fermees
+    /// si proxy.pause_closes_connections) ou le relance
+    pub fn set_paused(&self, paused: bool) {
+        if self.paused.swap(paused, Ordering::Relaxed) == paused {
+            return;
+        }
+        if paused {
+            tracing::info!("{}", crate::tl!("gui.paused_log"));
+            if self.pause_closes_connections.load(Ordering::Relaxed) {
+                self.close_connections.notify_waiters();
+            }
+        } else {
+            tracing::info!("{}", crate::tl!("gui.resumed_log"));
+        }
+        self.notify_changed();
+    }
+
+    /// Inverse l'etat de pause ; retourne le nouvel etat
+    pub fn toggle_pause(&self) -> bool {
+        let paused = !self.is_paused();
+        self.set_paused(paused);
+        paused
+    }
+
+    pub fn set_pause_closes_connections(&self, val: bool) {
+        self.pause_closes_connections.store(val, Ordering::Relaxed);
+    }
+
+    /// Jeton d'isolation a appliquer aux nouveaux flux SOCKS
+    pub fn isolation_token(&self) -> IsolationToken {
+        *self.isolation.lock().unwrap()
+    }
+
+    /// Jeton d'isolation propre a une cle (client, hote, port selon les options) : comme
+    /// IsolateClientAddr/IsolateDestAddr/IsolateDestPort de Tor, deux cles differentes ne
+    /// partagent jamais un circuit
+    pub fn keyed_isolation_token(&self, key: IsolationKey) -> IsolationToken {
+        let mut tokens = self.keyed_isolation.lock().unwrap();
+        if tokens.len() >= MAX_DEST_ISOLATION_TOKENS {
+            tokens.clear();
+        }
+        *tokens.entry(key).or_insert_with(IsolationToken::new)
+    }
+
+    /// Nouvelle identite : les flux suivants n'emprunteront plus les circuits existants
+    pub fn new_identity(&self) {
+        *self.isolation.lock().unwrap() = IsolationToken::new();
+        self.keyed_isolation.lock().unwrap().clear();
+        self.circuits.lock().unwrap().clear();
+        self.set_rebuilding(true);
+        tracing::info!("{}", crate::tl!("gui.new_identity_log"));
+    }
+
+    /// Enregistre le circuit auquel un flux vient d'etre attache
+    pub fn record_circuit(&self, info: CircuitInfo) {
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock().unwrap();
+        if let Some(entry) = circuits.iter_mut().find(|c| c.info.id == info.id) {
+            entry.last_used = now;
+            entry.streams += 1;
+        } else {
+            circuits.push(CircuitEntry {
+                info,
+                first_seen: now,
+                last_used: now,
+                streams: 1,
+            });
+        }
+    }
+
+    /// Retourne les circuits recents, du plus ancien au plus recent, en oubliant les perimes
+    pub fn get_circuits(&self) -> Vec<CircuitEntry> {
+        let mut circuits = self.circuits.lock().unwrap();
+        circuits.retain(|c| c.last_used.elapsed() < CIRCUIT_STALE_AFTER);
+        circuits.clone()
+    }
+
+    /// Relais de sortie du circuit le plus recemment utilise (pays affiche dans le statut)
+    pub fn current_exit(&self) -> Option<RelayInfo> {
+        self.circuits
+            .lock()
+            .unwrap()
+            .iter()
+            .max_by_key(|c| c.last_used)
+            .and_then(|c| c.info.relays.last().cloned())
+    }
+
+    /// Ajoute une connexion dont le flux Tor vient d'etre etabli
+    pub fn add_connection(&self, entry: ConnectionEntry) {
+        self.connections.lock().unwrap().push(entry);
+        self.notify_changed();
+    }
+
+    /// Retire une connexion fermee (sans effet si elle n'a jamais ete ajoutee)
+    pub fn remove_connection(&self, conn_id: u64) {
+        self.connections.lock().unwrap().retain(|c| c.conn_id != conn_id);
+        self.notify_changed();
+    }
+
+    /// Nombre de connexions SOCKS actives
+    pub fn active_connections(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+
+    pub fn get_connections(&self) -> Vec<ConnectionEntry> {
+        self.connections.lock().unwrap().clone()
+    }
+
+    pub fn set_history_size(&self, size: usize) {
+        self.history_size.store(size, Ordering::Relaxed);
+    }
+
+    /// Ajoute une connexion fermee a l'historique, en oubliant les plus anciennes au-dela de
+    /// history.size
+    pub fn record_history(&self, entry: HistoryEntry) {
+        let size = self.history_size.load(Ordering::Relaxed);
+        if size == 0 {
+            return;
+        }
+        let mut history = self.history.lock().unwrap();
+        history.push_back(entry);
+        while history.len() > size {
+            history.pop_front();
+        }
+    }
+
+    /// Historique des connexions fermees, la plus recente en premier
+    pub fn get_history(&self) -> Vec<HistoryEntry> {
+        self.history.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn clear_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+
+    /// Compte un flux etabli pour le pays de son relais de sortie ("??" si inconnu)
+    pub fn record_exit_country(&self, country: Option<&str>) {
+        let country = country.unwrap_or("??").to_string();
+        *self.exit_countries.lock().unwrap().entry(country).or_insert(0) += 1;
+    }
+
+    /// Flux par pays de sortie, du plus frequent au moins frequent
+    pub fn exit_country_stats(&self) -> Vec<(String, u64)> {
+        let mut stats: Vec<(String, u64)> =
+            self.exit_countries.lock().unwrap().iter().map(|(cc, n)| (cc.clone(), *n)).collect();
+        stats.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats
+    }
+
+    pub fn reset_exit_country_stats(&self) {
+        self.exit_countries.lock().unwrap().clear();
+    }
+
+    /// Ajoute une connexion fermee au trafic de son domaine
+    pub fn record_destination(&self, domain: &str, up: u64, down: u64) {
+        let mut destinations = self.destinations.lock().unwrap();
+        if !destinations.contains_key(domain) && destinations.len() >= MAX_DESTINATIONS {
+            let smallest = destinations.iter().min_by_key(|(_, stats)| stats.total()).map(|(key, _)| key.clone());
+            if let Some(smallest) = smallest {
+                destinations.remove(&smallest);
+            }
+        }
+        let stats = destinations.entry(domain.to_string()).or_default();
+        stats.connections += 1;
+        stats.up += up;
+        stats.down += down;
+    }
+
+    /// Domaines les plus gourmands (octets dans les deux sens), au plus `limit`
+    pub fn top_destinations(&self, limit: usize) -> Vec<(String, DestinationStats)> {
+        let mut top: Vec<(String, DestinationStats)> =
+            self.destinations.lock().unwrap().iter().map(|(domain, stats)| (domain.clone(), *stats)).collect();
+        top.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+
+    pub fn reset_destination_stats(&self) {
+        self.destinations.lock().unwrap().clear();
+    }
+
+    /// Octets relayes depuis le demarrage (montant, descendant)
+    pub fn traffic_totals(&self) -> (u64, u64) {
+        (self.bytes_up.load(Ordering::Relaxed), self.bytes_down.load(Ordering::Relaxed))
+    }
+
+    /// Compte une connexion fermee faute de handshake SOCKS termine a temps
+    pub fn record_handshake_timeout(&self) {
+        self.handshake_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Connexions fermees faute de handshake SOCKS termine a temps, depuis le demarrage
+    pub fn handshake_timeouts(&self) -> u64 {
+        self.handshake_timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Debit courant en octets par seconde (montant, descendant)
+    pub fn throughput(&self) -> (u64, u64) {
+        (self.rate_up.load(Ordering::Relaxed), self.rate_down.load(Ordering::Relaxed))
+    }
+
+    pub fn set_throughput(&self, up: u64, down: u64) {
+        let previous = (
+            self.rate_up.swap(up, Ordering::Relaxed),
+            self.rate_down.swap(down, Ordering::Relaxed),
+        );
+        // Un echantillon par mesure, meme inchange : les tableaux de bord tracent des courbes
+        self.publish(StateEvent::Bandwidth(up, down));
+        if previous != (up, down) {
+            self.notify_changed();
+        }
+    }
+
+    /// Enregistre (ou remplace) le statut d'un service onion publie
+    pub fn set_onion_service(&self, status: OnionServiceStatus) {
+        let mut services = self.onion_services.lock().unwrap();
+        services.retain(|s| s.nickname != status.nickname);
+        services.push(status);
+    }
+
+    pub fn get_onion_services(&self) -> Vec<OnionServiceStatus> {
+        self.onion_services.lock().unwrap().clone()
+    }
+
+    pub fn set_backend(&self, backend: Backend) {
+        *self.backend.lock().unwrap() = Some(backend);
+    }
+
+    pub fn get_backend(&self) -> Option<Backend> {
+        self.backend.lock().unwrap().clone()
+    }
+
+    /// Oublie le client Tor a l'arret, pour qu'il soit libere avec le runtime
+    pub fn clear_backend(&self) {
+        *self.backend.lock().unwrap() = None;
+    }
+
+    /// Lance le test "Verifier Tor" en tache de fond.
+    /// Retourne false si le client Tor n'est pas encore pret ou si un test est deja en cours.
+    pub fn request_tor_check(self: &Arc<Self>) -> bool {
+        let Some(backend) = self.get_backend() else {
+            return false;
+        };
+        {
+            let mut check = self.tor_check.lock().unwrap();
+            if matches!(*check, Some(TorCheckState::Running)) {
+                return false;
+            }
+            *check = Some(TorCheckState::Running);
+        }
+
+        let state = Arc::clone(self);
+        backend.runtime.spawn(async move {
+            let result = match crate::probe::check_tor(&backend.tor_client).await {
+                Ok(check) => TorCheckState::Done(check),
+                Err(e) => TorCheckState::Failed(e.to_string()),
+            };
+            *state.tor_check.lock().unwrap() = Some(result);
+        });
+        true
+    }
+
+    pub fn get_tor_check(&self) -> Option<TorCheckState> {
+        self.tor_check.lock().unwrap().clone()
+    }
+
+    /// Lance le test de debit en tache de fond.
+    /// Retourne false si le client Tor n'est pas encore pret ou si un test est deja en cours.
+    pub fn request_speed_test(self: &Arc<Self>) -> bool {
+        let Some(backend) = self.get_backend() else {
+            return false;
+        };
+        {
+            let mut test = self.speed_test.lock().unwrap();
+            if matches!(*test, Some(SpeedTestState::Running)) {
+                return false;
+            }
+            *test = Some(SpeedTestState::Running);
+        }
+
+        let state = Arc::clone(self);
+        backend.runtime.spawn(async move {
+            let result = match crate::probe::speed_test(&backend.tor_client).await {
+                Ok(test) => SpeedTestState::Done(test),
+                Err(e) => {
+                    tracing::warn!("{}", crate::tl!("probe.speed_test_failed", error = format!("{:#}", e)));
+                    SpeedTestState::Failed(e.to_string())
+                }
+            };
+            *state.speed_test.lock().unwrap() = Some(result);
+        });
+        true
+    }
+
+    pub fn get_speed_test(&self) -> Option<SpeedTestState> {
+        self.speed_test.lock().unwrap().clone()
+    }
+
+    pub fn set_update_state(&self, update: UpdateState) {
+        *self.update.lock().unwrap() = Some(update);
+        self.notify_changed();
+    }
+
+    pub fn set_health(&self, sample: HealthSample) {
+        if sample.latency.is_some() {
+            self.health_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.health_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        *self.health.lock().unwrap() = Some(sample);
+        self.notify_changed();
+    }
+
+    /// Mesures de sante consecutives sans reponse (circuits inutilisables)
+    pub fn health_failures(&self) -> u32 {
+        self.health_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn get_health(&self) -> Option<HealthSample> {
+        *self.health.lock().unwrap()
+    }
+
+    pub fn set_quota_reached(&self, period: Option<QuotaPeriod>) {
+        let previous = std::mem::replace(&mut *self.quota_reached.lock().unwrap(), period);
+        if previous != period {
+            self.notify_changed();
+        }
+    }
+
+    pub fn get_quota_reached(&self) -> Option<QuotaPeriod> {
+        *self.quota_reached.lock().unwrap()
+    }
+
+    /// Demande un captcha a BridgeDB dans un thread dedie (hors Tor : le client Tor n'a pas
+    /// besoin d'etre amorce). Retourne false si une requete est deja en cours.
+    pub fn request_moat_challenge(self: &Arc<Self>) -> bool {
+        if !self.begin_moat(MoatState::Fetching) {
+            return false;
+        }
+        let state = Arc::clone(self);
+        std::thread::spawn(move || {
+            let config = crate::config::IronCloakConfig::load(&state.config_path).unwrap_or_default();
+            let result = match crate::moat::fetch_challenge(&config.tor.bridges.moat) {
+                Ok(challenge) => MoatState::Challenge(challenge),
+                Err(e) => MoatState::Failed(format!("{:#}", e)),
+            };
+            *state.moat.lock().unwrap() = Some(result);
+        });
+        true
+    }
+
+    /// Envoie la solution du captcha affiche. Retourne false sans captcha en attente.
+    pub fn submit_moat_solution(self: &Arc<Self>, solution: String) -> bool {
+        let challenge = match self.get_moat_state() {
+            Some(MoatState::Challenge(challenge)) => challenge,
+            _ => return false,
+        };
+        if !self.begin_moat(MoatState::Checking) {
+            return false;
+        }
+        let state = Arc::clone(self);
+        std::thread::spawn(move || {
+            let config = crate::config::IronCloakConfig::load(&state.config_path).unwrap_or_default();
+            let result = match crate::moat::check_solution(&config.tor.bridges.moat, &challenge, &solution) {
+                Ok(bridges) => MoatState::Done(bridges),
+                Err(e) => MoatState::Failed(format!("{:#}", e)),
+            };
+            *state.moat.lock().unwrap() = Some(result);
+        });
+        true
+    }
+
+    /// Passe a une etape reseau de la demande de ponts, sauf si une autre est en cours
+    fn begin_moat(&self, step: MoatState) -> bool {
+        let mut moat = self.moat.lock().unwrap();
+        if matches!(*moat, Some(MoatState::Fetching | MoatState::Checking)) {
+            return false;
+        }
+        *moat = Some(step);
+        true
+    }
+
+    pub fn get_moat_state(&self) -> Option<MoatState> {
+        self.moat.lock().unwrap().clone()
+    }
+
+    /// Oublie la demande de ponts (fenetre fermee ou ponts enregistres)
+    pub fn clear_moat_state(&self) {
+        let mut moat = self.moat.lock().unwrap();
+        if !matches!(*moat, Some(MoatState::Fetching | MoatState::Checking)) {
+            *moat = None;
+        }
+    }
+
+    /// Demande la phrase secrete de l'etat chiffre a la fenetre et attend sa saisie
+    pub async fn ask_passphrase(&self, first_time: bool, error: Option<String>) -> Zeroizing<String> {
+        *self.passphrase_prompt.lock().unwrap() = Some(PassphrasePrompt { first_time, error });
+        self.notify_changed();
+        loop {
+            if let Some(passphrase) = self.passphrase.lock().unwrap().take() {
+                *self.passphrase_prompt.lock().unwrap() = None;
+                self.notify_changed();
+                return passphrase;
+            }
+            self.passphrase_ready.notified().await;
+        }
+    }
+
+    pub fn get_passphrase_prompt(&self) -> Option<PassphrasePrompt> {
+        self.passphrase_prompt.lock().unwrap().clone()
+    }
+
+    /// Transmet au backend la phrase secrete saisie dans la fenetre
+    pub fn provide_passphrase(&self, passphrase: String) {
+        *self.passphrase.lock().unwrap() = Some(Zeroizing::new(passphrase));
+        self.passphrase_ready.notify_one();
+    }
+
+    pub fn get_update_state(&self) -> Option<UpdateState> {
+        self.update.lock().unwrap().clone()
+    }
+
+    /// Version plus recente disponible, si la derniere verification en a trouve une
+    pub fn update_available(&self) -> Option<String> {
+        match self.get_update_state() {
+            Some(UpdateState::Available(version)) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Verifie les mises a jour a la demande, en tache de fond, aupres de `release_url` (celle de
+    /// la configuration deja chargee : le runtime, dans le bac a sable, ne la relit pas).
+    /// Retourne false si le client Tor n'est pas encore pret ou si une verification est en cours.
+    pub fn request_update_check(self: &Arc<Self>, release_url: String) -> bool {
+        let Some(backend) = self.get_backend() else {
+            return false;
+        };
+        {
+            let mut update = self.update.lock().unwrap();
+            if matches!(*update, Some(UpdateState::Running)) {
+                return false;
+            }
+            *update = Some(UpdateState::Running);
+        }
+
+        let state = Arc::clone(self);
+        backend.runtime.spawn(async move {
+            let result = crate::update::check(&release_url, &backend.tor_client).await;
+            state.set_update_state(result);
+        });
+        true
     }
 }
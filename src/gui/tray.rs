@@ -1,66 +1,95 @@
 // Icone systray Windows avec menu contextuel.
 // Utilise tray-icon pour l'icone et une boucle de messages Win32.
-// L'icone change selon l'etat de connexion Tor (on/off).
-// Double-clic sur l'icone ouvre la fenetre de configuration.
+// L'icone change selon l'etat du proxy (amorcage, connecte, en pause, erreur).
+// L'infobulle indique le port et le pays du relais de sortie courant.
+// Double-clic sur l'icone ouvre la fenetre de configuration ; simple clic selon
+// gui.tray_click_action (fenetre, pause/reprise ou nouvelle identite ; fenetre en cas d'erreur).
+// Le menu propose aussi : nouvelle identite, pause/reprise, dossier des journaux,
+// copie de l'adresse du proxy.
 
 #![cfg(windows)]
 
 use std::sync::Arc;
 use tray_icon::{
     menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
-    TrayIconBuilder, TrayIconEvent, Icon,
+    MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent, Icon,
 };
 
-use crate::gui::state::AppState;
-
-// Icones PNG embarquees dans le binaire
-const ICON_ON_PNG: &[u8] = include_bytes!("../../icon_256_on.png");
-const ICON_OFF_PNG: &[u8] = include_bytes!("../../icon_256_off.png");
+use crate::gui::{
+    click_command, error_text, execute_command, health_text, quota_text, status_icon, status_text, throughput_text,
+    tray_tooltip,
+};
+use crate::gui::state::{AppState, ProxyStatus, TrayCommand};
 
-/// Decode un PNG en Icon compatible tray-icon
-fn load_icon(png_data: &[u8]) -> Icon {
-    let img = image::load_from_memory(png_data)
-        .expect("Erreur de decodage de l'icone PNG")
-        .into_rgba8();
+/// Icone d'un etat du proxy, compatible tray-icon
+fn load_icon(status: ProxyStatus) -> Icon {
+    let img = status_icon(status);
     let (w, h) = img.dimensions();
     Icon::from_rgba(img.into_raw(), w, h).expect("Erreur de creation de l'icone")
 }
 
 /// Lance la boucle systray Windows. Bloquant jusqu'a la demande de fermeture.
 pub fn run_tray(state: Arc<AppState>) {
-    let icon_on = load_icon(ICON_ON_PNG);
-    let icon_off = load_icon(ICON_OFF_PNG);
+    let mut last_status = state.status();
+    let mut last_status_text = status_text(&state);
 
     // Construction du menu contextuel
-    let status_item = MenuItem::new(crate::t!("gui.disconnected"), false, None);
+    let status_item = MenuItem::new(last_status_text.clone(), false, None);
     let configure_item = MenuItem::new(crate::t!("gui.configure"), true, None);
+    let identity_item = MenuItem::new(crate::t!("gui.new_identity"), true, None);
+    let pause_item = MenuItem::new(crate::t!("gui.pause"), true, None);
+    let logs_item = MenuItem::new(crate::t!("gui.open_logs"), true, None);
+    let copy_item = MenuItem::new(crate::t!("gui.copy_address"), true, None);
     let quit_item = MenuItem::new(crate::t!("gui.quit"), true, None);
 
     let menu = Menu::new();
     let _ = menu.append(&status_item);
     let _ = menu.append(&PredefinedMenuItem::separator());
     let _ = menu.append(&configure_item);
+    let _ = menu.append(&identity_item);
+    let _ = menu.append(&pause_item);
+    let _ = menu.append(&logs_item);
+    let _ = menu.append(&copy_item);
     let _ = menu.append(&PredefinedMenuItem::separator());
     let _ = menu.append(&quit_item);
 
-    // Creation de l'icone systray (demarre en mode "off")
+    // Creation de l'icone systray (amorcage en general)
     let _tray_icon = TrayIconBuilder::new()
         .with_menu(Box::new(menu))
-        .with_tooltip(format!("IronCloak :{}", state.get_port()))
-        .with_icon(icon_off.clone())
+        .with_tooltip(tray_tooltip(&state))
+        .with_icon(load_icon(last_status))
         .build()
         .expect("Erreur de creation du systray");
+    state.set_tray_active(true);
+
+    // Stocker les IDs du menu dans l'etat partage
+    // pour que la fenetre egui puisse traiter ces evenements pendant qu'elle est ouverte
+    state.set_tray_menu(vec![
+        (configure_item.id().as_ref().to_string(), TrayCommand::Configure),
+        (identity_item.id().as_ref().to_string(), TrayCommand::NewIdentity),
+        (pause_item.id().as_ref().to_string(), TrayCommand::TogglePause),
+        (logs_item.id().as_ref().to_string(), TrayCommand::OpenLogs),
+        (copy_item.id().as_ref().to_string(), TrayCommand::CopyAddress),
+        (quit_item.id().as_ref().to_string(), TrayCommand::Quit),
+    ]);
+
+    let mut was_paused = false;
+    let mut prompt_shown = false;
+    let mut last_tooltip = String::new();
+
+    // Les changements d'etat (connexion, pause, debit...) reveillent la boucle par un message
+    // poste au thread du systray : aucune scrutation periodique
+    let tray_thread = unsafe { winapi::um::processthreadsapi::GetCurrentThreadId() };
+    let mut changes = state.subscribe_changes();
+    std::thread::spawn(move || {
+        while futures::executor::block_on(changes.changed()).is_ok() {
+            unsafe {
+                winapi::um::winuser::PostThreadMessageW(tray_thread, winapi::um::winuser::WM_APP, 0, 0);
+            }
+        }
+    });
 
-    let configure_id = configure_item.id().clone();
-    let quit_id = quit_item.id().clone();
-
-    // Stocker l'ID du menu "Quitter" dans l'etat partage
-    // pour que la fenetre egui puisse traiter cet evenement pendant qu'elle est ouverte
-    state.set_tray_quit_menu_id(quit_id.as_ref().to_string());
-
-    let mut was_connected = false;
-
-    // Boucle de messages Win32 non-bloquante
+    // Boucle de messages Win32 : attente bloquante des messages et des changements d'etat
     loop {
         // Traitement des messages Windows (necessaire pour le systray)
         unsafe {
@@ -78,23 +107,41 @@ pub fn run_tray(state: Arc<AppState>) {
             }
         }
 
-        // Verifier les evenements de clic sur l'icone (double-clic = ouvrir config)
+        // Verifier les evenements de clic sur l'icone (double-clic = ouvrir config ; simple
+        // clic = gui.tray_click_action)
         let mut open_config = false;
         while let Ok(event) = TrayIconEvent::receiver().try_recv() {
-            if matches!(event, TrayIconEvent::DoubleClick { .. }) {
-                open_config = true;
+            match event {
+                TrayIconEvent::DoubleClick { .. } => open_config = true,
+                TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } => match click_command(&state) {
+                    TrayCommand::Configure => open_config = true,
+                    command => execute_command(&state, command),
+                },
+                _ => {}
             }
         }
 
         // Verifier les evenements du menu
         while let Ok(event) = MenuEvent::receiver().try_recv() {
-            if event.id == configure_id {
-                open_config = true;
-            } else if event.id == quit_id {
-                state.request_quit();
+            match state.tray_command(event.id.as_ref()) {
+                Some(TrayCommand::Configure) => open_config = true,
+                Some(command) => execute_command(&state, command),
+                None => {}
             }
         }
 
+        // Etat chiffre ou portail captif : la fenetre s'ouvre d'elle-meme pour la saisie de la
+        // phrase secrete ou l'ouverture de la page de connexion
+        let prompt = state.get_passphrase_prompt().is_some() || state.captive_portal().is_some();
+        if prompt && !prompt_shown {
+            open_config = true;
+        }
+        prompt_shown = prompt;
+
         // Ouvrir la fenetre de configuration si demande
         if open_config && !state.should_quit() {
             let state_clone = Arc::clone(&state);
@@ -106,26 +153,66 @@ pub fn run_tray(state: Arc<AppState>) {
             break;
         }
 
-        // Mise a jour de l'icone selon l'etat de connexion
-        let connected = state.is_connected();
-        if connected != was_connected {
-            was_connected = connected;
-            let new_icon = if connected {
-                icon_on.clone()
-            } else {
-                icon_off.clone()
-            };
-            let _ = _tray_icon.set_icon(Some(new_icon));
+        // Mise a jour de l'icone et de l'entree d'etat (progression de l'amorcage comprise)
+        let status = state.status();
+        if status != last_status {
+            last_status = status;
+            let _ = _tray_icon.set_icon(Some(load_icon(status)));
+        }
+        let text = status_text(&state);
+        if text != last_status_text {
+            status_item.set_text(&text);
+            last_status_text = text;
+        }
 
-            let status_text = if connected {
-                crate::t!("gui.connected")
+        // Libelle de l'entree pause/reprise
+        let paused = state.is_paused();
+        if paused != was_paused {
+            was_paused = paused;
+            pause_item.set_text(if paused {
+                crate::t!("gui.resume")
             } else {
-                crate::t!("gui.disconnected")
-            };
-            status_item.set_text(status_text);
+                crate::t!("gui.pause")
+            });
         }
 
-        // Attendre 50ms pour ne pas saturer le CPU
-        std::thread::sleep(std::time::Duration::from_millis(50));
+        // Infobulle : amorcage ou erreur, puis etat, connexions actives, pays de sortie, debit,
+        // latence et quota de donnees atteint
+        let mut tooltip = tray_tooltip(&state);
+        if let Some(error) = error_text(&state) {
+            tooltip.push('\n');
+            tooltip.push_str(&error);
+        }
+        if let Some(throughput) = throughput_text(&state) {
+            tooltip.push('\n');
+            tooltip.push_str(&throughput);
+        }
+        if let Some(health) = health_text(&state) {
+            tooltip.push('\n');
+            tooltip.push_str(&health);
+        }
+        if let Some(quota) = quota_text(&state) {
+            tooltip.push('\n');
+            tooltip.push_str(&quota);
+        }
+        if let Some(version) = state.update_available() {
+            tooltip.push('\n');
+            tooltip.push_str(&crate::t!("gui.update_available", version = version));
+        }
+        if tooltip != last_tooltip {
+            let _ = _tray_icon.set_tooltip(Some(&tooltip));
+            last_tooltip = tooltip;
+        }
+
+        // Attendre le prochain message (clic, menu) ou changement d'etat
+        unsafe {
+            winapi::um::winuser::MsgWaitForMultipleObjectsEx(
+                0,
+                std::ptr::null(),
+                winapi::um::winbase::INFINITE,
+                winapi::um::winuser::QS_ALLINPUT,
+                winapi::um::winuser::MWMO_INPUTAVAILABLE,
+            );
+        }
     }
 }
@@ -2,23 +2,163 @@
 // Affiche le statut de connexion Tor, le port SOCKS5, un selecteur de langue,
 // un bouton Appliquer qui sauvegarde dans le fichier TOML,
 // et un bouton Redemarrer qui relance l'application avec la nouvelle config.
-// La fenetre reste au-dessus des autres et possede l'icone de l'application.
+// Un onglet "Circuits" liste les circuits Tor recemment utilises par les flux SOCKS,
+// un onglet "Connexions" les connexions actives avec leur circuit et relais de sortie.
+// L'onglet "Circuits" affiche aussi les gardes en service et permet de les renouveler.
+// Un onglet "Onion" affiche les adresses des services onion heberges et gere leurs cles
+// (ajout d'un service, sauvegarde, import, regeneration).
+// Un onglet "Avance" expose la journalisation, les repertoires, le rejet des IP et les delais.
+// L'onglet general permet de copier l'URL du proxy et d'afficher son QR code.
+// La fenetre reste au-dessus des autres et possede l'icone de l'application ;
+// sa position, sa taille et le dernier onglet ouvert sont memorises.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use eframe::egui;
-use crate::config::IronCloakConfig;
-use crate::gui::state::AppState;
+use serde::{Deserialize, Serialize};
+use crate::config::{GuiTheme, IronCloakConfig, OnionServiceConfig, TrayClickAction, VanguardsMode};
+use crate::gui::state::{
+    AppState, CircuitEntry, DestinationStats, HealthLevel, HistoryEntry, MoatState, PassphrasePrompt, ProxyStatus,
+    SpeedTestState, TorCheckState, UpdateState, TOP_DESTINATIONS,
+};
+use crate::i18n::languages;
+use crate::tor::RelayInfo;
 
 /// Icone PNG embarquee pour la fenetre
 const WINDOW_ICON_PNG: &[u8] = include_bytes!("../../icon_256_on.png");
 
-/// Les langues disponibles avec leur code et libelle
-const LANGUAGES: &[(&str, &str)] = &[
-    ("en", "English"),
-    ("fr", "Francais"),
-    ("es", "Espanol"),
+/// Niveaux de protection vanguards proposes avec leur cle de traduction
+const VANGUARDS_MODES: &[(VanguardsMode, &str)] = &[
+    (VanguardsMode::Off, "gui.vanguards_off"),
+    (VanguardsMode::Lite, "gui.vanguards_lite"),
+    (VanguardsMode::Full, "gui.vanguards_full"),
 ];
 
+/// Themes proposes avec leur cle de traduction
+const THEMES: &[(GuiTheme, &str)] = &[
+    (GuiTheme::System, "gui.theme_system"),
+    (GuiTheme::Light, "gui.theme_light"),
+    (GuiTheme::Dark, "gui.theme_dark"),
+];
+
+/// Actions d'un clic sur l'icone de notification avec leur cle de traduction
+const TRAY_CLICK_ACTIONS: &[(TrayClickAction, &str)] = &[
+    (TrayClickAction::OpenWindow, "gui.tray_click_open_window"),
+    (TrayClickAction::TogglePause, "gui.tray_click_toggle_pause"),
+    (TrayClickAction::NewIdentity, "gui.tray_click_new_identity"),
+];
+
+/// Taille par defaut (et minimale) de la fenetre, a l'echelle 1.0
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [520.0, 450.0];
+
+/// Intervalle de mesure de la taille du cache (parcours du repertoire)
+const CACHE_SIZE_REFRESH: Duration = Duration::from_secs(10);
+
+/// Bornes du facteur d'echelle de l'interface
+const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.75..=2.5;
+
+/// Onglets de la fenetre
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Tab {
+    #[default]
+    General,
+    Circuits,
+    Connections,
+    History,
+    Stats,
+    Onion,
+    Advanced,
+}
+
+/// Format d'export des onglets Statistiques et Historique
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Niveaux de journalisation proposes
+const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+/// Reglages de l'onglet "Avance", tels que saisis (les delais restent du texte jusqu'a l'enregistrement)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AdvancedSettings {
+    log_level: String,
+    log_dir: String,
+    data_dir: String,
+    dns_reject_ip: bool,
+    stream_attach_secs: String,
+    circuit_build_secs: String,
+    circuit_request_secs: String,
+    stream_connect_secs: String,
+}
+
+impl AdvancedSettings {
+    fn from_config(config: &IronCloakConfig) -> Self {
+        let optional = |secs: Option<u64>| secs.map(|s| s.to_string()).unwrap_or_default();
+        let timeouts = &config.tor.timeouts;
+        Self {
+            log_level: config.logging.level.clone(),
+            log_dir: config.logging.log_dir.clone(),
+            data_dir: config.tor.data_dir.clone(),
+            dns_reject_ip: config.proxy.dns_reject_ip,
+            stream_attach_secs: timeouts.stream_attach_secs.to_string(),
+            circuit_build_secs: optional(timeouts.circuit_build_secs),
+            circuit_request_secs: optional(timeouts.circuit_request_secs),
+            stream_connect_secs: optional(timeouts.stream_connect_secs),
+        }
+    }
+
+    /// Reporte les reglages dans la configuration.
+    /// En cas de valeur invalide, retourne le nom du champ et laisse la configuration intacte.
+    fn apply(&self, config: &mut IronCloakConfig) -> Result<(), String> {
+        let required = |value: &str, field: &str| -> Result<u64, String> {
+            match value.trim().parse() {
+                Ok(secs) if secs > 0 => Ok(secs),
+                _ => Err(field.to_string()),
+            }
+        };
+        let optional = |value: &str, field: &str| -> Result<Option<u64>, String> {
+            if value.trim().is_empty() {
+                Ok(None)
+            } else {
+                required(value, field).map(Some)
+            }
+        };
+
+        let stream_attach_secs = required(&self.stream_attach_secs, "stream_attach_secs")?;
+        let circuit_build_secs = optional(&self.circuit_build_secs, "circuit_build_secs")?;
+        let circuit_request_secs = optional(&self.circuit_request_secs, "circuit_request_secs")?;
+        let stream_connect_secs = optional(&self.stream_connect_secs, "stream_connect_secs")?;
+        if self.log_dir.trim().is_empty() {
+            return Err("log_dir".to_string());
+        }
+        if self.data_dir.trim().is_empty() {
+            return Err("data_dir".to_string());
+        }
+
+        config.logging.level = self.log_level.clone();
+        config.logging.log_dir = self.log_dir.trim().to_string();
+        config.tor.data_dir = self.data_dir.trim().to_string();
+        config.proxy.dns_reject_ip = self.dns_reject_ip;
+        let timeouts = &mut config.tor.timeouts;
+        timeouts.stream_attach_secs = stream_attach_secs;
+        timeouts.circuit_build_secs = circuit_build_secs;
+        timeouts.circuit_request_secs = circuit_request_secs;
+        timeouts.stream_connect_secs = stream_connect_secs;
+        Ok(())
+    }
+}
+
 /// Charge l'icone PNG et la convertit en IconData pour egui
 fn load_window_icon() -> egui::IconData {
     let img = image::load_from_memory(WINDOW_ICON_PNG)
@@ -32,40 +172,167 @@ fn load_window_icon() -> egui::IconData {
     }
 }
 
+/// Position, taille et onglet de la fenetre, memorises entre deux ouvertures
+/// dans un petit fichier a cote de la configuration (ironcloak-window.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct WindowMemory {
+    position: Option<[f32; 2]>,
+    size: Option<[f32; 2]>,
+    #[serde(default)]
+    tab: Tab,
+}
+
+impl WindowMemory {
+    fn path(config_path: &std::path::Path) -> std::path::PathBuf {
+        config_path.with_file_name("ironcloak-window.json")
+    }
+
+    fn load(config_path: &std::path::Path) -> Self {
+        std::fs::read(Self::path(config_path))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, config_path: &std::path::Path) {
+        let path = Self::path(config_path);
+        let result = serde_json::to_vec_pretty(self)
+            .map_err(std::io::Error::from)
+            .and_then(|data| std::fs::write(&path, data));
+        if let Err(e) = result {
+            tracing::debug!("{}", crate::tl!("gui.window_state_failed", path = path.display(), error = e));
+        }
+    }
+}
+
 /// Lance la fenetre egui. Bloquant jusqu'a la fermeture de la fenetre.
 pub fn run_window(state: Arc<AppState>) {
     let icon = load_window_icon();
+    let config_path = state.config_path.clone();
+    let memory = Arc::new(Mutex::new(WindowMemory::load(&config_path)));
+
+    // Tailles par defaut agrandies selon l'echelle de l'interface
+    let scale = clamp_scale(IronCloakConfig::load(&config_path).unwrap_or_default().gui.scale);
+    let default_size = [DEFAULT_WINDOW_SIZE[0] * scale, DEFAULT_WINDOW_SIZE[1] * scale];
+
+    let mut viewport = {
+        let memory = memory.lock().unwrap();
+        let mut viewport = egui::ViewportBuilder::default()
+            .with_inner_size(memory.size.unwrap_or(default_size))
+            .with_min_inner_size(default_size);
+        if let Some(position) = memory.position {
+            viewport = viewport.with_position(position);
+        }
+        viewport
+    };
+    viewport = viewport.with_always_on_top().with_icon(Arc::new(icon));
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([380.0, 280.0])
-            .with_resizable(false)
-            .with_always_on_top()
-            .with_icon(Arc::new(icon)),
+        viewport,
         ..Default::default()
     };
 
+    let app_memory = Arc::clone(&memory);
     let _ = eframe::run_native(
         &crate::t!("gui.window_title"),
         options,
-        Box::new(move |_cc| Ok(Box::new(IronCloakApp::new(state)))),
+        Box::new(move |cc| {
+            let app = IronCloakApp::new(state, app_memory);
+            install_system_fonts(&cc.egui_ctx);
+            cc.egui_ctx.set_theme(theme_preference(app.theme));
+            apply_scale(&cc.egui_ctx, app.scale);
+            Ok(Box::new(app))
+        }),
     );
+
+    // Fenetre fermee : memoriser sa geometrie et l'onglet affiche
+    memory.lock().unwrap().save(&config_path);
 }
 
 /// Application egui principale
 struct IronCloakApp {
     state: Arc<AppState>,
+    /// Geometrie et onglet a memoriser a la fermeture
+    memory: Arc<Mutex<WindowMemory>>,
+    tab: Tab,
     port_input: String,
     selected_lang_index: usize,
     /// Index precedent de la langue pour detecter les changements
     prev_lang_index: usize,
+    /// Niveau vanguards choisi et niveau actif (pour detecter un redemarrage necessaire)
+    vanguards: VanguardsMode,
+    running_vanguards: VanguardsMode,
+    /// Theme de la fenetre (applique immediatement)
+    theme: GuiTheme,
+    /// Echelle de l'interface (appliquee au relachement du curseur)
+    scale: f32,
+    /// Action d'un clic sur l'icone de notification (appliquee avec "Appliquer")
+    tray_click_action: TrayClickAction,
+    /// Reglages de l'onglet "Avance" saisis et en vigueur (pour detecter un redemarrage necessaire)
+    advanced: AdvancedSettings,
+    running_advanced: AdvancedSettings,
     status_message: Option<(String, bool)>,
     /// Indique que la config a ete modifiee et sauvegardee (affiche le bouton Redemarrer)
     needs_restart: bool,
+    /// Confirmation de la rotation des gardes en cours d'affichage
+    confirm_rotate_guards: bool,
+    /// Confirmation de l'effacement des donnees d'identite en cours d'affichage
+    confirm_wipe_identity: bool,
+    /// Taille du cache de l'annuaire et date de la mesure (rafraichie periodiquement)
+    cache_size: Option<(Instant, u64)>,
+    /// Adresse du proxy SOCKS5 vue depuis cette machine (navigateur lance)
+    proxy_host: String,
+    /// API des releases (updates.release_url) pour la verification a la demande
+    release_url: String,
+    /// Adresse du proxy a communiquer aux autres appareils (URL copiee, QR code)
+    share_host: String,
+    /// Fenetre du QR code ouverte
+    show_qr: bool,
+    /// Lancement automatique a l'ouverture de session (etat reel, relu au demarrage)
+    autostart: bool,
+    /// Repertoire de donnees Tor (cles des services onion)
+    data_dir: String,
+    /// Services onion declares dans la configuration
+    onion_services: Vec<OnionServiceConfig>,
+    /// Formulaire d'ajout d'un service onion
+    new_onion_nickname: String,
+    new_onion_port: String,
+    new_onion_target: String,
+    /// Service dont la regeneration des cles attend confirmation
+    confirm_regenerate: Option<String>,
+    /// Nombre de ponts configures ([tor.bridges])
+    bridge_count: usize,
+    /// Fenetre d'import de ponts ouverte, et texte colle
+    show_bridge_import: bool,
+    bridge_text: String,
+    /// Fenetre de demande de ponts a BridgeDB ouverte, solution saisie et image du captcha
+    /// (cle : jeton du captcha)
+    show_moat: bool,
+    moat_solution: String,
+    moat_texture: Option<(String, egui::TextureHandle)>,
+    /// Domaine frontal configure (tor.bridges.moat.front) : sans lui, BridgeDB n'est pas
+    /// contacte et le bouton reste inactif
+    moat_front_set: bool,
+    /// Recherche dans l'historique des connexions (destination), echecs seuls
+    history_search: String,
+    history_errors_only: bool,
+    /// Phrase secrete de l'etat chiffre en cours de saisie (et sa confirmation a la creation)
+    passphrase: String,
+    passphrase_confirm: String,
+    /// Empreinte du mot de passe des reglages (gui.lock_password) et verrouillage courant :
+    /// la fenetre s'ouvre verrouillee si un mot de passe est defini
+    lock_hash: Option<String>,
+    locked: bool,
+    /// Saisie du mot de passe pour deverrouiller, et echec de la derniere tentative
+    unlock_input: String,
+    unlock_failed: bool,
+    /// Nouveau mot de passe des reglages en cours de saisie (et sa confirmation)
+    new_lock_password: String,
+    new_lock_confirm: String,
 }
 
 impl IronCloakApp {
-    fn new(state: Arc<AppState>) -> Self {
+    fn new(state: Arc<AppState>, memory: Arc<Mutex<WindowMemory>>) -> Self {
         // Initialiser le port affiche : le port en attente s'il existe, sinon le port courant
         let pending = state.get_pending_port();
         let port_input = if pending > 0 {
@@ -76,20 +343,63 @@ impl IronCloakApp {
 
         // Trouver l'index de la langue courante
         let current_lang = state.get_language();
-        let selected_lang_index = LANGUAGES.iter()
-            .position(|(code, _)| *code == current_lang)
+        let selected_lang_index = languages().iter()
+            .position(|lang| lang.code.eq_ignore_ascii_case(&current_lang))
             .unwrap_or(0);
 
         // Si un port en attente existe, on a deja des changements non appliques
         let needs_restart = pending > 0 && pending != state.get_port();
 
+        // Options lues depuis le fichier de configuration
+        let config = IronCloakConfig::load(&state.config_path).unwrap_or_default();
+
         Self {
             state,
+            tab: memory.lock().unwrap().tab,
+            memory,
             port_input,
             selected_lang_index,
             prev_lang_index: selected_lang_index,
+            vanguards: config.tor.security.vanguards,
+            running_vanguards: config.tor.security.vanguards,
+            theme: config.gui.theme,
+            scale: clamp_scale(config.gui.scale),
+            tray_click_action: config.gui.tray_click_action,
+            advanced: AdvancedSettings::from_config(&config),
+            running_advanced: AdvancedSettings::from_config(&config),
             status_message: None,
             needs_restart,
+            confirm_rotate_guards: false,
+            confirm_wipe_identity: false,
+            cache_size: None,
+            proxy_host: local_proxy_host(&config.proxy.listen_addr),
+            release_url: config.updates.release_url.clone(),
+            share_host: share_proxy_host(&config.proxy.listen_addr),
+            show_qr: false,
+            autostart: crate::autostart::is_enabled(),
+            data_dir: config.tor.data_dir.clone(),
+            onion_services: config.onion_services.clone(),
+            new_onion_nickname: String::new(),
+            new_onion_port: "80".to_string(),
+            new_onion_target: "127.0.0.1:8080".to_string(),
+            confirm_regenerate: None,
+            bridge_count: config.tor.bridges.lines.len(),
+            show_bridge_import: false,
+            bridge_text: String::new(),
+            show_moat: false,
+            moat_solution: String::new(),
+            moat_texture: None,
+            moat_front_set: config.tor.bridges.moat.front.as_deref().is_some_and(|front| !front.is_empty()),
+            history_search: String::new(),
+            history_errors_only: false,
+            passphrase: String::new(),
+            passphrase_confirm: String::new(),
+            locked: config.gui.lock_password.is_some(),
+            lock_hash: config.gui.lock_password,
+            unlock_input: String::new(),
+            unlock_failed: false,
+            new_lock_password: String::new(),
+            new_lock_confirm: String::new(),
         }
     }
 
@@ -98,20 +408,28 @@ impl IronCloakApp {
         let new_port: u16 = match self.port_input.trim().parse() {
             Ok(p) if p > 0 => p,
             _ => {
-                self.status_message = Some(("Invalid port".to_string(), false));
+                self.status_message = Some((crate::t!("gui.invalid_port"), false));
                 return;
             }
         };
 
-        let (lang_code, _) = LANGUAGES[self.selected_lang_index];
+        let lang_code = languages()[self.selected_lang_index].code;
         let config_path = &self.state.config_path;
 
         // Charger la config existante, appliquer les modifications, sauvegarder
         let mut config = IronCloakConfig::load(config_path)
             .unwrap_or_default();
 
+        if let Err(field) = self.advanced.apply(&mut config) {
+            self.status_message = Some((crate::t!("gui.invalid_value", field = field), false));
+            return;
+        }
         config.proxy.listen_port = new_port;
-        config.logging.language = Some(lang_code.to_string());
+        config.gui.language = Some(lang_code.to_string());
+        config.tor.security.vanguards = self.vanguards;
+        config.gui.theme = self.theme;
+        config.gui.scale = self.scale;
+        config.gui.tray_click_action = self.tray_click_action;
 
         match config.save(config_path) {
             Ok(()) => {
@@ -131,12 +449,28 @@ impl IronCloakApp {
                     self.needs_restart = true;
                 }
 
-                tracing::info!("{}", crate::t!("gui.saved"));
+                // Action du clic sur l'icone : prise en compte au prochain clic
+                self.state.set_tray_click_action(self.tray_click_action);
+
+                // Le niveau des traces s'applique tout de suite, sans redemarrage
+                if self.advanced.log_level != self.running_advanced.log_level {
+                    match crate::logging::set_filter(&config.logging.filter_directives()) {
+                        Ok(()) => self.running_advanced.log_level = self.advanced.log_level.clone(),
+                        Err(e) => tracing::warn!("{:#}", e),
+                    }
+                }
+
+                // Les options Tor ne sont prises en compte qu'au demarrage du client
+                if self.vanguards != self.running_vanguards || self.advanced != self.running_advanced {
+                    self.needs_restart = true;
+                }
+
+                tracing::info!("{}", crate::tl!("gui.saved"));
                 self.status_message = Some((crate::t!("gui.saved"), true));
             }
             Err(e) => {
-                tracing::error!("{}", crate::t!("gui.save_failed", e));
-                self.status_message = Some((crate::t!("gui.save_failed", e), false));
+                tracing::error!("{}", crate::tl!("gui.save_failed", error = e));
+                self.status_message = Some((crate::t!("gui.save_failed", error = e), false));
             }
         }
     }
@@ -146,7 +480,8 @@ impl IronCloakApp {
         let exe = std::env::current_exe().expect("Impossible de determiner le chemin de l'executable");
         let config_path = &self.state.config_path;
 
-        // Lancer un nouveau processus avec le meme fichier de config
+        // Lancer un nouveau processus avec le meme fichier de config (et le meme groupe : c'est
+        // le proxy lui-meme, voir killswitch::confine_child)
         let _ = std::process::Command::new(&exe)
             .arg("--config")
             .arg(config_path)
@@ -156,132 +491,1894 @@ impl IronCloakApp {
         self.state.request_quit();
     }
 
-    /// Traite les evenements du menu systray pendant que la fenetre est ouverte (Windows)
-    /// Permet de quitter l'application meme si la fenetre de config est affichee
-    fn drain_tray_menu_events(&self) {
-        #[cfg(windows)]
+    /// Expose la ligne de statut aux lecteurs d'ecran comme une zone "live" :
+    /// chaque changement d'etat de connexion est annonce sans deplacer le focus
+    fn announce_status(&self, response: &egui::Response) {
+        let mut text = format!("{}: {}", crate::t!("gui.status"), crate::gui::status_text(&self.state));
+        if let Some(cc) = self.state.current_exit().and_then(|exit| exit.country) {
+            text.push_str(", ");
+            text.push_str(&crate::t!("gui.exit_country", country = cc));
+        }
+        response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, true, &text));
+        response.ctx.accesskit_node_builder(response.id, |node| {
+            node.set_live(egui::accesskit::Live::Polite);
+        });
+    }
+
+    /// Raccourcis clavier globaux : Entree applique les reglages (onglets General et Avance)
+    /// quand aucun widget n'a le focus, Echap ferme la fenetre (IronCloak reste dans la zone
+    /// de notification) ou la reduit s'il n'y a pas d'icone de notification
+    fn handle_shortcuts(&mut self, ctx: &egui::Context, busy_before: bool) {
+        if busy_before || ctx.memory(|m| m.any_popup_open()) {
+            return;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Enter))
+            && matches!(self.tab, Tab::General | Tab::Advanced)
+            && !self.locked
         {
-            use tray_icon::menu::MenuEvent;
-            if let Some(ref quit_id) = self.state.get_tray_quit_menu_id() {
-                while let Ok(event) = MenuEvent::receiver().try_recv() {
-                    if event.id.as_ref() == quit_id.as_str() {
-                        self.state.request_quit();
+            self.save_config();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            if self.show_qr {
+                self.show_qr = false;
+            } else if self.show_bridge_import {
+                self.show_bridge_import = false;
+            } else if self.show_moat {
+                self.show_moat = false;
+                self.state.clear_moat_state();
+            } else if self.state.is_tray_active() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            } else {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+            }
+        }
+    }
+
+    /// Onglet general : statut, port, langue et boutons Appliquer/Redemarrer
+    fn show_general(&mut self, ui: &mut egui::Ui) {
+        // Statut de connexion Tor avec indicateur colore
+        let connected = self.state.is_connected();
+        let proxy_status = self.state.status();
+        let status = ui.horizontal(|ui| {
+            ui.label(crate::i18n::visual(&format!("{}: ", crate::t!("gui.status"))));
+            if matches!(proxy_status, ProxyStatus::Connected | ProxyStatus::Paused) {
+                if proxy_status == ProxyStatus::Paused {
+                    ui.colored_label(egui::Color32::from_rgb(180, 140, 0), crate::tv!("gui.paused"));
+                    // Pause imposee par le quota de donnees
+                    if let Some(quota) = crate::gui::quota_text(&self.state) {
+                        ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::i18n::visual(&quota));
                     }
+                } else {
+                    ui.colored_label(egui::Color32::from_rgb(0, 180, 0), crate::tv!("gui.connected"));
+                }
+                // Pays du relais de sortie du dernier circuit utilise (GeoIP embarquee)
+                if let Some(cc) = self.state.current_exit().and_then(|exit| exit.country) {
+                    ui.label(crate::tv!("gui.exit_country", country = cc));
                 }
+                // Sante des circuits : pastille coloree et latence de la derniere mesure
+                if let (Some(sample), Some(text)) = (self.state.get_health(), crate::gui::health_text(&self.state)) {
+                    let color = match sample.level {
+                        HealthLevel::Good => egui::Color32::from_rgb(0, 180, 0),
+                        HealthLevel::Slow => egui::Color32::from_rgb(180, 140, 0),
+                        HealthLevel::Bad => egui::Color32::from_rgb(220, 0, 0),
+                    };
+                    ui.colored_label(color, "●");
+                    ui.label(crate::i18n::visual(&text));
+                }
+            } else {
+                let color = match proxy_status {
+                    ProxyStatus::Failed => egui::Color32::from_rgb(220, 0, 0),
+                    ProxyStatus::Offline => egui::Color32::GRAY,
+                    _ => egui::Color32::from_rgb(180, 140, 0),
+                };
+                ui.colored_label(color, crate::i18n::visual(&crate::gui::status_text(&self.state)));
             }
 
-            // Drainer aussi les evenements de clic sur l'icone pour eviter l'accumulation
-            use tray_icon::TrayIconEvent;
-            while TrayIconEvent::receiver().try_recv().is_ok() {}
+            // Indicateur discret de nouvelle version
+            if let Some(version) = self.state.update_available() {
+                ui.label(
+                    egui::RichText::new(crate::tv!("gui.update_available", version = version))
+                        .small()
+                        .color(egui::Color32::from_rgb(0, 120, 200)),
+                );
+            }
+        });
+        self.announce_status(&status.response);
+
+        // Echec du backend : cause et nouvelle tentative de l'etape en echec
+        if let Some(error) = self.state.last_error() {
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::i18n::visual(&error.message));
+                if ui.button(crate::tv!("gui.retry")).clicked() {
+                    self.state.request_retry();
+                }
+            });
         }
-    }
-}
 
-impl eframe::App for IronCloakApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Rafraichir automatiquement toutes les secondes pour mettre a jour le statut
-        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        // Test de bout en bout via check.torproject.org
+        ui.horizontal(|ui| {
+            let check = self.state.get_tor_check();
+            let running = matches!(check, Some(TorCheckState::Running));
+            if ui
+                .add_enabled(connected && !running, egui::Button::new(crate::tv!("gui.verify_tor")))
+                .clicked()
+            {
+                self.state.request_tor_check();
+            }
 
-        // Traiter les evenements systray (quit depuis le menu pendant que la fenetre est ouverte)
-        self.drain_tray_menu_events();
+            match check {
+                Some(TorCheckState::Running) => {
+                    busy_spinner(ui);
+                }
+                Some(TorCheckState::Done(result)) if result.is_tor => {
+                    let country = result.country.as_deref().unwrap_or("??");
+                    ui.colored_label(
+                        egui::Color32::from_rgb(0, 160, 0),
+                        crate::tv!("gui.verify_tor_ok", ip = &result.ip, country = country),
+                    );
+                }
+                Some(TorCheckState::Done(result)) => {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(220, 0, 0),
+                        crate::tv!("gui.verify_tor_not_tor", ip = &result.ip),
+                    );
+                }
+                Some(TorCheckState::Failed(e)) => {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(220, 0, 0),
+                        crate::tv!("gui.verify_tor_failed", error = e),
+                    );
+                }
+                None => {}
+            }
+        });
 
-        // Detecter le changement de langue dans la liste deroulante → apercu instantane
-        if self.selected_lang_index != self.prev_lang_index {
-            let (lang_code, _) = LANGUAGES[self.selected_lang_index];
-            crate::i18n::init(lang_code);
-            self.prev_lang_index = self.selected_lang_index;
+        // Test de debit : telechargement de taille fixe a travers les circuits courants
+        ui.horizontal(|ui| {
+            let test = self.state.get_speed_test();
+            let running = matches!(test, Some(SpeedTestState::Running));
+            if ui
+                .add_enabled(connected && !running, egui::Button::new(crate::tv!("gui.speed_test")))
+                .clicked()
+            {
+                self.state.request_speed_test();
+            }
+
+            match test {
+                Some(SpeedTestState::Running) => {
+                    busy_spinner(ui);
+                }
+                Some(SpeedTestState::Done(result)) => {
+                    ui.label(crate::tv!(
+                        "gui.speed_test_result",
+                        mbps = format!("{:.2}", result.mbps()),
+                        latency = result.latency.as_millis()
+                    ));
+                }
+                Some(SpeedTestState::Failed(e)) => {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(220, 0, 0),
+                        crate::tv!("gui.speed_test_failed", error = e),
+                    );
+                }
+                None => {}
+            }
+        });
+
+        ui.horizontal(|ui| {
+            // Navigateur preconfigure pour utiliser le proxy
+            if ui
+                .add_enabled(connected, egui::Button::new(crate::tv!("gui.launch_browser")))
+                .clicked()
+            {
+                self.status_message = Some(match crate::browser::launch(&self.proxy_host, self.state.get_port()) {
+                    Ok(exe) => (crate::t!("gui.browser_launched", browser = exe.display()), true),
+                    Err(e) => {
+                        tracing::error!("{:#}", e);
+                        (format!("{:#}", e), false)
+                    }
+                });
+            }
+
+            // Pause / reprise du proxy (le client Tor reste amorce)
+            let label = if self.state.is_paused() {
+                crate::tv!("gui.resume")
+            } else {
+                crate::tv!("gui.pause")
+            };
+            if ui.button(label).clicked() {
+                self.state.toggle_pause();
+            }
+        });
+
+        // URL du proxy : copie et QR code pour les appareils mobiles du reseau local
+        let proxy_url = format!("socks5://{}:{}", self.share_host, self.state.get_port());
+        ui.horizontal(|ui| {
+            ui.monospace(&proxy_url);
+            if ui.small_button(crate::tv!("gui.copy")).clicked() {
+                ui.ctx().copy_text(proxy_url.clone());
+            }
+            if ui.small_button(crate::tv!("gui.qr_code")).clicked() {
+                self.show_qr = !self.show_qr;
+            }
+        });
+        if self.show_qr {
+            egui::Window::new(crate::tv!("gui.qr_code"))
+                .open(&mut self.show_qr)
+                .collapsible(false)
+                .resizable(false)
+                .show(ui.ctx(), |ui| {
+                    paint_qr_code(ui, &proxy_url);
+                    ui.monospace(&proxy_url);
+                });
         }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading(crate::t!("gui.window_title"));
-            ui.add_space(10.0);
+        ui.add_space(10.0);
+        ui.separator();
+        ui.add_space(10.0);
 
-            // Statut de connexion Tor avec indicateur colore
-            let connected = self.state.is_connected();
-            ui.horizontal(|ui| {
-                ui.label(format!("{}: ", crate::t!("gui.status")));
-                if connected {
-                    ui.colored_label(egui::Color32::from_rgb(0, 180, 0), crate::t!("gui.connected"));
-                } else {
-                    ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::t!("gui.disconnected"));
+        // Reglages grises tant que la fenetre est verrouillee
+        let locked = self.locked;
+        ui.add_enabled_ui(!locked, |ui| self.show_general_settings(ui));
+    }
+
+    /// Reglages de l'onglet general : port, langue, vanguards, theme, echelle, lancement
+    /// automatique et boutons Appliquer/Redemarrer
+    fn show_general_settings(&mut self, ui: &mut egui::Ui) {
+        // Champ de saisie du port SOCKS5
+        ui.horizontal(|ui| {
+            let label = ui.label(crate::tv!("gui.port_label"));
+            let response = ui
+                .add(egui::TextEdit::singleline(&mut self.port_input).desired_width(80.0))
+                .labelled_by(label.id);
+            if submitted(&response) {
+                self.save_config();
+            }
+
+            // Afficher le port en attente s'il differe du port courant
+            let current_port = self.state.get_port();
+            if let Ok(input_port) = self.port_input.trim().parse::<u16>() {
+                if input_port != current_port {
+                    ui.label(
+                        egui::RichText::new(crate::tv!("gui.pending_port", port = input_port))
+                            .small()
+                            .color(egui::Color32::from_rgb(180, 140, 0)),
+                    );
                 }
-            });
+            }
+        });
 
-            ui.add_space(10.0);
-            ui.separator();
-            ui.add_space(10.0);
+        ui.add_space(8.0);
 
-            // Champ de saisie du port SOCKS5
-            ui.horizontal(|ui| {
-                ui.label(crate::t!("gui.port_label"));
-                ui.add(egui::TextEdit::singleline(&mut self.port_input).desired_width(80.0));
+        // Selecteur de langue (le changement est applique instantanement a l'affichage)
+        ui.horizontal(|ui| {
+            let label = ui.label(crate::tv!("gui.language_label"));
+            egui::ComboBox::from_id_salt("lang_combo")
+                .selected_text(crate::i18n::visual(languages()[self.selected_lang_index].name))
+                .show_ui(ui, |ui| {
+                    for (i, lang) in languages().iter().enumerate() {
+                        ui.selectable_value(&mut self.selected_lang_index, i, crate::i18n::visual(lang.name));
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+        });
 
-                // Afficher le port en attente s'il differe du port courant
-                let current_port = self.state.get_port();
-                if let Ok(input_port) = self.port_input.trim().parse::<u16>() {
-                    if input_port != current_port {
-                        ui.label(
-                            egui::RichText::new(crate::t!("gui.pending_port", input_port))
-                                .small()
-                                .color(egui::Color32::from_rgb(180, 140, 0)),
-                        );
+        ui.add_space(8.0);
+
+        // Selecteur du niveau vanguards (services onion sensibles)
+        ui.horizontal(|ui| {
+            let label = ui.label(crate::tv!("gui.vanguards_label"));
+            let selected = VANGUARDS_MODES
+                .iter()
+                .find(|(mode, _)| *mode == self.vanguards)
+                .map(|(_, key)| crate::tv!(key))
+                .unwrap_or_default();
+            egui::ComboBox::from_id_salt("vanguards_combo")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (mode, key) in VANGUARDS_MODES {
+                        ui.selectable_value(&mut self.vanguards, *mode, crate::tv!(key));
                     }
-                }
-            });
+                })
+                .response
+                .labelled_by(label.id);
+        });
 
-            ui.add_space(8.0);
+        ui.add_space(8.0);
+
+        // Selecteur du theme (apercu instantane, sauvegarde avec "Appliquer")
+        ui.horizontal(|ui| {
+            let label = ui.label(crate::tv!("gui.theme_label"));
+            let selected = THEMES
+                .iter()
+                .find(|(theme, _)| *theme == self.theme)
+                .map(|(_, key)| crate::tv!(key))
+                .unwrap_or_default();
+            let previous = self.theme;
+            egui::ComboBox::from_id_salt("theme_combo")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (theme, key) in THEMES {
+                        ui.selectable_value(&mut self.theme, *theme, crate::tv!(key));
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+            if self.theme != previous {
+                ui.ctx().set_theme(theme_preference(self.theme));
+            }
+        });
+
+        ui.add_space(8.0);
 
-            // Selecteur de langue (le changement est applique instantanement a l'affichage)
+        // Echelle de l'interface : appliquee une fois le curseur relache pour qu'il ne
+        // se deplace pas sous la souris pendant le glissement
+        ui.horizontal(|ui| {
+            let label = ui.label(crate::tv!("gui.scale_label"));
+            let response = ui
+                .add(
+                    egui::Slider::new(&mut self.scale, SCALE_RANGE)
+                        .step_by(0.05)
+                        .fixed_decimals(2),
+                )
+                .labelled_by(label.id);
+            if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                apply_scale(ui.ctx(), self.scale);
+            }
+        });
+
+        ui.add_space(8.0);
+
+        // Action d'un clic sur l'icone de notification (sans icone, rien a regler)
+        if self.state.is_tray_active() {
             ui.horizontal(|ui| {
-                ui.label(crate::t!("gui.language_label"));
-                egui::ComboBox::from_id_salt("lang_combo")
-                    .selected_text(LANGUAGES[self.selected_lang_index].1)
+                let label = ui.label(crate::tv!("gui.tray_click_label"));
+                let selected = TRAY_CLICK_ACTIONS
+                    .iter()
+                    .find(|(action, _)| *action == self.tray_click_action)
+                    .map(|(_, key)| crate::tv!(key))
+                    .unwrap_or_default();
+                egui::ComboBox::from_id_salt("tray_click_combo")
+                    .selected_text(selected)
                     .show_ui(ui, |ui| {
-                        for (i, (_code, label)) in LANGUAGES.iter().enumerate() {
-                            ui.selectable_value(&mut self.selected_lang_index, i, *label);
+                        for (action, key) in TRAY_CLICK_ACTIONS {
+                            ui.selectable_value(&mut self.tray_click_action, *action, crate::tv!(key));
                         }
-                    });
+                    })
+                    .response
+                    .labelled_by(label.id);
             });
 
-            ui.add_space(10.0);
+            ui.add_space(8.0);
+        }
 
-            // Boutons Appliquer et Redemarrer sur la meme ligne
-            ui.horizontal(|ui| {
-                if ui.button(crate::t!("gui.apply")).clicked() {
+        // Lancement automatique : applique immediatement, sans redemarrage
+        if ui.checkbox(&mut self.autostart, crate::tv!("gui.autostart")).changed() {
+            if let Err(e) = crate::autostart::set_enabled(self.autostart, &self.state.config_path) {
+                tracing::error!("{:#}", e);
+                self.status_message = Some((format!("{:#}", e), false));
+            }
+            // Refleter l'etat effectif (echec d'ecriture)
+            self.autostart = crate::autostart::is_enabled();
+        }
+
+        ui.add_space(10.0);
+
+        self.show_apply_buttons(ui);
+    }
+
+    /// Boutons Appliquer et Redemarrer sur la meme ligne, suivis du message de statut
+    fn show_apply_buttons(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(crate::tv!("gui.apply")).clicked() {
+                self.save_config();
+            }
+
+            if self.needs_restart {
+                if ui.button(
+                    egui::RichText::new(crate::tv!("gui.restart")).color(egui::Color32::from_rgb(220, 120, 0))
+                ).clicked() {
                     self.save_config();
+                    self.restart_app();
                 }
+            }
+        });
 
-                if self.needs_restart {
-                    if ui.button(
-                        egui::RichText::new(crate::t!("gui.restart")).color(egui::Color32::from_rgb(220, 120, 0))
-                    ).clicked() {
-                        self.save_config();
-                        self.restart_app();
-                    }
+        ui.add_space(5.0);
+
+        self.show_status_message(ui);
+    }
+
+    /// Onglet avance : journalisation, repertoires, DNS et delais (pris en compte au redemarrage,
+    /// sauf le niveau des traces)
+    fn show_advanced(&mut self, ui: &mut egui::Ui) {
+        let adv = &mut self.advanced;
+        let mut submit = false;
+        egui::Grid::new("advanced_grid")
+            .num_columns(2)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                let label = ui.label(crate::tv!("gui.log_level"));
+                egui::ComboBox::from_id_salt("log_level_combo")
+                    .selected_text(adv.log_level.as_str())
+                    .show_ui(ui, |ui| {
+                        for level in LOG_LEVELS {
+                            ui.selectable_value(&mut adv.log_level, level.to_string(), *level);
+                        }
+                    })
+                    .response
+                    .labelled_by(label.id);
+                ui.end_row();
+
+                let label = ui.label(crate::tv!("gui.log_dir"));
+                submit |= folder_field(ui, &mut adv.log_dir, label.id);
+                ui.end_row();
+
+                let label = ui.label(crate::tv!("gui.data_dir"));
+                submit |= folder_field(ui, &mut adv.data_dir, label.id);
+                ui.end_row();
+
+                let label = ui.label(crate::tv!("gui.dns_reject_ip"));
+                ui.checkbox(&mut adv.dns_reject_ip, "").labelled_by(label.id);
+                ui.end_row();
+
+                let label = ui.label(crate::tv!("gui.stream_attach_secs"));
+                let response = ui
+                    .add(egui::TextEdit::singleline(&mut adv.stream_attach_secs).desired_width(60.0))
+                    .labelled_by(label.id);
+                submit |= submitted(&response);
+                ui.end_row();
+
+                let optional = crate::tv!("gui.timeout_default_hint");
+                for (label, value) in [
+                    (crate::tv!("gui.circuit_build_secs"), &mut adv.circuit_build_secs),
+                    (crate::tv!("gui.circuit_request_secs"), &mut adv.circuit_request_secs),
+                    (crate::tv!("gui.stream_connect_secs"), &mut adv.stream_connect_secs),
+                ] {
+                    let label = ui.label(label);
+                    let response = ui
+                        .add(
+                            egui::TextEdit::singleline(value)
+                                .hint_text(optional.as_str())
+                                .desired_width(60.0),
+                        )
+                        .labelled_by(label.id);
+                    submit |= submitted(&response);
+                    ui.end_row();
                 }
             });
+        if submit {
+            self.save_config();
+        }
 
-            ui.add_space(5.0);
+        ui.add_space(8.0);
+
+        // Verification des mises a jour a la demande (a travers Tor)
+        ui.horizontal(|ui| {
+            let update = self.state.get_update_state();
+            let running = matches!(update, Some(UpdateState::Running));
+            if ui
+                .add_enabled(
+                    self.state.is_connected() && !running,
+                    egui::Button::new(crate::tv!("gui.check_updates")),
+                )
+                .clicked()
+            {
+                self.state.request_update_check(self.release_url.clone());
+            }
+            match update {
+                Some(UpdateState::Running) => {
+                    busy_spinner(ui);
+                }
+                Some(UpdateState::UpToDate) => {
+                    ui.label(crate::tv!("gui.up_to_date", version = crate::update::CURRENT_VERSION));
+                }
+                Some(UpdateState::Available(version)) => {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(0, 120, 200),
+                        crate::tv!("gui.update_available", version = version),
+                    );
+                }
+                Some(UpdateState::Failed(e)) => {
+                    ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::tv!("gui.update_failed", error = e));
+                }
+                None => {}
+            }
+        });
+
+        // Ponts : nombre configure et import de lignes torrc ou Tor Browser
+        ui.horizontal(|ui| {
+            ui.label(crate::tv!("gui.bridges_count", count = self.bridge_count));
+            if ui.button(crate::tv!("gui.import_bridges")).clicked() {
+                self.show_bridge_import = true;
+            }
+            // BridgeDB, hors Tor : disponible meme si l'amorcage echoue (reseau censure), mais
+            // seulement par un domaine frontal
+            let get_bridges = ui
+                .add_enabled(self.moat_front_set, egui::Button::new(crate::tv!("gui.get_bridges")))
+                .on_disabled_hover_text(crate::tv!("gui.get_bridges_no_front"));
+            if get_bridges.clicked() {
+                self.show_moat = true;
+                self.moat_solution.clear();
+                self.state.request_moat_challenge();
+            }
+        });
+        if !self.moat_front_set {
+            ui.label(egui::RichText::new(crate::tv!("gui.get_bridges_no_front")).small().weak());
+        }
+        if self.show_moat {
+            self.show_moat_window(ui.ctx());
+        }
+        if self.show_bridge_import {
+            let mut import = false;
+            egui::Window::new(crate::tv!("gui.import_bridges"))
+                .open(&mut self.show_bridge_import)
+                .collapsible(false)
+                .show(ui.ctx(), |ui| {
+                    ui.label(crate::tv!("gui.import_bridges_hint"));
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.bridge_text)
+                            .code_editor()
+                            .desired_rows(6)
+                            .desired_width(f32::INFINITY),
+                    );
+                    import = ui
+                        .add_enabled(!self.bridge_text.trim().is_empty(), egui::Button::new(crate::tv!("gui.import")))
+                        .clicked();
+                });
+            if import {
+                self.import_bridges();
+            }
+        }
+
+        // Cache de l'annuaire : taille sur le disque et vidage (au redemarrage, client arrete)
+        if !self.cache_size.is_some_and(|(measured, _)| measured.elapsed() < CACHE_SIZE_REFRESH) {
+            self.cache_size = Some((Instant::now(), crate::tor::cache_size(&self.data_dir)));
+        }
+        let cache_bytes = self.cache_size.map(|(_, bytes)| bytes).unwrap_or(0);
+        ui.horizontal(|ui| {
+            ui.label(crate::tv!("gui.cache_size", size = super::format_bytes(cache_bytes)));
+            if ui
+                .button(crate::tv!("gui.clear_cache"))
+                .on_hover_text(crate::tv!("gui.clear_cache_hint"))
+                .clicked()
+            {
+                self.clear_cache();
+            }
+        });
+        self.show_wipe_identity(ui);
+
+        // Mot de passe des reglages : defini, change ou supprime (fenetre deverrouillee)
+        self.show_lock_settings(ui);
+
+        // A propos : version et ressources du processus (reperer les fuites)
+        egui::CollapsingHeader::new(crate::tv!("gui.about")).show(ui, |ui| {
+            ui.label(format!("IronCloak {}", crate::update::CURRENT_VERSION));
+            let runtime = self.state.get_backend().map(|backend| backend.runtime);
+            let usage = crate::resources::snapshot(runtime.as_ref());
+            let unknown = || "-".to_string();
+            ui.label(crate::tv!(
+                "gui.resource_memory",
+                mb = usage.memory_bytes.map(|b| format!("{:.1}", b as f64 / 1_048_576.0)).unwrap_or_else(unknown)
+            ));
+            ui.label(crate::tv!(
+                "gui.resource_handles",
+                count = usage.open_handles.map(|n| n.to_string()).unwrap_or_else(unknown)
+            ));
+            ui.label(crate::tv!(
+                "gui.resource_threads",
+                count = usage.threads.map(|n| n.to_string()).unwrap_or_else(unknown)
+            ));
+            ui.label(crate::tv!(
+                "gui.resource_tasks",
+                count = usage.tasks.map(|n| n.to_string()).unwrap_or_else(unknown)
+            ));
+        });
 
-            // Message de statut (succes en vert, erreur en rouge)
-            if let Some((ref msg, success)) = self.status_message {
-                let color = if success {
-                    egui::Color32::from_rgb(0, 160, 0)
+        ui.add_space(10.0);
+        self.show_apply_buttons(ui);
+    }
+
+    /// Saisie du mot de passe des reglages (avec confirmation) et suppression du mot de passe
+    fn show_lock_settings(&mut self, ui: &mut egui::Ui) {
+        let mut submit = false;
+        let mut remove = false;
+        egui::Grid::new("lock_grid")
+            .num_columns(2)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                let label = ui.label(crate::tv!("gui.lock_password"));
+                let response = ui
+                    .add(egui::TextEdit::singleline(&mut self.new_lock_password).password(true).desired_width(160.0))
+                    .labelled_by(label.id);
+                submit |= submitted(&response);
+                ui.end_row();
+
+                let label = ui.label(crate::tv!("gui.lock_password_confirm"));
+                let response = ui
+                    .add(egui::TextEdit::singleline(&mut self.new_lock_confirm).password(true).desired_width(160.0))
+                    .labelled_by(label.id);
+                submit |= submitted(&response);
+                ui.end_row();
+            });
+        let ready = !self.new_lock_password.is_empty() && self.new_lock_password == self.new_lock_confirm;
+        ui.horizontal(|ui| {
+            submit |= ui.add_enabled(ready, egui::Button::new(crate::tv!("gui.set_lock_password"))).clicked();
+            if self.lock_hash.is_some() {
+                remove = ui.button(crate::tv!("gui.remove_lock_password")).clicked();
+            }
+            if !self.new_lock_confirm.is_empty() && self.new_lock_confirm != self.new_lock_password {
+                ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::tv!("gui.lock_password_mismatch"));
+            }
+        });
+        if submit && ready {
+            match crate::security::hash_password(&self.new_lock_password) {
+                Ok(hash) => self.save_lock_password(Some(hash)),
+                Err(e) => self.status_message = Some((e.to_string(), false)),
+            }
+        } else if remove {
+            self.save_lock_password(None);
+        }
+    }
+
+    /// Enregistre l'empreinte du mot de passe des reglages (None : plus de verrouillage)
+    fn save_lock_password(&mut self, hash: Option<String>) {
+        let mut config = IronCloakConfig::load(&self.state.config_path).unwrap_or_default();
+        let set = hash.is_some();
+        config.gui.lock_password = hash.clone();
+        match config.save(&self.state.config_path) {
+            Ok(()) => {
+                self.lock_hash = hash;
+                self.new_lock_password.clear();
+                self.new_lock_confirm.clear();
+                let (log, message) = if set {
+                    (crate::tl!("gui.lock_password_set"), crate::t!("gui.lock_password_set"))
                 } else {
-                    egui::Color32::from_rgb(220, 0, 0)
+                    (crate::tl!("gui.lock_password_removed"), crate::t!("gui.lock_password_removed"))
                 };
-                ui.label(egui::RichText::new(msg.as_str()).small().color(color));
+                tracing::info!("{}", log);
+                self.status_message = Some((message, true));
             }
+            Err(e) => {
+                tracing::error!("{}", crate::tl!("gui.save_failed", error = e));
+                self.status_message = Some((crate::t!("gui.save_failed", error = e), false));
+            }
+        }
+    }
 
-            if self.needs_restart {
-                ui.add_space(3.0);
-                ui.label(
-                    egui::RichText::new(crate::t!("gui.restart_required"))
-                        .small()
-                        .color(egui::Color32::GRAY),
-                );
+    /// Verrouille les reglages. Les fenetres ouvertes avant (demande de ponts, import) et les
+    /// confirmations en attente sont fermees : elles resteraient sinon utilisables.
+    fn lock(&mut self) {
+        self.locked = true;
+        self.show_bridge_import = false;
+        self.bridge_text.clear();
+        if self.show_moat {
+            self.show_moat = false;
+            self.moat_texture = None;
+            self.moat_solution.clear();
+            self.state.clear_moat_state();
+        }
+        self.confirm_rotate_guards = false;
+        self.confirm_wipe_identity = false;
+        self.confirm_regenerate = None;
+    }
+
+    /// Bandeau de verrouillage : saisie du mot de passe quand les reglages sont verrouilles,
+    /// bouton pour les reverrouiller sinon
+    fn show_lock_bar(&mut self, ui: &mut egui::Ui) {
+        let Some(hash) = self.lock_hash.clone() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            if !self.locked {
+                if ui.button(crate::tv!("gui.lock")).clicked() {
+                    self.lock();
+                }
+                return;
+            }
+            let label = ui.label(crate::tv!("gui.settings_locked"));
+            let response = ui
+                .add(egui::TextEdit::singleline(&mut self.unlock_input).password(true).desired_width(140.0))
+                .labelled_by(label.id);
+            let submit = submitted(&response) | ui.button(crate::tv!("gui.unlock")).clicked();
+            if submit && !self.unlock_input.is_empty() {
+                if crate::security::verify_password(&hash, &self.unlock_input) {
+                    self.locked = false;
+                    self.unlock_failed = false;
+                } else {
+                    tracing::warn!("{}", crate::tl!("gui.wrong_lock_password"));
+                    self.unlock_failed = true;
+                }
+                self.unlock_input.clear();
+            }
+            if self.unlock_failed {
+                ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::tv!("gui.wrong_lock_password"));
             }
         });
+        ui.add_space(6.0);
+    }
+
+    /// Ajoute a la configuration les ponts colles dans la fenetre d'import (pris en compte au
+    /// redemarrage) ; le texte reste en place en cas d'erreur pour etre corrige
+    fn import_bridges(&mut self) {
+        match crate::bridges::parse(&self.bridge_text) {
+            Ok(imported) => {
+                if self.add_bridges(imported) {
+                    self.bridge_text.clear();
+                    self.show_bridge_import = false;
+                }
+            }
+            Err(e) => self.status_message = Some((e.to_string(), false)),
+        }
+    }
+
+    /// Fenetre "Obtenir des ponts" : captcha de BridgeDB, puis enregistrement des ponts recus
+    fn show_moat_window(&mut self, ctx: &egui::Context) {
+        let moat = self.state.get_moat_state();
+
+        // Ponts recus : enregistres aussitot, fenetre fermee
+        if let Some(MoatState::Done(lines)) = &moat {
+            match crate::bridges::parse(&lines.join("\n")) {
+                Ok(imported) => {
+                    self.add_bridges(imported);
+                }
+                Err(e) => self.status_message = Some((e.to_string(), false)),
+            }
+            self.state.clear_moat_state();
+            self.show_moat = false;
+            self.moat_texture = None;
+            return;
+        }
+
+        let mut open = true;
+        let mut submit = false;
+        let mut new_captcha = false;
+        egui::Window::new(crate::tv!("gui.get_bridges"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| match &moat {
+                Some(MoatState::Fetching | MoatState::Checking) => {
+                    busy_spinner(ui);
+                }
+                Some(MoatState::Challenge(challenge)) => {
+                    // Image du captcha (JPEG), decodee une fois par captcha
+                    if self.moat_texture.as_ref().map(|(token, _)| token) != Some(&challenge.challenge) {
+                        self.moat_texture = image::load_from_memory(&challenge.image).ok().map(|img| {
+                            let img = img.into_rgba8();
+                            let size = [img.width() as usize, img.height() as usize];
+                            let color = egui::ColorImage::from_rgba_unmultiplied(size, img.as_raw());
+                            (
+                                challenge.challenge.clone(),
+                                ctx.load_texture("moat_captcha", color, egui::TextureOptions::LINEAR),
+                            )
+                        });
+                    }
+                    if let Some((_, texture)) = &self.moat_texture {
+                        ui.image((texture.id(), texture.size_vec2()));
+                    }
+                    ui.label(crate::tv!("gui.captcha_hint"));
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.moat_solution).desired_width(200.0));
+                    submit |= submitted(&response);
+                    ui.horizontal(|ui| {
+                        submit |= ui
+                            .add_enabled(!self.moat_solution.trim().is_empty(), egui::Button::new(crate::tv!("gui.submit")))
+                            .clicked();
+                        new_captcha |= ui.button(crate::tv!("gui.new_captcha")).clicked();
+                    });
+                }
+                Some(MoatState::Failed(e)) => {
+                    ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::i18n::visual(e));
+                    new_captcha |= ui.button(crate::tv!("gui.retry")).clicked();
+                }
+                Some(MoatState::Done(_)) | None => {}
+            });
+
+        if submit && !self.moat_solution.trim().is_empty() {
+            self.state.submit_moat_solution(std::mem::take(&mut self.moat_solution));
+        }
+        if new_captcha {
+            self.moat_solution.clear();
+            self.state.request_moat_challenge();
+        }
+        if !open {
+            self.show_moat = false;
+            self.moat_texture = None;
+            self.state.clear_moat_state();
+        }
+    }
+
+    /// Saisie de la phrase secrete de l'etat chiffre (tor.encrypt_state), confirmee a la creation
+    fn show_passphrase_prompt(&mut self, ctx: &egui::Context, prompt: &PassphrasePrompt) {
+        let mut submit = false;
+        egui::Modal::new(egui::Id::new("passphrase_prompt")).show(ctx, |ui| {
+            ui.strong(crate::tv!("gui.passphrase_title"));
+            let hint = if prompt.first_time {
+                "gui.passphrase_create_hint"
+            } else {
+                "gui.passphrase_hint"
+            };
+            ui.label(crate::tv!(hint));
+            let response = ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true));
+            if !response.has_focus() && self.passphrase.is_empty() {
+                response.request_focus();
+            }
+            submit |= submitted(&response);
+            let confirmed = if prompt.first_time {
+                ui.label(crate::tv!("gui.passphrase_confirm"));
+                let response = ui.add(egui::TextEdit::singleline(&mut self.passphrase_confirm).password(true));
+                submit |= submitted(&response);
+                if !self.passphrase_confirm.is_empty() && self.passphrase_confirm != self.passphrase {
+                    ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::tv!("gui.passphrase_mismatch"));
+                }
+                self.passphrase_confirm == self.passphrase
+            } else {
+                true
+            };
+            if let Some(error) = &prompt.error {
+                ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::i18n::visual(error));
+            }
+            let ready = confirmed && !self.passphrase.is_empty();
+            submit |= ui.add_enabled(ready, egui::Button::new(crate::tv!("gui.unlock"))).clicked();
+            submit &= ready;
+        });
+        if submit {
+            self.passphrase_confirm.clear();
+            self.state.provide_passphrase(std::mem::take(&mut self.passphrase));
+        }
+    }
+
+    /// Portail captif detecte apres un echec de l'amorcage : ouverture de sa page de connexion
+    /// dans le navigateur du systeme, hors Tor
+    fn show_captive_portal_prompt(&mut self, ctx: &egui::Context, url: &str) {
+        let mut open = false;
+        let mut dismiss = false;
+        egui::Modal::new(egui::Id::new("captive_portal")).show(ctx, |ui| {
+            ui.strong(crate::tv!("gui.captive_portal_title"));
+            ui.label(crate::tv!("gui.captive_portal_hint"));
+            ui.label(egui::RichText::new(url).small().monospace());
+            ui.horizontal(|ui| {
+                open = ui.button(crate::tv!("gui.captive_portal_open")).clicked();
+                dismiss = ui.button(crate::tv!("gui.not_now")).clicked();
+            });
+        });
+        if open {
+            if let Err(e) = crate::gui::open_url(url) {
+                tracing::warn!("{}", crate::tl!("gui.open_url_failed", error = e));
+                self.status_message = Some((crate::t!("gui.open_url_failed", error = e), false));
+            }
+        }
+        if open || dismiss {
+            self.state.set_captive_portal(None);
+        }
+    }
+
+    /// Ajoute des ponts verifies a la configuration (pris en compte au redemarrage).
+    /// Retourne true si la configuration a ete enregistree.
+    fn add_bridges(&mut self, imported: crate::bridges::ImportedBridges) -> bool {
+        let mut config = IronCloakConfig::load(&self.state.config_path).unwrap_or_default();
+        let missing = imported.missing_transports(&config);
+        let added = imported.merge_into(&mut config);
+        match config.save(&self.state.config_path) {
+            Ok(()) => {
+                let total = config.tor.bridges.lines.len();
+                let mut message = crate::t!("bridges.imported", count = added, total = total);
+                if !missing.is_empty() {
+                    message.push(' ');
+                    message.push_str(&crate::t!("bridges.transport_missing", names = missing.join(", ")));
+                }
+                tracing::info!("{}", crate::tl!("bridges.imported", count = added, total = total));
+                self.status_message = Some((message, true));
+                self.bridge_count = total;
+                self.needs_restart |= added > 0;
+                true
+            }
+            Err(e) => {
+                tracing::error!("{}", crate::tl!("gui.save_failed", error = e));
+                self.status_message = Some((crate::t!("gui.save_failed", error = e), false));
+                false
+            }
+        }
+    }
+
+    /// Message de statut (succes en vert, erreur en rouge) et rappel de redemarrage
+    fn show_status_message(&self, ui: &mut egui::Ui) {
+        if let Some((ref msg, success)) = self.status_message {
+            let color = if success {
+                egui::Color32::from_rgb(0, 160, 0)
+            } else {
+                egui::Color32::from_rgb(220, 0, 0)
+            };
+            ui.label(egui::RichText::new(crate::i18n::visual(msg)).small().color(color));
+        }
+
+        if self.needs_restart {
+            ui.add_space(3.0);
+            ui.label(
+                egui::RichText::new(crate::tv!("gui.restart_required"))
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+        }
+    }
+
+    /// Onglet circuits : gardes en service, puis chemin, pays et age des circuits recents
+    fn show_circuits(&mut self, ui: &mut egui::Ui) {
+        let circuits = self.state.get_circuits();
+        self.show_guards(ui, &circuits);
+        ui.separator();
+
+        if circuits.is_empty() {
+            ui.label(
+                egui::RichText::new(crate::tv!("gui.circuits_empty"))
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+            return;
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("circuits_grid")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(crate::tv!("gui.circuit_id"));
+                    ui.strong(crate::tv!("gui.circuit_path"));
+                    ui.strong(crate::tv!("gui.circuit_age"));
+                    ui.strong(crate::tv!("gui.circuit_streams"));
+                    ui.end_row();
+
+                    for circuit in &circuits {
+                        ui.label(&circuit.info.id);
+                        ui.label(format_circuit_path(circuit));
+                        ui.label(format_age(circuit.first_seen.elapsed()));
+                        ui.label(circuit.streams.to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Gardes observees en premier saut des circuits recents et action de rotation
+    fn show_guards(&mut self, ui: &mut egui::Ui, circuits: &[CircuitEntry]) {
+        let mut guards: Vec<&RelayInfo> = Vec::new();
+        for circuit in circuits {
+            if let Some(guard) = circuit.info.relays.first() {
+                if !guards.iter().any(|g| g.fingerprint == guard.fingerprint) {
+                    guards.push(guard);
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(crate::tv!("gui.guards_label"));
+            if guards.is_empty() {
+                ui.label(egui::RichText::new("-").color(egui::Color32::GRAY));
+            } else {
+                let list = guards.iter().map(|g| format_relay(g)).collect::<Vec<_>>().join(", ");
+                ui.label(list);
+            }
+        });
+
+        // Efface l'etat et redemarre : reglage verrouille comme ceux des autres onglets
+        if !self.confirm_rotate_guards {
+            if ui.add_enabled(!self.locked, egui::Button::new(crate::tv!("gui.rotate_guards"))).clicked() {
+                self.confirm_rotate_guards = true;
+            }
+            return;
+        }
+
+        // Avertissement explicite : changer de gardes augmente l'exposition aux relais malveillants
+        ui.label(
+            egui::RichText::new(crate::tv!("gui.rotate_guards_warning"))
+                .small()
+                .color(egui::Color32::from_rgb(220, 0, 0)),
+        );
+        ui.horizontal(|ui| {
+            if ui.button(crate::tv!("gui.rotate_guards_confirm")).clicked() {
+                self.confirm_rotate_guards = false;
+                self.rotate_guards();
+            }
+            if ui.button(crate::tv!("gui.cancel")).clicked() {
+                self.confirm_rotate_guards = false;
+            }
+        });
+    }
+
+    /// Effacement des donnees d'identite, apres confirmation
+    fn show_wipe_identity(&mut self, ui: &mut egui::Ui) {
+        if !self.confirm_wipe_identity {
+            if ui
+                .button(crate::tv!("gui.wipe_identity"))
+                .on_hover_text(crate::tv!("gui.wipe_identity_hint"))
+                .clicked()
+            {
+                self.confirm_wipe_identity = true;
+            }
+            return;
+        }
+
+        ui.label(
+            egui::RichText::new(crate::tv!("gui.wipe_identity_warning"))
+                .small()
+                .color(egui::Color32::from_rgb(220, 0, 0)),
+        );
+        ui.horizontal(|ui| {
+            if ui.button(crate::tv!("gui.wipe_identity_confirm")).clicked() {
+                self.confirm_wipe_identity = false;
+                self.wipe_identity();
+            }
+            if ui.button(crate::tv!("gui.cancel")).clicked() {
+                self.confirm_wipe_identity = false;
+            }
+        });
+    }
+
+    /// Programme l'effacement des donnees d'identite (client principal et ports d'ecoute a
+    /// client distinct) puis redemarre l'application
+    fn wipe_identity(&mut self) {
+        let config = IronCloakConfig::load(&self.state.config_path).unwrap_or_default();
+        let data_dirs = std::iter::once(&config.tor.data_dir)
+            .chain(config.listeners.iter().filter_map(|l| l.data_dir.as_ref()));
+        let result: anyhow::Result<()> = data_dirs.map(|dir| crate::tor::request_identity_wipe(dir)).collect();
+        match result {
+            Ok(()) => self.restart_app(),
+            Err(e) => {
+                tracing::error!("{}", crate::tl!("tor.identity_wipe_failed", error = e));
+                self.status_message = Some((crate::t!("tor.identity_wipe_failed", error = e), false));
+            }
+        }
+    }
+
+    /// Programme le vidage du cache puis redemarre l'application
+    fn clear_cache(&mut self) {
+        match crate::tor::request_cache_clear(&self.data_dir) {
+            Ok(()) => self.restart_app(),
+            Err(e) => {
+                tracing::error!("{}", crate::tl!("tor.cache_clear_failed", error = e));
+                self.status_message = Some((crate::t!("tor.cache_clear_failed", error = e), false));
+            }
+        }
+    }
+
+    /// Programme la suppression de l'etat des gardes puis redemarre l'application
+    fn rotate_guards(&mut self) {
+        let config = IronCloakConfig::load(&self.state.config_path).unwrap_or_default();
+        match crate::tor::request_guard_rotation(&config.tor.data_dir) {
+            Ok(()) => self.restart_app(),
+            Err(e) => {
+                tracing::error!("{}", crate::tl!("tor.guards_rotate_failed", error = e));
+                self.status_message = Some((crate::t!("tor.guards_rotate_failed", error = e), false));
+            }
+        }
+    }
+
+    /// Onglet connexions : destination, circuit et relais de sortie des connexions actives
+    fn show_connections(&self, ui: &mut egui::Ui) {
+        let connections = self.state.get_connections();
+        if connections.is_empty() {
+            ui.label(
+                egui::RichText::new(crate::tv!("gui.connections_empty"))
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+            return;
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("connections_grid")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("#");
+                    ui.strong(crate::tv!("gui.connection_destination"));
+                    ui.strong(crate::tv!("gui.circuit_id"));
+                    ui.strong(crate::tv!("gui.connection_exit"));
+                    ui.strong(crate::tv!("gui.circuit_age"));
+                    ui.end_row();
+
+                    for conn in &connections {
+                        ui.label(conn.conn_id.to_string());
+                        ui.label(format!("{}:{}", conn.host, conn.port));
+                        ui.label(conn.circuit_id.as_deref().unwrap_or("-"));
+                        ui.label(conn.exit.as_ref().map(format_relay).unwrap_or_else(|| "-".to_string()));
+                        ui.label(format_age(conn.started.elapsed()));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Onglet historique : connexions fermees recemment, filtrees par destination et issue
+    fn show_history(&mut self, ui: &mut egui::Ui) {
+        let search = self.history_search.trim().to_lowercase();
+        let history: Vec<_> = self
+            .state
+            .get_history()
+            .into_iter()
+            .filter(|entry| !self.history_errors_only || entry.outcome != "completed")
+            .filter(|entry| search.is_empty() || entry.host.to_lowercase().contains(&search))
+            .collect();
+
+        let mut export = None;
+        ui.horizontal(|ui| {
+            let label = ui.label(crate::tv!("gui.history_search"));
+            ui.add(egui::TextEdit::singleline(&mut self.history_search).desired_width(180.0))
+                .labelled_by(label.id);
+            ui.checkbox(&mut self.history_errors_only, crate::tv!("gui.history_errors_only"));
+            if ui.button(crate::tv!("gui.history_clear")).clicked() {
+                self.state.clear_history();
+            }
+            ui.add_enabled_ui(!history.is_empty(), |ui| export = export_buttons(ui));
+        });
+        // Export de la vue filtree
+        if let Some(format) = export {
+            self.save_export("ironcloak-history", format, history_export(&history, format));
+        }
+        self.show_status_message(ui);
+        ui.add_space(6.0);
+
+        if history.is_empty() {
+            ui.label(
+                egui::RichText::new(crate::tv!("gui.history_empty"))
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+            return;
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("history_grid")
+                .num_columns(6)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(crate::tv!("gui.history_closed"));
+                    ui.strong(crate::tv!("gui.connection_destination"));
+                    ui.strong(crate::tv!("gui.history_duration"));
+                    ui.strong(crate::tv!("gui.history_up"));
+                    ui.strong(crate::tv!("gui.history_down"));
+                    ui.strong(crate::tv!("gui.history_outcome"));
+                    ui.end_row();
+
+                    for entry in &history {
+                        ui.label(entry.closed.format("%H:%M:%S").to_string());
+                        ui.label(format!("{}:{}", entry.host, entry.port));
+                        ui.label(format_age(entry.duration));
+                        ui.label(super::format_bytes(entry.up));
+                        ui.label(super::format_bytes(entry.down));
+                        match entry.outcome {
+                            "completed" => ui.label(crate::tv!("gui.outcome_completed")),
+                            "closed_by_pause" => ui.label(crate::tv!("gui.outcome_closed_by_pause")),
+                            "relay_error" => ui.colored_label(
+                                egui::Color32::from_rgb(180, 140, 0),
+                                crate::tv!("gui.outcome_relay_error"),
+                            ),
+                            _ => ui.colored_label(egui::Color32::from_rgb(220, 0, 0), crate::tv!("gui.outcome_error")),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Onglet statistiques : flux etablis depuis le demarrage par pays du relais de sortie
+    fn show_stats(&mut self, ui: &mut egui::Ui) {
+        let stats = self.state.exit_country_stats();
+        let destinations = self.state.top_destinations(TOP_DESTINATIONS);
+        if stats.is_empty() && destinations.is_empty() {
+            ui.label(
+                egui::RichText::new(crate::tv!("gui.stats_empty"))
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+            return;
+        }
+        let total: u64 = stats.iter().map(|(_, n)| n).sum();
+
+        let mut export = None;
+        let mut export_destinations = None;
+        ui.horizontal(|ui| {
+            ui.label(crate::tv!("gui.stats_total", count = total));
+            if ui.button(crate::tv!("gui.stats_reset")).clicked() {
+                self.state.reset_exit_country_stats();
+                self.state.reset_destination_stats();
+            }
+            export = export_buttons(ui);
+        });
+        if let Some(format) = export {
+            let (up, down) = self.state.traffic_totals();
+            self.save_export("ironcloak-stats", format, stats_export(&stats, up, down, format));
+        }
+        self.show_status_message(ui);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("exit_countries_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(crate::tv!("gui.stats_country"));
+                    ui.strong(crate::tv!("gui.stats_streams"));
+                    ui.end_row();
+
+                    for (country, count) in &stats {
+                        let share = *count as f32 / total as f32;
+                        ui.label(country);
+                        ui.label(count.to_string());
+                        ui.add(
+                            egui::ProgressBar::new(share)
+                                .desired_width(160.0)
+                                .text(format!("{:.0} %", share * 100.0)),
+                        );
+                        ui.end_row();
+                    }
+                });
+
+            // Domaines les plus gourmands (octets dans les deux sens), masques comme dans les traces
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.strong(crate::tv!("gui.top_destinations"));
+                ui.add_enabled_ui(!destinations.is_empty(), |ui| export_destinations = export_buttons(ui));
+            });
+            if destinations.is_empty() {
+                ui.label(
+                    egui::RichText::new(crate::tv!("gui.destinations_empty"))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+                return;
+            }
+            let largest = destinations[0].1.total().max(1);
+            egui::Grid::new("destinations_grid")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(crate::tv!("gui.destination_domain"));
+                    ui.strong(crate::tv!("gui.destination_connections"));
+                    ui.strong(crate::tv!("gui.history_up"));
+                    ui.strong(crate::tv!("gui.history_down"));
+                    ui.end_row();
+
+                    for (domain, stats) in &destinations {
+                        ui.label(domain);
+                        ui.label(stats.connections.to_string());
+                        ui.label(super::format_bytes(stats.up));
+                        ui.label(super::format_bytes(stats.down));
+                        ui.add(
+                            egui::ProgressBar::new(stats.total() as f32 / largest as f32)
+                                .desired_width(120.0)
+                                .text(super::format_bytes(stats.total())),
+                        );
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some(format) = export_destinations {
+            self.save_export("ironcloak-destinations", format, destinations_export(&destinations, format));
+        }
+    }
+
+    /// Enregistre un export dans le fichier choisi par l'utilisateur
+    fn save_export(&mut self, name: &str, format: ExportFormat, content: String) {
+        let extension = format.extension();
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(format!("{}.{}", name, extension))
+            .add_filter(extension.to_uppercase(), &[extension])
+            .save_file()
+        else {
+            return;
+        };
+        self.status_message = Some(match std::fs::write(&path, content) {
+            Ok(()) => (crate::t!("gui.exported", path = path.display()), true),
+            Err(e) => (crate::t!("gui.export_failed", error = e), false),
+        });
+    }
+
+    /// Onglet onion : services declares, adresse publiee et gestion des cles
+    fn show_onion_services(&mut self, ui: &mut egui::Ui) {
+        let running = self.state.get_onion_services();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if self.onion_services.is_empty() {
+                ui.label(
+                    egui::RichText::new(crate::tv!("gui.onion_empty"))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
+
+            for service in self.onion_services.clone() {
+                let address = running
+                    .iter()
+                    .find(|s| s.nickname == service.nickname)
+                    .map(|s| s.address.clone());
+
+                ui.group(|ui| {
+                    ui.strong(format!("{} — :{} → {}", service.nickname, service.port, service.target));
+                    if !service.authorized_clients.is_empty() {
+                        ui.label(
+                            egui::RichText::new(crate::tv!(
+                                "gui.onion_restricted",
+                                count = service.authorized_clients.len()
+                            ))
+                            .small(),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(crate::tv!("gui.onion_address"));
+                        match address {
+                            Some(ref addr) => {
+                                ui.label(egui::RichText::new(addr).monospace());
+                                if ui.small_button(crate::tv!("gui.copy")).clicked() {
+                                    ui.ctx().copy_text(addr.clone());
+                                }
+                            }
+                            None => {
+                                ui.label(
+                                    egui::RichText::new(crate::tv!("gui.onion_not_published"))
+                                        .color(egui::Color32::GRAY),
+                                );
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        if ui.button(crate::tv!("gui.onion_export")).clicked() {
+                            self.export_onion_keys(&service.nickname);
+                        }
+                        if ui.button(crate::tv!("gui.onion_import")).clicked() {
+                            self.import_onion_keys(&service.nickname);
+                        }
+                        if ui.button(crate::tv!("gui.onion_regenerate")).clicked() {
+                            self.confirm_regenerate = Some(service.nickname.clone());
+                        }
+                    });
+
+                    if self.confirm_regenerate.as_deref() == Some(service.nickname.as_str()) {
+                        ui.label(
+                            egui::RichText::new(crate::tv!("gui.onion_regenerate_warning"))
+                                .small()
+                                .color(egui::Color32::from_rgb(220, 0, 0)),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button(crate::tv!("gui.onion_regenerate_confirm")).clicked() {
+                                self.confirm_regenerate = None;
+                                self.regenerate_onion_keys(&service.nickname);
+                            }
+                            if ui.button(crate::tv!("gui.cancel")).clicked() {
+                                self.confirm_regenerate = None;
+                            }
+                        });
+                    }
+                });
+            }
+
+            ui.add_space(8.0);
+
+            // Ajout d'un service : arti genere ses cles au prochain demarrage
+            ui.label(crate::tv!("gui.onion_new"));
+            ui.horizontal(|ui| {
+                ui.label(crate::tv!("gui.onion_nickname"));
+                ui.add(egui::TextEdit::singleline(&mut self.new_onion_nickname).desired_width(80.0));
+                ui.label(crate::tv!("gui.port_short"));
+                ui.add(egui::TextEdit::singleline(&mut self.new_onion_port).desired_width(40.0));
+                ui.label(crate::tv!("gui.onion_target"));
+                ui.add(egui::TextEdit::singleline(&mut self.new_onion_target).desired_width(110.0));
+                if ui.button(crate::tv!("gui.onion_add")).clicked() {
+                    self.add_onion_service();
+                }
+            });
+
+            ui.add_space(5.0);
+            self.show_status_message(ui);
+        });
+    }
+
+    /// Ajoute un service onion a la configuration (cles generees par arti au redemarrage)
+    fn add_onion_service(&mut self) {
+        let nickname = self.new_onion_nickname.trim().to_string();
+        let port = match self.new_onion_port.trim().parse::<u16>() {
+            Ok(p) if p > 0 => p,
+            _ => {
+                self.status_message = Some((crate::t!("gui.invalid_port"), false));
+                return;
+            }
+        };
+        if nickname.is_empty() || self.onion_services.iter().any(|s| s.nickname == nickname) {
+            self.status_message = Some((crate::t!("gui.onion_invalid_nickname"), false));
+            return;
+        }
+
+        let service = OnionServiceConfig {
+            nickname,
+            port,
+            target: self.new_onion_target.trim().to_string(),
+            authorized_clients: Default::default(),
+        };
+
+        let config_path = &self.state.config_path;
+        let mut config = IronCloakConfig::load(config_path).unwrap_or_default();
+        config.onion_services.push(service.clone());
+        match config.save(config_path) {
+            Ok(()) => {
+                self.onion_services.push(service);
+                self.new_onion_nickname.clear();
+                self.needs_restart = true;
+                self.status_message = Some((crate::t!("gui.saved"), true));
+            }
+            Err(e) => {
+                tracing::error!("{}", crate::tl!("gui.save_failed", error = e));
+                self.status_message = Some((crate::t!("gui.save_failed", error = e), false));
+            }
+        }
+    }
+
+    /// Sauvegarde les cles d'un service dans un dossier choisi par l'utilisateur
+    fn export_onion_keys(&mut self, nickname: &str) {
+        let Some(dest) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        self.status_message = Some(match crate::onion::export_keys(&self.data_dir, nickname, &dest) {
+            Ok(count) => (crate::t!("gui.onion_exported", count = count, path = dest.join(nickname).display()), true),
+            Err(e) => (e.to_string(), false),
+        });
+    }
+
+    /// Importe les cles d'un service depuis une sauvegarde (adresse restauree au redemarrage)
+    fn import_onion_keys(&mut self, nickname: &str) {
+        let Some(src) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        self.status_message = Some(match crate::onion::import_keys(&self.data_dir, nickname, &src) {
+            Ok(count) => {
+                self.needs_restart = true;
+                (crate::t!("gui.onion_imported", count = count), true)
+            }
+            Err(e) => (e.to_string(), false),
+        });
+    }
+
+    /// Supprime les cles d'un service : une nouvelle adresse sera generee au redemarrage
+    fn regenerate_onion_keys(&mut self, nickname: &str) {
+        self.status_message = Some(match crate::onion::delete_keys(&self.data_dir, nickname) {
+            Ok(()) => {
+                self.needs_restart = true;
+                (crate::t!("gui.onion_regenerated"), true)
+            }
+            Err(e) => (e.to_string(), false),
+        });
+    }
+
+    /// Titre, barre d'onglets et contenu de l'onglet courant
+    fn show_tabs(&mut self, ui: &mut egui::Ui) {
+        ui.heading(crate::tv!("gui.window_title"));
+        ui.add_space(6.0);
+
+        // Barre d'onglets
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.tab, Tab::General, crate::tv!("gui.tab_general"));
+            ui.selectable_value(&mut self.tab, Tab::Circuits, crate::tv!("gui.tab_circuits"));
+            ui.selectable_value(&mut self.tab, Tab::Connections, crate::tv!("gui.tab_connections"));
+            ui.selectable_value(&mut self.tab, Tab::History, crate::tv!("gui.tab_history"));
+            ui.selectable_value(&mut self.tab, Tab::Stats, crate::tv!("gui.tab_stats"));
+            ui.selectable_value(&mut self.tab, Tab::Onion, crate::tv!("gui.tab_onion"));
+            ui.selectable_value(&mut self.tab, Tab::Advanced, crate::tv!("gui.tab_advanced"));
+        });
+        ui.separator();
+        ui.add_space(6.0);
+
+        // Mot de passe des reglages defini : statut consultable, modifications verrouillees
+        self.show_lock_bar(ui);
+        let locked = self.locked;
+
+        match self.tab {
+            Tab::General => self.show_general(ui),
+            Tab::Circuits => self.show_circuits(ui),
+            Tab::Connections => self.show_connections(ui),
+            Tab::History => self.show_history(ui),
+            Tab::Stats => self.show_stats(ui),
+            Tab::Onion => {
+                ui.add_enabled_ui(!locked, |ui| self.show_onion_services(ui));
+            }
+            Tab::Advanced => {
+                ui.add_enabled_ui(!locked, |ui| self.show_advanced(ui));
+            }
+        }
+    }
+
+    /// Traite les evenements du menu systray pendant que la fenetre est ouverte (Windows)
+    /// Permet de quitter l'application meme si la fenetre de config est affichee
+    fn drain_tray_menu_events(&self) {
+        #[cfg(windows)]
+        {
+            use tray_icon::menu::MenuEvent;
+            while let Ok(event) = MenuEvent::receiver().try_recv() {
+                // "Configurer" : la fenetre est deja ouverte
+                if let Some(command) = self.state.tray_command(event.id.as_ref()) {
+                    crate::gui::execute_command(&self.state, command);
+                }
+            }
+
+            // Drainer aussi les evenements de clic sur l'icone pour eviter l'accumulation
+            use tray_icon::TrayIconEvent;
+            while TrayIconEvent::receiver().try_recv().is_ok() {}
+        }
+    }
+}
+
+/// Champ texte d'un repertoire avec bouton de selection, etiquete par `label` pour les
+/// lecteurs d'ecran. Retourne true si la saisie a ete validee avec Entree.
+fn folder_field(ui: &mut egui::Ui, value: &mut String, label: egui::Id) -> bool {
+    ui.horizontal(|ui| {
+        let response = ui
+            .add(egui::TextEdit::singleline(value).desired_width(240.0))
+            .labelled_by(label);
+        let browse = crate::t!("gui.browse_folder");
+        let button = ui.small_button("...").on_hover_text(crate::i18n::visual(&browse));
+        button.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, true, browse.as_str()));
+        if button.clicked() {
+            let mut dialog = rfd::FileDialog::new();
+            if std::path::Path::new(value.as_str()).is_dir() {
+                dialog = dialog.set_directory(value.as_str());
+            }
+            if let Some(dir) = dialog.pick_folder() {
+                *value = dir.display().to_string();
+            }
+        }
+        submitted(&response)
+    })
+    .inner
+}
+
+/// Boutons "Exporter en CSV" et "Exporter en JSON" : format demande
+fn export_buttons(ui: &mut egui::Ui) -> Option<ExportFormat> {
+    let mut format = None;
+    if ui.button(crate::tv!("gui.export_csv")).clicked() {
+        format = Some(ExportFormat::Csv);
+    }
+    if ui.button(crate::tv!("gui.export_json")).clicked() {
+        format = Some(ExportFormat::Json);
+    }
+    format
+}
+
+/// Flux par pays de sortie (et octets relayes en JSON)
+fn stats_export(stats: &[(String, u64)], up: u64, down: u64, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => {
+            let mut csv = String::from("country,streams\n");
+            for (country, count) in stats {
+                csv.push_str(&format!("{},{}\n", crate::audit::csv_field(country), count));
+            }
+            csv
+        }
+        ExportFormat::Json => {
+            let countries: serde_json::Map<String, serde_json::Value> =
+                stats.iter().map(|(cc, n)| (cc.clone(), serde_json::json!(n))).collect();
+            let json = serde_json::json!({
+                "bytes_up": up,
+                "bytes_down": down,
+                "exit_countries": countries,
+            });
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        }
+    }
+}
+
+/// Trafic par domaine de destination
+fn destinations_export(destinations: &[(String, DestinationStats)], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => {
+            let mut csv = String::from("domain,connections,bytes_up,bytes_down\n");
+            for (domain, stats) in destinations {
+                csv.push_str(&format!(
+                    "{},{},{},{}\n",
+                    crate::audit::csv_field(domain),
+                    stats.connections,
+                    stats.up,
+                    stats.down
+                ));
+            }
+            csv
+        }
+        ExportFormat::Json => {
+            let entries: Vec<serde_json::Value> = destinations
+                .iter()
+                .map(|(domain, stats)| {
+                    serde_json::json!({
+                        "domain": domain,
+                        "connections": stats.connections,
+                        "bytes_up": stats.up,
+                        "bytes_down": stats.down,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&entries).unwrap_or_default()
+        }
+    }
+}
+
+/// Connexions de l'historique, memes colonnes que le journal d'audit plus l'issue
+fn history_export(history: &[HistoryEntry], format: ExportFormat) -> String {
+    let closed = |entry: &HistoryEntry| entry.closed.to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    match format {
+        ExportFormat::Csv => {
+            let mut csv = String::from("closed,conn,destination,port,duration_secs,bytes_up,bytes_down,outcome\n");
+            for entry in history {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{},{}\n",
+                    closed(entry),
+                    entry.conn_id,
+                    crate::audit::csv_field(&entry.host),
+                    entry.port,
+                    entry.duration.as_secs_f64(),
+                    entry.up,
+                    entry.down,
+                    entry.outcome
+                ));
+            }
+            csv
+        }
+        ExportFormat::Json => {
+            let entries: Vec<serde_json::Value> = history
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "closed": closed(entry),
+                        "conn": entry.conn_id,
+                        "destination": entry.host,
+                        "port": entry.port,
+                        "duration_secs": entry.duration.as_secs_f64(),
+                        "bytes_up": entry.up,
+                        "bytes_down": entry.down,
+                        "outcome": entry.outcome,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&entries).unwrap_or_default()
+        }
+    }
+}
+
+/// Indicateur d'activite annonce comme tel par les lecteurs d'ecran
+fn busy_spinner(ui: &mut egui::Ui) {
+    ui.spinner().widget_info(|| {
+        egui::WidgetInfo::labeled(egui::WidgetType::ProgressIndicator, true, crate::t!("gui.in_progress"))
+    });
+}
+
+/// Champ texte valide avec Entree (egui lui retire alors le focus)
+fn submitted(response: &egui::Response) -> bool {
+    response.lost_focus() && response.ctx.input(|i| i.key_pressed(egui::Key::Enter))
+}
+
+/// Polices systeme couvrant l'hebreu, absent des polices egui par defaut
+/// (relatives au dossier des polices de Windows)
+#[cfg(windows)]
+const HEBREW_FONTS: &[&str] = &["segoeui.ttf", "arial.ttf", "david.ttf"];
+
+/// Polices systeme couvrant l'hebreu, absent des polices egui par defaut
+#[cfg(not(windows))]
+const HEBREW_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/noto/NotoSansHebrew-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSansHebrew-Regular.ttf",
+    "/usr/share/fonts/google-noto/NotoSansHebrew-Regular.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+];
+
+/// Polices systeme couvrant le chinois et le japonais, absents des polices egui par defaut
+/// (relatives au dossier des polices de Windows)
+#[cfg(windows)]
+const CJK_FONTS: &[&str] = &["msyh.ttc", "YuGothR.ttc", "meiryo.ttc", "simsun.ttc"];
+
+/// Polices systeme couvrant le chinois et le japonais, absents des polices egui par defaut
+#[cfg(not(windows))]
+const CJK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// Polices de repli par ecriture : nom egui, fichiers candidats et message si aucun n'est present
+const SYSTEM_FONTS: &[(&str, &[&str], &str)] = &[
+    ("hebrew", HEBREW_FONTS, "gui.hebrew_font_missing"),
+    ("cjk", CJK_FONTS, "gui.cjk_font_missing"),
+];
+
+/// Ajoute en repli des polices egui la premiere police systeme trouvee pour chaque ecriture,
+/// pour les langues concernees et leurs noms dans la liste des langues
+fn install_system_fonts(ctx: &egui::Context) {
+    #[cfg(windows)]
+    let fonts_dir = std::env::var_os("WINDIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| "C:\\Windows".into())
+        .join("Fonts");
+    #[cfg(not(windows))]
+    let fonts_dir = std::path::PathBuf::new();
+
+    let mut fonts = egui::FontDefinitions::default();
+    for (name, candidates, missing) in SYSTEM_FONTS {
+        let Some(data) = candidates.iter().find_map(|font| std::fs::read(fonts_dir.join(font)).ok()) else {
+            tracing::debug!("{}", crate::tl!(missing));
+            continue;
+        };
+        fonts
+            .font_data
+            .insert(name.to_string(), Arc::new(egui::FontData::from_owned(data)));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().push(name.to_string());
+        }
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// Ramene un facteur d'echelle lu dans la configuration dans les bornes acceptees
+fn clamp_scale(scale: f32) -> f32 {
+    if scale.is_finite() {
+        scale.clamp(*SCALE_RANGE.start(), *SCALE_RANGE.end())
+    } else {
+        1.0
+    }
+}
+
+/// Applique l'echelle de l'interface par rapport a la densite native de l'ecran
+fn apply_scale(ctx: &egui::Context, scale: f32) {
+    let native = ctx.native_pixels_per_point().unwrap_or(1.0);
+    ctx.set_pixels_per_point(native * scale);
+}
+
+/// Preference egui correspondant au theme configure
+fn theme_preference(theme: GuiTheme) -> egui::ThemePreference {
+    match theme {
+        GuiTheme::Light => egui::ThemePreference::Light,
+        GuiTheme::Dark => egui::ThemePreference::Dark,
+        GuiTheme::System => egui::ThemePreference::System,
+    }
+}
+
+/// Adresse a utiliser localement pour joindre le proxy (une ecoute sur toutes
+/// les interfaces est joignable via le loopback)
+fn local_proxy_host(listen_addr: &str) -> String {
+    match listen_addr {
+        "0.0.0.0" | "::" | "[::]" => "127.0.0.1".to_string(),
+        addr => addr.to_string(),
+    }
+}
+
+/// Adresse a communiquer aux autres appareils : l'adresse du reseau local
+/// si le proxy ecoute sur toutes les interfaces, sinon l'adresse d'ecoute
+fn share_proxy_host(listen_addr: &str) -> String {
+    match listen_addr {
+        "0.0.0.0" | "::" | "[::]" => lan_address()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "127.0.0.1".to_string()),
+        addr => addr.to_string(),
+    }
+}
+
+/// Adresse locale de l'interface par defaut. Un socket UDP "connecte" ne fait que
+/// choisir la route : aucun paquet n'est envoye.
+fn lan_address() -> Option<std::net::IpAddr> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:9").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Dessine le QR code d'un texte (modules noirs sur fond blanc, marge de 4 modules)
+fn paint_qr_code(ui: &mut egui::Ui, text: &str) {
+    const MODULE: f32 = 4.0;
+    const QUIET: usize = 4;
+
+    let Ok(code) = qrcode::QrCode::new(text) else {
+        return;
+    };
+    let width = code.width();
+    let side = (width + 2 * QUIET) as f32 * MODULE;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != qrcode::Color::Dark {
+            continue;
+        }
+        let (x, y) = ((i % width + QUIET) as f32, (i / width + QUIET) as f32);
+        let min = rect.min + egui::vec2(x * MODULE, y * MODULE);
+        painter.rect_filled(
+            egui::Rect::from_min_size(min, egui::vec2(MODULE, MODULE)),
+            0.0,
+            egui::Color32::BLACK,
+        );
+    }
+}
+
+/// Formate le chemin d'un circuit : "FR a1b2c3d4 → DE e5f6a7b8 → US 01234567"
+fn format_circuit_path(circuit: &CircuitEntry) -> String {
+    circuit
+        .info
+        .relays
+        .iter()
+        .map(format_relay)
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// Formate un relais : code pays et debut de l'empreinte ("FR a1b2c3d4")
+fn format_relay(relay: &RelayInfo) -> String {
+    let fingerprint: String = relay.fingerprint.trim_start_matches('$').chars().take(8).collect();
+    match relay.country {
+        Some(ref cc) => format!("{} {}", cc, fingerprint),
+        None => format!("?? {}", fingerprint),
+    }
+}
+
+/// Formate une duree courte : "42s", "3m 05s"
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+impl eframe::App for IronCloakApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Rafraichir automatiquement toutes les secondes pour mettre a jour le statut
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+
+        // Suivre la geometrie de la fenetre et l'onglet pour les retrouver a la prochaine ouverture
+        {
+            let mut memory = self.memory.lock().unwrap();
+            ctx.input(|i| {
+                let viewport = i.viewport();
+                if let Some(rect) = viewport.outer_rect {
+                    memory.position = Some([rect.min.x, rect.min.y]);
+                }
+                if let Some(rect) = viewport.inner_rect {
+                    memory.size = Some([rect.width(), rect.height()]);
+                }
+            });
+            memory.tab = self.tab;
+        }
+
+        // Traiter les evenements systray (menu utilise pendant que la fenetre est ouverte)
+        self.drain_tray_menu_events();
+
+        // Detecter le changement de langue dans la liste deroulante → apercu instantane
+        if self.selected_lang_index != self.prev_lang_index {
+            let lang_code = languages()[self.selected_lang_index].code;
+            crate::i18n::init(lang_code);
+            self.prev_lang_index = self.selected_lang_index;
+        }
+
+        // Widget focalise ou liste ouverte avant ce rendu : Entree/Echap leur reviennent
+        // (egui retire le focus d'un champ texte des qu'Entree ou Echap y est presse)
+        let busy_before = ctx.memory(|m| m.focused().is_some() || m.any_popup_open());
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // Langue de droite a gauche : contenu aligne a droite et lignes (ui.horizontal)
+            // disposees de droite a gauche
+            let layout = if crate::i18n::is_rtl() {
+                egui::Layout::top_down(egui::Align::Max)
+            } else {
+                *ui.layout()
+            };
+            ui.with_layout(layout, |ui| self.show_tabs(ui));
+        });
+
+        // Etat chiffre : le backend attend la phrase secrete avant l'amorcage
+        if let Some(prompt) = self.state.get_passphrase_prompt() {
+            self.show_passphrase_prompt(ctx, &prompt);
+        } else if let Some(url) = self.state.captive_portal() {
+            self.show_captive_portal_prompt(ctx, &url);
+        }
+
+        self.handle_shortcuts(ctx, busy_before);
 
         // Si l'application doit quitter, fermer la fenetre
         if self.state.should_quit() {
@@ -0,0 +1,85 @@
+// Indicateur de sante des circuits : un flux Tor minuscule est ouvert periodiquement et son
+// temps d'etablissement mesure, pour distinguer "connecte mais inutilisable tant c'est lent"
+// de "fonctionne bien". Le niveau (bon, lent, mauvais) et la latence sont affiches dans la
+// fenetre, l'infobulle de la zone de notification et la commande "status".
+// Apres une nouvelle identite, un premier flux ouvert avec le nouveau jeton d'isolation
+// construit les circuits neufs et signale la fin de la reconstruction (proxy.rebuild).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arti_client::TorClient;
+use tor_rtcompat::PreferredRuntime;
+
+use crate::config::HealthConfig;
+use crate::gui::state::{AppState, HealthLevel, HealthSample};
+
+/// Premiere mesure peu apres l'amorcage (circuits deja construits par les premiers flux)
+const FIRST_PROBE_DELAY: Duration = Duration::from_secs(10);
+
+/// Delai au-dela duquel la mesure est abandonnee (niveau mauvais, sans latence)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Intervalle minimal entre deux mesures
+const MIN_INTERVAL_SECS: u64 = 10;
+
+/// Mesure la latence a intervalles reguliers (health.interval_secs) jusqu'a l'arret
+pub async fn run_health_probe(config: &HealthConfig, tor_client: Arc<TorClient<PreferredRuntime>>, state: Arc<AppState>) {
+    let interval = Duration::from_secs(config.interval_secs.max(MIN_INTERVAL_SECS));
+    tokio::time::sleep(FIRST_PROBE_DELAY).await;
+    loop {
+        // Memes circuits que les flux SOCKS : la mesure reflete ce que vivent les clients
+        let sample = match crate::probe::stream_latency(&tor_client, state.isolation_token(), PROBE_TIMEOUT).await {
+            Ok(latency) => {
+                let ms = latency.as_millis() as u64;
+                tracing::debug!("{}", crate::tl!("health.sample", ms = ms));
+                HealthSample {
+                    latency: Some(latency),
+                    level: level_for(config, ms),
+                }
+            }
+            Err(e) => {
+                tracing::warn!("{}", crate::tl!("health.probe_failed", error = format!("{:#}", e)));
+                HealthSample {
+                    latency: None,
+                    level: HealthLevel::Bad,
+                }
+            }
+        };
+        state.set_health(sample);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Reconstruit les circuits apres chaque nouvelle identite, jusqu'a l'arret
+pub async fn run_rebuild_monitor(tor_client: Arc<TorClient<PreferredRuntime>>, state: Arc<AppState>) {
+    let mut changes = state.subscribe_changes();
+    loop {
+        if state.rebuilding.load(std::sync::atomic::Ordering::Relaxed) && !state.is_offline() {
+            let token = state.isolation_token();
+            match crate::probe::stream_latency(&tor_client, token, PROBE_TIMEOUT).await {
+                Ok(latency) => tracing::debug!("{}", crate::tl!("health.rebuilt", ms = latency.as_millis() as u64)),
+                Err(e) => tracing::warn!("{}", crate::tl!("health.rebuild_failed", error = format!("{:#}", e))),
+            }
+            // Nouvelle identite demandee pendant la mesure : le tour suivant reconstruit encore
+            if state.isolation_token() == token {
+                state.set_rebuilding(false);
+            }
+            continue;
+        }
+        if changes.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Niveau de sante d'une latence selon les seuils health.slow_ms et health.bad_ms
+fn level_for(config: &HealthConfig, ms: u64) -> HealthLevel {
+    if ms >= config.bad_ms {
+        HealthLevel::Bad
+    } else if ms >= config.slow_ms {
+        HealthLevel::Slow
+    } else {
+        HealthLevel::Good
+    }
+}
@@ -1,69 +1,471 @@
 // Module d'internationalisation (i18n)
 // Charge les traductions depuis des fichiers JSON embarques dans le binaire
 // et fournit une macro t!() pour acceder aux messages traduits.
+// Les arguments sont nommes ({host}) et un marqueur peut choisir une variante selon la
+// categorie de pluriel de la langue ({count -> [one] ... *[other] ...}).
 // Utilise un RwLock pour permettre le changement de langue a chaud.
+// Les journaux ont leur propre langue (macro tl!()) : l'apercu d'une langue dans la fenetre
+// ne change pas celle des traces en cours d'execution.
+// Des paquets de langue (langs/<code>.json a cote de la configuration) peuvent completer ou
+// corriger les traductions embarquees, ou ajouter une langue, sans recompiler.
+// Les langues de droite a gauche (hebreu) sont remises dans l'ordre d'affichage pour egui,
+// qui dessine le texte de gauche a droite sans algorithme bidirectionnel.
 
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use unicode_bidi::{Level, ParagraphBidiInfo};
 
-// Fichiers JSON embarques dans le binaire
-const EN_JSON: &str = include_str!("../langs/en.json");
-const FR_JSON: &str = include_str!("../langs/fr.json");
-const ES_JSON: &str = include_str!("../langs/es.json");
+/// Langue disponible : code (cle `logging.language`), nom affiche, sens d'ecriture
+/// et traductions embarquees
+pub struct Language {
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Ecriture de droite a gauche
+    pub rtl: bool,
+    json: &'static str,
+    /// Paquet de langue charge au demarrage, prioritaire sur les traductions embarquees
+    pack: Option<&'static str>,
+}
+
+impl Language {
+    /// Traductions aplaties, celles du paquet de langue remplacant les embarquees
+    fn translations(&self) -> HashMap<String, String> {
+        let mut map = flatten_json(self.json);
+        if let Some(pack) = self.pack {
+            map.extend(flatten_json(pack));
+        }
+        map
+    }
+}
 
-// Singleton global contenant les traductions chargees (remplacable via RwLock)
+// Embarque langs/<code>.json dans le binaire ; `rtl` marque une langue de droite a gauche
+macro_rules! language {
+    (@ $code:literal, $name:literal, $rtl:literal) => {
+        Language {
+            code: $code,
+            name: $name,
+            rtl: $rtl,
+            json: include_str!(concat!("../langs/", $code, ".json")),
+            pack: None,
+        }
+    };
+    ($code:literal, $name:literal) => {
+        language!(@ $code, $name, false)
+    };
+    ($code:literal, $name:literal, rtl) => {
+        language!(@ $code, $name, true)
+    };
+}
+
+/// Langues embarquees, dans l'ordre de la liste deroulante de la fenetre.
+/// Ajouter une langue : deposer langs/<code>.json et l'ajouter ici (l'anglais sert de repli).
+const EMBEDDED: &[Language] = &[
+    language!("en", "English"),
+    language!("fr", "Francais"),
+    language!("es", "Espanol"),
+    language!("de", "Deutsch"),
+    language!("it", "Italiano"),
+    language!("pt-BR", "Portugues (Brasil)"),
+    language!("ru", "Русский"),
+    language!("zh-CN", "简体中文"),
+    language!("ja", "日本語"),
+    language!("he", "עברית", rtl),
+];
+
+/// Langues embarquees completees par les paquets de langue (voir `load_packs`)
+static LANGUAGES: OnceLock<Vec<Language>> = OnceLock::new();
+
+// Singletons globaux contenant les traductions chargees (remplacables via RwLock) :
+// interface (t!, tv!) et journaux (tl!)
 static I18N: RwLock<Option<I18nStore>> = RwLock::new(None);
+static LOG_I18N: RwLock<Option<I18nStore>> = RwLock::new(None);
 
 /// Stockage des traductions pour la langue selectionnee et le fallback anglais
 struct I18nStore {
     current: HashMap<String, String>,
     fallback: HashMap<String, String>,
+    language: &'static Language,
 }
 
-/// Initialise ou reinitialise le systeme i18n avec la langue demandee.
+/// Initialise ou reinitialise la langue de l'interface.
 /// Peut etre appele plusieurs fois pour changer de langue.
 pub fn init(language: &str) {
-    let current_json = match language {
-        "fr" => FR_JSON,
-        "es" => ES_JSON,
-        _ => EN_JSON,
-    };
+    *I18N.write().unwrap() = Some(I18nStore::load(language));
+}
 
-    let current = flatten_json(current_json);
-    let fallback = if language == "en" {
-        current.clone()
-    } else {
-        flatten_json(EN_JSON)
+/// Initialise ou reinitialise la langue des journaux
+pub fn init_log(language: &str) {
+    *LOG_I18N.write().unwrap() = Some(I18nStore::load(language));
+}
+
+impl I18nStore {
+    fn load(language: &str) -> Self {
+        let selected = find(language).unwrap_or(fallback_language());
+
+        let current = selected.translations();
+        let fallback = if selected.code == fallback_language().code {
+            current.clone()
+        } else {
+            fallback_language().translations()
+        };
+
+        I18nStore { current, fallback, language: selected }
+    }
+
+    fn get(&self, key: &str) -> String {
+        if let Some(val) = self.current.get(key) {
+            val.clone()
+        } else if let Some(val) = self.fallback.get(key) {
+            val.clone()
+        } else {
+            key.to_string()
+        }
+    }
+
+    fn get_with_args(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let template = self.get(key);
+        let mut result = String::with_capacity(template.len());
+        format_into(&mut result, &template, args, self.language);
+        result
+    }
+}
+
+/// Recherche une langue embarquee par son code (sans tenir compte de la casse : "pt-br" = "pt-BR")
+pub fn find(code: &str) -> Option<&'static Language> {
+    languages().iter().find(|lang| lang.code.eq_ignore_ascii_case(code))
+}
+
+/// Langues disponibles, dans l'ordre de la liste deroulante de la fenetre
+pub fn languages() -> &'static [Language] {
+    LANGUAGES.get().map_or(EMBEDDED, Vec::as_slice)
+}
+
+/// Langue de repli pour les cles absentes d'une traduction
+fn fallback_language() -> &'static Language {
+    &languages()[0]
+}
+
+/// Langue embarquee correspondant a la langue du systeme (variables LC_ALL, LC_MESSAGES, LANG
+/// sous Unix, parametres regionaux de l'utilisateur sous Windows), l'anglais a defaut.
+/// Sert aux messages affiches avant le chargement de la configuration.
+pub fn system_language() -> &'static str {
+    system_locale()
+        .and_then(|locale| match_locale(&locale))
+        .map_or(fallback_language().code, |lang| lang.code)
+}
+
+/// Langue embarquee d'une etiquette de langue ("fr_FR.UTF-8", "pt-BR", "zh_CN") : etiquette
+/// complete d'abord, puis langue seule ("pt" → "pt-BR")
+fn match_locale(locale: &str) -> Option<&'static Language> {
+    let tag = locale.split(['.', '@']).next().unwrap_or_default().replace('_', "-");
+    let primary = tag.split('-').next().unwrap_or_default();
+    if primary.is_empty() || primary.eq_ignore_ascii_case("C") || primary.eq_ignore_ascii_case("POSIX") {
+        return None;
+    }
+    find(&tag).or_else(|| {
+        languages()
+            .iter()
+            .find(|lang| lang.code.split('-').next().is_some_and(|code| code.eq_ignore_ascii_case(primary)))
+    })
+}
+
+#[cfg(unix)]
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use winapi::um::winnls::GetUserDefaultLocaleName;
+    use winapi::um::winnt::LOCALE_NAME_MAX_LENGTH;
+
+    let mut buf = [0u16; LOCALE_NAME_MAX_LENGTH];
+    // Longueur avec le zero final, 0 en cas d'echec
+    let len = unsafe { GetUserDefaultLocaleName(buf.as_mut_ptr(), buf.len() as i32) };
+    (len > 1).then(|| String::from_utf16_lossy(&buf[..len as usize - 1]))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn system_locale() -> Option<String> {
+    None
+}
+
+/// Charge les paquets de langue `<code>.json` de `dir` (absent : aucun paquet).
+/// Un paquet portant le code d'une langue embarquee remplace les cles qu'il definit ;
+/// un autre code ajoute une langue, nommee par `"meta": {"name": "...", "rtl": false}`
+/// (a defaut, par son code). A appeler une fois au demarrage, avant init() ; retourne le
+/// nombre de cles de chaque paquet, ou l'erreur qui l'a fait ignorer, pour le journal.
+pub fn load_packs(dir: &Path) -> Vec<(PathBuf, Result<usize, String>)> {
+    let mut paths: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => return Vec::new(),
     };
+    paths.sort();
 
-    let mut store = I18N.write().unwrap();
-    *store = Some(I18nStore { current, fallback });
+    let mut languages: Vec<Language> = EMBEDDED
+        .iter()
+        .map(|lang| Language { pack: None, ..*lang })
+        .collect();
+    let mut report = Vec::with_capacity(paths.len());
+    for path in paths {
+        let result = load_pack(&path, &mut languages);
+        report.push((path, result));
+    }
+    let _ = LANGUAGES.set(languages);
+    report
+}
+
+fn load_pack(path: &Path, languages: &mut Vec<Language>) -> Result<usize, String> {
+    let code = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| path.display().to_string())?;
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let mut keys = HashMap::new();
+    flatten_value(&value, "", &mut keys);
+
+    // Contenu conserve pour toute la duree du programme, comme les traductions embarquees
+    let pack: &'static str = Box::leak(content.into_boxed_str());
+    match languages.iter_mut().find(|lang| lang.code.eq_ignore_ascii_case(code)) {
+        Some(lang) => lang.pack = Some(pack),
+        None => {
+            let meta = &value["meta"];
+            let name = meta["name"].as_str().unwrap_or(code).to_string();
+            languages.push(Language {
+                code: Box::leak(code.to_string().into_boxed_str()),
+                name: Box::leak(name.into_boxed_str()),
+                rtl: meta["rtl"].as_bool().unwrap_or(false),
+                json: "{}",
+                pack: Some(pack),
+            });
+        }
+    }
+    Ok(keys.len())
+}
+
+/// Indique si la langue de l'interface s'ecrit de droite a gauche
+pub fn is_rtl() -> bool {
+    I18N.read().unwrap().as_ref().is_some_and(|store| store.language.rtl)
 }
 
 /// Recupere un message traduit par sa cle pointee (ex: "tor.connected").
 /// Retourne le fallback anglais si la cle n'existe pas dans la langue courante.
 pub fn get(key: &str) -> String {
     let store = I18N.read().unwrap();
-    let store = store.as_ref().expect("i18n non initialise — appeler i18n::init() d'abord");
-    if let Some(val) = store.current.get(key) {
-        val.clone()
-    } else if let Some(val) = store.fallback.get(key) {
-        val.clone()
-    } else {
-        key.to_string()
+    store.as_ref().expect("i18n non initialise — appeler i18n::init() d'abord").get(key)
+}
+
+/// Comme get(), dans la langue des journaux
+pub fn get_log(key: &str) -> String {
+    let store = LOG_I18N.read().unwrap();
+    store.as_ref().expect("i18n non initialise — appeler i18n::init_log() d'abord").get(key)
+}
+
+/// Recupere un message traduit et remplace les arguments nommes ({host}, {count}...).
+/// Le modele est parcouru une seule fois : l'ordre des marqueurs peut differer d'une langue
+/// a l'autre et un argument contenant "{host}" n'est pas remplace a son tour. Dans une langue
+/// de droite a gauche, chaque argument est isole (U+2068..U+2069) pour qu'une adresse ou un
+/// chemin garde son sens de lecture au milieu de la phrase.
+///
+/// Un marqueur de selection choisit une variante selon un argument numerique :
+/// `{count -> [0] aucun fichier [one] {count} fichier *[other] {count} fichiers}`.
+/// Une cle numerique exacte l'emporte sur la categorie de pluriel de la langue (voir
+/// `plural_category`) ; a defaut, la variante marquee `*` est retenue. Le texte d'une variante
+/// peut contenir d'autres marqueurs mais pas de `[` en dehors de ceux-ci.
+pub fn get_with_args(key: &str, args: &[(&str, &str)]) -> String {
+    let store = I18N.read().unwrap();
+    store.as_ref().expect("i18n non initialise — appeler i18n::init() d'abord").get_with_args(key, args)
+}
+
+/// Comme get_with_args(), dans la langue des journaux
+pub fn get_log_with_args(key: &str, args: &[(&str, &str)]) -> String {
+    let store = LOG_I18N.read().unwrap();
+    store.as_ref().expect("i18n non initialise — appeler i18n::init_log() d'abord").get_with_args(key, args)
+}
+
+fn format_into(result: &mut String, template: &str, args: &[(&str, &str)], language: &Language) {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(len) = placeable_len(&rest[start..]) else {
+            rest = &rest[start..];
+            break;
+        };
+        let placeable = &rest[start + 1..start + len - 1];
+        match placeable.split_once("->") {
+            Some((selector, variants)) => {
+                match select_variant(selector.trim(), variants, args, language) {
+                    Some(variant) => format_into(result, variant.trim(), args, language),
+                    None => result.push_str(&rest[start..start + len]),
+                }
+            }
+            None => match args.iter().find(|(name, _)| *name == placeable.trim()) {
+                Some((_, value)) if language.rtl => {
+                    result.push(FIRST_STRONG_ISOLATE);
+                    result.push_str(value);
+                    result.push(POP_DIRECTIONAL_ISOLATE);
+                }
+                Some((_, value)) => result.push_str(value),
+                None => result.push_str(&rest[start..start + len]),
+            },
+        }
+        rest = &rest[start + len..];
     }
+    result.push_str(rest);
 }
 
-/// Recupere un message traduit et remplace les arguments positionnels {0}, {1}, etc.
-pub fn get_with_args(key: &str, args: &[&str]) -> String {
-    let template = get(key);
-    let mut result = template;
-    for (i, arg) in args.iter().enumerate() {
-        result = result.replace(&format!("{{{}}}", i), arg);
+/// Longueur du marqueur qui commence `text` (accolades comprises), marqueurs imbriques inclus
+fn placeable_len(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Variante retenue par `{selector -> [cle] texte *[cle] texte}`
+fn select_variant<'a>(
+    selector: &str,
+    variants: &'a str,
+    args: &[(&str, &str)],
+    language: &Language,
+) -> Option<&'a str> {
+    let value = args.iter().find(|(name, _)| *name == selector)?.1;
+    let n: u64 = value.parse().ok()?;
+    let category = plural_category(language.code, n);
+
+    let mut parsed = Vec::new();
+    let mut rest = variants;
+    while let Some(open) = rest.find('[') {
+        let is_default = rest[..open].trim_end().ends_with('*');
+        let close = open + rest[open..].find(']')?;
+        let key = rest[open + 1..close].trim();
+        rest = &rest[close + 1..];
+        let end = variant_end(rest);
+        parsed.push((key, is_default, &rest[..end]));
+        rest = &rest[end..];
+    }
+
+    parsed
+        .iter()
+        .find(|(key, _, _)| key.parse::<u64>().ok() == Some(n))
+        .or_else(|| parsed.iter().find(|(key, _, _)| *key == category))
+        .or_else(|| parsed.iter().find(|(_, is_default, _)| *is_default))
+        .or(parsed.last())
+        .map(|(_, _, text)| text.trim_end().trim_end_matches('*'))
+}
+
+/// Fin du texte d'une variante : prochain `[` hors marqueur imbrique (ou fin du texte)
+fn variant_end(text: &str) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '[' if depth == 0 => return i,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Categorie de pluriel CLDR d'un nombre entier dans une langue embarquee
+fn plural_category(code: &str, n: u64) -> &'static str {
+    match code {
+        // 0 et 1 au singulier
+        "fr" | "pt-BR" if n <= 1 => "one",
+        "ru" => match (n % 10, n % 100) {
+            (1, m) if m != 11 => "one",
+            (2..=4, m) if !(12..=14).contains(&m) => "few",
+            _ => "many",
+        },
+        "he" => match n {
+            1 => "one",
+            2 => "two",
+            _ => "other",
+        },
+        // Pas de pluriel grammatical
+        "zh-CN" | "ja" => "other",
+        _ if n == 1 => "one",
+        _ => "other",
+    }
+}
+
+/// Isolat dont le sens est donne par le premier caractere fort de l'argument
+const FIRST_STRONG_ISOLATE: char = '\u{2068}';
+/// Fin de l'isolat
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
+
+/// Remet un texte dans l'ordre d'affichage (algorithme bidirectionnel Unicode, ligne par ligne)
+/// pour egui, qui dessine les caracteres de gauche a droite dans l'ordre de la chaine.
+/// Les segments de droite a gauche sont inverses et leurs parentheses retournees, les
+/// caracteres de controle bidi (sans glyphe) retires. Le texte sans caractere de droite a
+/// gauche est rendu tel quel. Les journaux et l'info-bulle de la zone de notification gardent
+/// l'ordre logique : ce sont le terminal et le systeme qui l'affichent.
+pub fn visual(text: &str) -> String {
+    let base = is_rtl().then(Level::rtl);
+    let mut result = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        let info = ParagraphBidiInfo::new(line, base);
+        if !info.has_rtl() {
+            result.extend(line.chars().filter(|c| !is_bidi_control(*c)));
+            continue;
+        }
+        let (levels, runs) = info.visual_runs(0..line.len());
+        for run in runs {
+            let segment = line[run.clone()].chars().filter(|c| !is_bidi_control(*c));
+            if levels[run.start].is_rtl() {
+                result.extend(segment.rev().map(mirror));
+            } else {
+                result.extend(segment);
+            }
+        }
     }
     result
 }
 
+/// Marques et isolats bidi : invisibles, absents des polices egui
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Glyphe miroir d'une ponctuation appariee dans un segment de droite a gauche
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        c => c,
+    }
+}
+
 /// Aplatit un JSON imbrique en cles pointees.
 /// Ex: {"tor": {"connected": "ok"}} → {"tor.connected": "ok"}
 fn flatten_json(json_str: &str) -> HashMap<String, String> {
@@ -94,15 +496,108 @@ fn flatten_value(value: &serde_json::Value, prefix: &str, map: &mut HashMap<Stri
 }
 
 /// Macro pour acceder facilement aux traductions.
-/// Usage : t!("tor.connected") ou t!("socks.listening", bind_addr)
+/// Usage : t!("tor.connected") ou t!("socks.listening", addr = bind_addr)
 #[macro_export]
 macro_rules! t {
     ($key:expr) => {
         $crate::i18n::get($key)
     };
-    ($key:expr, $($arg:expr),+) => {{
-        let args: Vec<String> = vec![$($arg.to_string()),+];
-        let refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    ($key:expr, $($name:ident = $arg:expr),+ $(,)?) => {{
+        let args: Vec<(&str, String)> = vec![$((stringify!($name), $arg.to_string())),+];
+        let refs: Vec<(&str, &str)> = args.iter().map(|(name, value)| (*name, value.as_str())).collect();
         $crate::i18n::get_with_args($key, &refs)
     }};
 }
+
+/// Comme t!(), mais dans la langue des journaux (`logging.language`) : pour les traces
+#[macro_export]
+macro_rules! tl {
+    ($key:expr) => {
+        $crate::i18n::get_log($key)
+    };
+    ($key:expr, $($name:ident = $arg:expr),+ $(,)?) => {{
+        let args: Vec<(&str, String)> = vec![$((stringify!($name), $arg.to_string())),+];
+        let refs: Vec<(&str, &str)> = args.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        $crate::i18n::get_log_with_args($key, &refs)
+    }};
+}
+
+/// Comme t!(), mais dans l'ordre d'affichage (voir `i18n::visual`) : pour le texte
+/// dessine par egui. Les journaux utilisent tl!().
+#[macro_export]
+macro_rules! tv {
+    ($($arg:tt)+) => {
+        $crate::i18n::visual(&$crate::t!($($arg)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(code: &str, template: &str, args: &[(&str, &str)]) -> String {
+        let mut result = String::new();
+        format_into(&mut result, template, args, find(code).unwrap());
+        result
+    }
+
+    #[test]
+    fn plural_categories() {
+        assert_eq!([0, 1, 2].map(|n| plural_category("en", n)), ["other", "one", "other"]);
+        assert_eq!([0, 1, 2].map(|n| plural_category("fr", n)), ["one", "one", "other"]);
+        assert_eq!([0, 1, 2].map(|n| plural_category("pt-BR", n)), ["one", "one", "other"]);
+        assert_eq!([0, 1, 2].map(|n| plural_category("de", n)), ["other", "one", "other"]);
+        assert_eq!(
+            [1, 2, 4, 5, 11, 12, 14, 21, 22, 25, 111].map(|n| plural_category("ru", n)),
+            ["one", "few", "few", "many", "many", "many", "many", "one", "few", "many", "many"]
+        );
+        assert_eq!([1, 2, 3, 10].map(|n| plural_category("he", n)), ["one", "two", "other", "other"]);
+        assert_eq!([0, 1, 2].map(|n| plural_category("ja", n)), ["other", "other", "other"]);
+        assert_eq!([0, 1, 2].map(|n| plural_category("zh-CN", n)), ["other", "other", "other"]);
+    }
+
+    #[test]
+    fn placeholders_are_replaced_once() {
+        assert_eq!(format("en", "Connecting to {host}:{port}", &[("host", "example.com"), ("port", "443")]), "Connecting to example.com:443");
+        // Ordre libre, espaces tolerees dans le marqueur
+        assert_eq!(format("en", "{ b } then {a}", &[("a", "1"), ("b", "2")]), "2 then 1");
+        // Un argument contenant un marqueur n'est pas remplace a son tour
+        assert_eq!(format("en", "{a}{b}", &[("a", "{b}"), ("b", "x")]), "{b}x");
+        // Marqueur inconnu ou accolade non fermee : texte conserve
+        assert_eq!(format("en", "{missing} and {a", &[("a", "1")]), "{missing} and {a");
+    }
+
+    #[test]
+    fn rtl_arguments_are_isolated() {
+        assert_eq!(format("he", "x {a} y", &[("a", "192.0.2.1")]), "x \u{2068}192.0.2.1\u{2069} y");
+        assert_eq!(format("en", "x {a} y", &[("a", "192.0.2.1")]), "x 192.0.2.1 y");
+    }
+
+    #[test]
+    fn selectors_pick_exact_key_then_category_then_default() {
+        let template = "{count -> [0] no file [one] {count} file *[other] {count} files}";
+        assert_eq!(format("en", template, &[("count", "0")]), "no file");
+        assert_eq!(format("en", template, &[("count", "1")]), "1 file");
+        assert_eq!(format("en", template, &[("count", "7")]), "7 files");
+
+        // Categorie de la langue : 0 est au singulier en francais
+        let template = "{n -> [one] {n} fichier *[other] {n} fichiers}";
+        assert_eq!(format("fr", template, &[("n", "0")]), "0 fichier");
+        assert_eq!(format("fr", template, &[("n", "2")]), "2 fichiers");
+
+        // Russe : few et many, variante par defaut pour une categorie absente
+        let template = "{n -> [one] {n} most [few] {n} mosta *[many] {n} mostov}";
+        assert_eq!(format("ru", template, &[("n", "21")]), "21 most");
+        assert_eq!(format("ru", template, &[("n", "3")]), "3 mosta");
+        assert_eq!(format("ru", template, &[("n", "11")]), "11 mostov");
+        let template = "{n -> [one] {n} most *[other] {n} mostov}";
+        assert_eq!(format("ru", template, &[("n", "5")]), "5 mostov");
+    }
+
+    #[test]
+    fn selectors_without_numeric_argument_are_kept() {
+        let template = "a {count -> [one] one *[other] other} b";
+        assert_eq!(format("en", template, &[("count", "many")]), template);
+        assert_eq!(format("en", template, &[]), template);
+    }
+}
@@ -0,0 +1,185 @@
+// Kill switch systeme : tant qu'il est actif, seul le trafic d'IronCloak (Tor) peut sortir.
+// Si le proxy meurt, les regles restent en place et aucune application ne fuit en clair.
+// Linux : table nftables dediee (necessite CAP_NET_ADMIN), retiree a l'arret propre. Seuls
+// les sockets du groupe dedie (kill_switch.group) sortent : IronCloak doit tourner avec ce
+// groupe comme groupe effectif (Group= de systemd, ou sg), que les autres applications de
+// l'utilisateur, root compris, n'ont pas.
+// Les programmes lances par IronCloak (navigateur, gestionnaire de fichiers) reprennent le
+// groupe principal de l'utilisateur avant exec (voir confine_child), sans quoi ils
+// heriteraient du groupe dedie et sortiraient hors Tor.
+// Windows (WFP) et autres plateformes : non pris en charge, l'activation est refusee.
+
+use std::process::Command;
+#[cfg(target_os = "linux")]
+use std::sync::OnceLock;
+
+use anyhow::Result;
+
+use crate::config::IronCloakConfig;
+
+/// Groupe principal de l'utilisateur, rendu aux programmes lances tant que le kill switch est
+/// actif (vide : kill switch inactif)
+#[cfg(target_os = "linux")]
+static CHILD_GID: OnceLock<u32> = OnceLock::new();
+
+/// Regles de pare-feu installees, retirees a la destruction (arret propre)
+pub struct KillSwitch {
+    #[cfg(target_os = "linux")]
+    table: String,
+}
+
+/// Installe le kill switch si active dans la configuration.
+/// Retourne `None` si desactive, une erreur s'il ne peut pas etre applique (plateforme non
+/// prise en charge, groupe dedie absent ou partage) : mieux vaut ne pas demarrer que de
+/// laisser croire a une protection inexistante.
+pub fn enable(config: &IronCloakConfig) -> Result<Option<KillSwitch>> {
+    if !config.kill_switch.enabled {
+        return Ok(None);
+    }
+    install(&config.kill_switch.group).map(Some)
+}
+
+#[cfg(target_os = "linux")]
+fn install(group: &str) -> Result<KillSwitch> {
+    use anyhow::Context;
+
+    let (gid, primary) = dedicated_gid(group)?;
+
+    // Une table par processus : lors d'un redemarrage, le nouveau processus installe
+    // ses regles avant que l'ancien ne retire les siennes, sans fenetre de fuite
+    let table = format!("ironcloak_{}", std::process::id());
+
+    // Politique "drop" en sortie : seuls le loopback et les sockets du groupe dedie passent
+    let ruleset = format!(
+        "table inet {table} {{\n\
+         \tchain output {{\n\
+         \t\ttype filter hook output priority 0; policy drop;\n\
+         \t\toif \"lo\" accept\n\
+         \t\tmeta skgid {gid} accept\n\
+         \t}}\n\
+         }}\n"
+    );
+    nft(&["-f", "-"], Some(&ruleset)).context(crate::t!("killswitch.install_failed"))?;
+
+    tracing::info!("{}", crate::tl!("killswitch.enabled", group = group, gid = gid));
+
+    // Nettoyer les tables laissees par des processus disparus (crash precedent) :
+    // nos propres regles sont deja actives, elles prennent le relais
+    remove_stale_tables(&table);
+
+    let _ = CHILD_GID.set(primary);
+    Ok(KillSwitch { table })
+}
+
+/// Gid du groupe dedie, verifie : il doit etre le groupe effectif du processus (les sockets
+/// d'IronCloak le portent) sans etre celui de root ni le groupe principal de l'utilisateur,
+/// que partagent toutes ses applications. Rendu avec ce groupe principal.
+#[cfg(target_os = "linux")]
+fn dedicated_gid(group: &str) -> Result<(u32, u32)> {
+    let name = std::ffi::CString::new(group)
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("killswitch.unknown_group", group = group)))?;
+    let entry = unsafe { libc::getgrnam(name.as_ptr()) };
+    if entry.is_null() {
+        anyhow::bail!("{}", crate::t!("killswitch.unknown_group", group = group));
+    }
+    let gid = unsafe { (*entry).gr_gid };
+
+    let current = unsafe { libc::getegid() };
+    if current != gid {
+        anyhow::bail!("{}", crate::t!("killswitch.wrong_group", group = group, gid = current));
+    }
+    let user = unsafe { libc::getpwuid(libc::getuid()) };
+    if user.is_null() {
+        anyhow::bail!("{}", crate::t!("killswitch.no_primary_group"));
+    }
+    let primary = unsafe { (*user).pw_gid };
+    if gid == 0 || primary == gid {
+        anyhow::bail!("{}", crate::t!("killswitch.shared_group", group = group));
+    }
+    Ok((gid, primary))
+}
+
+/// Prepare un programme lance par IronCloak : avec le kill switch actif, il repasse au groupe
+/// principal de l'utilisateur avant exec (groupes reel, effectif et sauvegarde), et ses
+/// sockets restent bloques. Si le changement de groupe est refuse (ni CAP_SETGID, ni groupe
+/// reel de l'utilisateur), le lancement echoue plutot que de sortir hors Tor.
+/// Exceptions voulues : la relance d'IronCloak lui-meme, les transports enfichables (lances
+/// par arti) et la page d'un portail captif (gui::open_url), qui doit justement sortir hors Tor.
+pub fn confine_child(command: &mut Command) {
+    #[cfg(target_os = "linux")]
+    if let Some(&gid) = CHILD_GID.get() {
+        use std::os::unix::process::CommandExt;
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setresgid(gid, gid, gid) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = command;
+}
+
+#[cfg(not(target_os = "linux"))]
+fn install(_group: &str) -> Result<KillSwitch> {
+    anyhow::bail!("{}", crate::t!("killswitch.unsupported"))
+}
+
+/// Execute `nft` avec les arguments donnes, en lui passant `input` sur l'entree standard
+#[cfg(target_os = "linux")]
+fn nft(args: &[&str], input: Option<&str>) -> Result<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("nft")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(input) = input {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Supprime les tables "ironcloak_<pid>" dont le processus n'existe plus
+#[cfg(target_os = "linux")]
+fn remove_stale_tables(current: &str) {
+    let Ok(tables) = nft(&["list", "tables"], None) else {
+        return;
+    };
+    for line in tables.lines() {
+        // Format : "table inet ironcloak_1234"
+        let Some(name) = line.trim().strip_prefix("table inet ") else {
+            continue;
+        };
+        let Some(pid) = name.strip_prefix("ironcloak_") else {
+            continue;
+        };
+        if name == current || std::path::Path::new("/proc").join(pid).exists() {
+            continue;
+        }
+        if nft(&["delete", "table", "inet", name], None).is_ok() {
+            tracing::info!("{}", crate::tl!("killswitch.stale_removed", table = name));
+        }
+    }
+}
+
+impl Drop for KillSwitch {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        match nft(&["delete", "table", "inet", &self.table], None) {
+            Ok(_) => tracing::info!("{}", crate::tl!("killswitch.disabled")),
+            Err(e) => tracing::error!("{}", crate::tl!("killswitch.remove_failed", error = e)),
+        }
+    }
+}
@@ -0,0 +1,720 @@
+// Ecriture des fichiers de journal.
+// Un fichier par jour dans {log_dir}/AAAA/MM/ (ironcloak.AAAA-MM-JJ), comme l'appender
+// quotidien de tracing_appender, mais decoupe par taille (logging.max_file_mb) :
+// ironcloak.AAAA-MM-JJ.1, .2... Les fichiers termines peuvent etre compresses en gzip
+// (logging.compress) dans un thread a part, sans retarder l'ecriture des traces.
+// Une tache de fond supprime les plus anciens (logging.keep_days, logging.max_total_mb).
+// Sous Linux, les traces peuvent aussi partir vers le journal systeme (logging.syslog),
+// sous Windows les avertissements et erreurs vers le journal d'evenements (logging.event_log).
+// Les hotes de destination peuvent etre masques dans les traces (logging.redact_destinations).
+// Le filtre des traces est rechargeable : niveau modifiable sans redemarrer (fenetre, socket
+// de controle). Les paniques sont journalisees avec leur pile d'appels (et signalees par une boite de
+// dialogue sous Windows en release, faute de console).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+#[cfg(any(unix, windows))]
+use tracing::{Level, Metadata};
+#[cfg(any(unix, windows))]
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::config::{LoggingConfig, Redaction};
+
+/// Prefixe des fichiers de journal
+const PREFIX: &str = "ironcloak";
+
+/// Intervalle entre deux passes de nettoyage des anciens journaux
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Poignee de rechargement du filtre des traces, posee a l'initialisation du logging
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Rend le filtre des traces rechargeable par set_filter() (premiere couche du registre)
+pub fn reloadable_filter(filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(handle);
+    layer
+}
+
+/// Remplace le filtre des traces sans redemarrer (ex : "debug", "info,arti_client=warn")
+pub fn set_filter(directives: &str) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| anyhow::anyhow!("{}", crate::t!("app.log_filter_invalid", filter = directives, error = e)))?;
+    let handle = LOG_FILTER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("app.log_filter_unavailable")))?;
+    handle.reload(filter)?;
+    tracing::info!("{}", crate::tl!("app.log_filter_changed", filter = directives));
+    Ok(())
+}
+
+/// Filtre des traces en vigueur
+pub fn current_filter() -> Option<String> {
+    LOG_FILTER.get()?.with_current(|filter| filter.to_string()).ok()
+}
+
+/// Sel des empreintes d'hotes dans les traces, tire au demarrage : une meme destination garde
+/// la meme empreinte pendant l'execution, sans lien d'un lancement a l'autre
+static REDACTION_SALT: OnceLock<[u8; 16]> = OnceLock::new();
+
+/// Hote de destination tel qu'il apparait dans les traces (logging.redact_destinations)
+pub fn redact_host(mode: Redaction, host: &str) -> String {
+    match mode {
+        Redaction::Off => host.to_string(),
+        Redaction::Hash => {
+            let salt = REDACTION_SALT.get_or_init(rand::random);
+            format!("#{}", &crate::audit::hash_host(host, salt)[..12])
+        }
+        // Sous-domaines masques, dernier octet (IPv4) ou interface (IPv6) masques
+        Redaction::Truncate => match host.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(ip)) => {
+                let [a, b, c, _] = ip.octets();
+                format!("{}.{}.{}.*", a, b, c)
+            }
+            Ok(std::net::IpAddr::V6(ip)) => {
+                let s = ip.segments();
+                format!("{:x}:{:x}:{:x}:*", s[0], s[1], s[2])
+            }
+            Err(_) => {
+                let domain = crate::audit::domain_only(host);
+                if domain.len() == host.len() {
+                    domain.to_string()
+                } else {
+                    format!("*.{}", domain)
+                }
+            }
+        },
+    }
+}
+
+/// Writer de fichiers de journal avec rotation quotidienne et par taille
+pub struct RollingWriter {
+    log_dir: PathBuf,
+    max_bytes: Option<u64>,
+    compress: bool,
+    date: NaiveDate,
+    /// Numero du fichier du jour (0 : ironcloak.AAAA-MM-JJ sans suffixe)
+    index: u32,
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RollingWriter {
+    /// Ouvre le fichier du jour, en reprenant le dernier fichier non compresse s'il existe
+    pub fn new(config: &LoggingConfig) -> io::Result<Self> {
+        let log_dir = PathBuf::from(&config.log_dir);
+        let date = Local::now().date_naive();
+        let index = last_index(&log_dir, date);
+        let path = file_path(&log_dir, date, index);
+        let (file, size) = open(&path)?;
+        Ok(Self {
+            log_dir,
+            max_bytes: config.max_file_mb.filter(|mb| *mb > 0).map(|mb| mb * 1024 * 1024),
+            compress: config.compress,
+            date,
+            index,
+            path,
+            file,
+            size,
+        })
+    }
+
+    /// Passe au fichier suivant si le jour a change ou si le fichier courant est plein
+    fn roll(&mut self) -> io::Result<()> {
+        let today = Local::now().date_naive();
+        let full = self.max_bytes.is_some_and(|max| self.size >= max);
+        if today == self.date && !full {
+            return Ok(());
+        }
+        self.file.flush()?;
+
+        let index = if today != self.date {
+            last_index(&self.log_dir, today)
+        } else {
+            next_index(&self.log_dir, today, self.index)
+        };
+        let path = file_path(&self.log_dir, today, index);
+        let (file, size) = open(&path)?;
+
+        // Le fichier termine est ferme (remplace) avant d'etre compresse
+        self.file = file;
+        let finished = std::mem::replace(&mut self.path, path);
+        self.date = today;
+        self.index = index;
+        self.size = size;
+        if self.compress {
+            std::thread::spawn(move || {
+                // Echec sans consequence : le fichier reste lisible, non compresse
+                let _ = compress(&finished);
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Write for RollingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.roll()?;
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// {log_dir}/AAAA/MM/ironcloak.AAAA-MM-JJ[.n]
+fn file_path(log_dir: &Path, date: NaiveDate, index: u32) -> PathBuf {
+    let name = format!("{}.{}", PREFIX, date.format("%Y-%m-%d"));
+    let name = if index == 0 { name } else { format!("{}.{}", name, index) };
+    log_dir
+        .join(date.format("%Y").to_string())
+        .join(date.format("%m").to_string())
+        .join(name)
+}
+
+/// Fichier du jour (compresse ou non) deja present
+fn exists(log_dir: &Path, date: NaiveDate, index: u32) -> bool {
+    let path = file_path(log_dir, date, index);
+    path.exists() || gz_path(&path).exists()
+}
+
+/// Dernier fichier du jour a reprendre, ou le suivant si celui-ci est deja compresse
+fn last_index(log_dir: &Path, date: NaiveDate) -> u32 {
+    let mut index = 0;
+    while exists(log_dir, date, index + 1) {
+        index += 1;
+    }
+    if gz_path(&file_path(log_dir, date, index)).exists() {
+        index + 1
+    } else {
+        index
+    }
+}
+
+/// Premier numero libre apres `index`
+fn next_index(log_dir: &Path, date: NaiveDate, index: u32) -> u32 {
+    let mut next = index + 1;
+    while exists(log_dir, date, next) {
+        next += 1;
+    }
+    next
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// Ouvre un fichier de journal en ajout et retourne sa taille actuelle
+fn open(path: &Path) -> io::Result<(File, u64)> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok((file, size))
+}
+
+/// Compresse un fichier termine en <fichier>.gz puis supprime l'original
+fn compress(path: &Path) -> io::Result<()> {
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(gz_path(path))?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    drop(input);
+    fs::remove_file(path)
+}
+
+/// Supprime periodiquement les journaux de plus de `keep_days` jours, puis les plus anciens
+/// tant que le total depasse `max_total_mb`. Le fichier en cours d'ecriture est conserve.
+pub async fn run_pruner(config: LoggingConfig) {
+    loop {
+        let pass = config.clone();
+        match tokio::task::spawn_blocking(move || prune(&pass)).await {
+            Ok(Ok((0, _))) => {}
+            Ok(Ok((count, bytes))) => {
+                tracing::info!(
+                    "{}",
+                    crate::tl!("app.logs_pruned", count = count, size = bytes / (1024 * 1024))
+                );
+            }
+            Ok(Err(e)) => tracing::warn!("{}", crate::tl!("app.logs_prune_failed", error = e)),
+            Err(e) => tracing::warn!("{}", crate::tl!("app.logs_prune_failed", error = e)),
+        }
+        tokio::time::sleep(PRUNE_INTERVAL).await;
+    }
+}
+
+/// Fichier de journal trouve dans l'arborescence {log_dir}/AAAA/MM/
+struct LogFile {
+    path: PathBuf,
+    date: NaiveDate,
+    index: u32,
+    size: u64,
+}
+
+/// Une passe de nettoyage : retourne le nombre de fichiers supprimes et leur taille
+fn prune(config: &LoggingConfig) -> io::Result<(usize, u64)> {
+    let log_dir = Path::new(&config.log_dir);
+    let mut files = list_log_files(log_dir)?;
+    files.sort_by_key(|file| (file.date, file.index));
+    // Le plus recent est celui que RollingWriter remplit
+    files.pop();
+
+    let today = Local::now().date_naive();
+    let oldest_kept = config
+        .keep_days
+        .filter(|days| *days > 0)
+        .and_then(|days| today.checked_sub_days(chrono::Days::new(days)));
+    let mut total: u64 = files.iter().map(|file| file.size).sum();
+    let max_total = config.max_total_mb.filter(|mb| *mb > 0).map(|mb| mb * 1024 * 1024);
+
+    let mut removed = (0, 0);
+    for file in &files {
+        let expired = oldest_kept.is_some_and(|oldest| file.date < oldest);
+        let over_quota = max_total.is_some_and(|max| total > max);
+        if !expired && !over_quota {
+            break;
+        }
+        fs::remove_file(&file.path)?;
+        total -= file.size;
+        removed.0 += 1;
+        removed.1 += file.size;
+        // Repertoires mensuel et annuel vides (echec sans consequence s'ils ne le sont pas)
+        if let Some(month) = file.path.parent() {
+            if fs::remove_dir(month).is_ok() {
+                if let Some(year) = month.parent() {
+                    let _ = fs::remove_dir(year);
+                }
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Fichiers ironcloak.AAAA-MM-JJ[.n][.gz] de {log_dir}/AAAA/MM/
+fn list_log_files(log_dir: &Path) -> io::Result<Vec<LogFile>> {
+    let mut files = Vec::new();
+    if !log_dir.is_dir() {
+        return Ok(files);
+    }
+    for year in fs::read_dir(log_dir)?.flatten().filter(|e| e.path().is_dir()) {
+        for month in fs::read_dir(year.path())?.flatten().filter(|e| e.path().is_dir()) {
+            for entry in fs::read_dir(month.path())?.flatten() {
+                let name = entry.file_name();
+                let Some((date, index)) = name.to_str().and_then(parse_file_name) else {
+                    continue;
+                };
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                files.push(LogFile { path: entry.path(), date, index, size });
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Date et numero d'un nom de fichier de journal (voir `file_path`)
+fn parse_file_name(name: &str) -> Option<(NaiveDate, u32)> {
+    let rest = name.strip_prefix(PREFIX)?.strip_prefix('.')?;
+    let rest = rest.strip_suffix(".gz").unwrap_or(rest);
+    let (date, index) = match rest.split_once('.') {
+        Some((date, index)) => (date, index.parse().ok()?),
+        None => (rest, 0),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some((date, index))
+}
+
+/// Socket local du demon syslog (rsyslog, ou journald qui l'ecoute aussi)
+#[cfg(unix)]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Destination des traces vers le journal systeme : un datagramme par evenement, avec la
+/// priorite correspondant au niveau de la trace (facilite "daemon")
+#[cfg(unix)]
+pub struct SyslogWriter {
+    socket: UnixDatagram,
+    tag: String,
+}
+
+#[cfg(unix)]
+impl SyslogWriter {
+    pub fn connect() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(SYSLOG_SOCKET)?;
+        Ok(Self {
+            socket,
+            tag: format!("{}[{}]", PREFIX, std::process::id()),
+        })
+    }
+
+    fn send(&self, priority: u8, message: &str) {
+        let datagram = format!("<{}>{}: {}", priority, self.tag, message);
+        if self.socket.send(datagram.as_bytes()).is_err() {
+            // Demon redemarre : le socket est a reconnecter ; une trace perdue sinon
+            let _ = self.socket.connect(SYSLOG_SOCKET);
+            let _ = self.socket.send(datagram.as_bytes());
+        }
+    }
+
+    fn make_line(&self, level: &Level) -> SyslogLine<'_> {
+        SyslogLine {
+            writer: self,
+            priority: syslog_priority(level),
+            buf: Vec::new(),
+        }
+    }
+}
+
+/// Facilite LOG_DAEMON (3) et gravite syslog du niveau tracing
+#[cfg(unix)]
+fn syslog_priority(level: &Level) -> u8 {
+    let severity = match *level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        _ => 7,
+    };
+    3 * 8 + severity
+}
+
+#[cfg(unix)]
+impl<'a> MakeWriter<'a> for SyslogWriter {
+    type Writer = SyslogLine<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.make_line(&Level::INFO)
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        self.make_line(meta.level())
+    }
+}
+
+/// Evenement formate en cours d'ecriture, envoye d'un bloc a sa liberation
+#[cfg(unix)]
+pub struct SyslogLine<'a> {
+    writer: &'a SyslogWriter,
+    priority: u8,
+    buf: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl Write for SyslogLine<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SyslogLine<'_> {
+    fn drop(&mut self) {
+        let message = String::from_utf8_lossy(&self.buf);
+        let message = message.trim_end();
+        if !message.is_empty() {
+            self.writer.send(self.priority, message);
+        }
+    }
+}
+
+/// Source des evenements dans l'Observateur d'evenements (journal Application)
+#[cfg(windows)]
+const EVENT_SOURCE: &str = "IronCloak";
+
+/// Destination des traces vers le journal d'evenements Windows, sous la source "IronCloak".
+/// Seuls les avertissements et les erreurs y sont destines (filtre pose dans main.rs).
+#[cfg(windows)]
+pub struct EventLogWriter {
+    handle: winapi::um::winnt::HANDLE,
+}
+
+// Le handle de source d'evenements peut etre utilise depuis n'importe quel thread
+#[cfg(windows)]
+unsafe impl Send for EventLogWriter {}
+#[cfg(windows)]
+unsafe impl Sync for EventLogWriter {}
+
+#[cfg(windows)]
+impl EventLogWriter {
+    pub fn register() -> io::Result<Self> {
+        let source = wide(EVENT_SOURCE);
+        let handle = unsafe {
+            winapi::um::winbase::RegisterEventSourceW(std::ptr::null(), source.as_ptr())
+        };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { handle })
+    }
+
+    fn report(&self, event_type: u16, message: &str) {
+        let message = wide(message);
+        let mut strings = [message.as_ptr()];
+        unsafe {
+            winapi::um::winbase::ReportEventW(
+                self.handle,
+                event_type,
+                0,
+                0,
+                std::ptr::null_mut(),
+                1,
+                0,
+                strings.as_mut_ptr(),
+                std::ptr::null_mut(),
+            );
+        }
+    }
+
+    fn make_line(&self, level: &Level) -> EventLogLine<'_> {
+        let event_type = if *level == Level::ERROR {
+            winapi::um::winnt::EVENTLOG_ERROR_TYPE
+        } else if *level == Level::WARN {
+            winapi::um::winnt::EVENTLOG_WARNING_TYPE
+        } else {
+            winapi::um::winnt::EVENTLOG_INFORMATION_TYPE
+        };
+        EventLogLine {
+            writer: self,
+            event_type,
+            buf: Vec::new(),
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for EventLogWriter {
+    fn drop(&mut self) {
+        unsafe {
+            winapi::um::winbase::DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+/// Chaine UTF-16 terminee par un zero pour les API Win32
+#[cfg(windows)]
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+#[cfg(windows)]
+impl<'a> MakeWriter<'a> for EventLogWriter {
+    type Writer = EventLogLine<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.make_line(&Level::INFO)
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        self.make_line(meta.level())
+    }
+}
+
+/// Evenement formate en cours d'ecriture, rapporte d'un bloc a sa liberation
+#[cfg(windows)]
+pub struct EventLogLine<'a> {
+    writer: &'a EventLogWriter,
+    event_type: u16,
+    buf: Vec<u8>,
+}
+
+#[cfg(windows)]
+impl Write for EventLogLine<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl Drop for EventLogLine<'_> {
+    fn drop(&mut self) {
+        let message = String::from_utf8_lossy(&self.buf);
+        let message = message.trim_end();
+        if !message.is_empty() {
+            self.writer.report(self.event_type, message);
+        }
+    }
+}
+
+/// Installe le gestionnaire de paniques : message, emplacement et pile d'appels dans les
+/// traces (une panique du thread tokio passait inapercue) ; sous Windows en release, sans
+/// console, une boite de dialogue previent aussi l'utilisateur
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+        let thread = std::thread::current().name().unwrap_or("?").to_string();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+
+        tracing::error!(
+            "{}\n{}",
+            crate::tl!("app.panic", thread = &thread, message = &message, location = &location),
+            backtrace
+        );
+        default_hook(info);
+
+        #[cfg(all(windows, not(debug_assertions)))]
+        show_panic_dialog(&crate::t!("app.panic_dialog", message = &message, location = &location));
+    }));
+}
+
+/// Boite de dialogue d'erreur native (Windows release, sans console)
+#[cfg(all(windows, not(debug_assertions)))]
+fn show_panic_dialog(text: &str) {
+    let text = wide(text);
+    let title = wide(EVENT_SOURCE);
+    unsafe {
+        winapi::um::winuser::MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            title.as_ptr(),
+            winapi::um::winuser::MB_OK | winapi::um::winuser::MB_ICONERROR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn config(log_dir: &Path, keep_days: Option<u64>, max_total_mb: Option<u64>) -> LoggingConfig {
+        LoggingConfig {
+            log_dir: log_dir.to_string_lossy().into_owned(),
+            keep_days,
+            max_total_mb,
+            ..Default::default()
+        }
+    }
+
+    fn create(log_dir: &Path, date: NaiveDate, index: u32, size: usize) -> PathBuf {
+        let path = file_path(log_dir, date, index);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_file_name_accepts_index_and_gz() {
+        assert_eq!(parse_file_name("ironcloak.2024-03-05"), Some((date("2024-03-05"), 0)));
+        assert_eq!(parse_file_name("ironcloak.2024-03-05.2"), Some((date("2024-03-05"), 2)));
+        assert_eq!(parse_file_name("ironcloak.2024-03-05.gz"), Some((date("2024-03-05"), 0)));
+        assert_eq!(parse_file_name("ironcloak.2024-03-05.7.gz"), Some((date("2024-03-05"), 7)));
+    }
+
+    #[test]
+    fn parse_file_name_rejects_other_names() {
+        assert_eq!(parse_file_name("other.2024-03-05"), None);
+        assert_eq!(parse_file_name("ironcloak2024-03-05"), None);
+        assert_eq!(parse_file_name("ironcloak.2024-13-05"), None);
+        assert_eq!(parse_file_name("ironcloak.2024-03-05.x"), None);
+        assert_eq!(parse_file_name("ironcloak.2024-03-05.-1"), None);
+        assert_eq!(parse_file_name("ironcloak."), None);
+    }
+
+    #[test]
+    fn file_path_round_trips_through_parse() {
+        let path = file_path(Path::new("/logs"), date("2024-03-05"), 3);
+        assert_eq!(path, Path::new("/logs/2024/03/ironcloak.2024-03-05.3"));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(parse_file_name(name), Some((date("2024-03-05"), 3)));
+    }
+
+    #[test]
+    fn prune_removes_expired_files_and_empty_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let today = Local::now().date_naive();
+        let old = create(dir.path(), date("2001-01-01"), 0, 10);
+        let recent = create(dir.path(), today, 0, 10);
+        let current = create(dir.path(), today, 1, 10);
+
+        assert_eq!(prune(&config(dir.path(), Some(30), None)).unwrap(), (1, 10));
+        assert!(!old.exists());
+        assert!(!dir.path().join("2001").exists());
+        assert!(recent.exists());
+        assert!(current.exists());
+    }
+
+    #[test]
+    fn prune_enforces_total_size_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mb = 1024 * 1024;
+        let first = create(dir.path(), date("2024-03-01"), 0, mb / 2);
+        let second = create(dir.path(), date("2024-03-02"), 0, mb / 2);
+        let third = create(dir.path(), date("2024-03-02"), 1, mb / 2);
+        let current = create(dir.path(), date("2024-03-03"), 0, 2 * mb);
+
+        assert_eq!(prune(&config(dir.path(), None, Some(1))).unwrap(), (1, (mb / 2) as u64));
+        assert!(!first.exists());
+        assert!(second.exists());
+        assert!(third.exists());
+        // Le fichier en cours n'est jamais compte ni supprime
+        assert!(current.exists());
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let only = create(dir.path(), date("2001-01-01"), 0, 10);
+
+        assert_eq!(prune(&config(dir.path(), Some(1), Some(1))).unwrap(), (0, 0));
+        assert!(only.exists());
+    }
+
+    #[test]
+    fn prune_ignores_missing_dir_and_foreign_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        assert_eq!(prune(&config(&missing, Some(1), None)).unwrap(), (0, 0));
+
+        let foreign = dir.path().join("2001").join("01").join("notes.txt");
+        fs::create_dir_all(foreign.parent().unwrap()).unwrap();
+        fs::write(&foreign, b"x").unwrap();
+        create(dir.path(), Local::now().date_naive(), 0, 10);
+        assert_eq!(prune(&config(dir.path(), Some(1), None)).unwrap(), (0, 0));
+        assert!(foreign.exists());
+    }
+
+    #[test]
+    fn prune_without_limits_keeps_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = create(dir.path(), date("2001-01-01"), 0, 10);
+        create(dir.path(), date("2001-01-02"), 0, 10);
+
+        assert_eq!(prune(&config(dir.path(), Some(0), Some(0))).unwrap(), (0, 0));
+        assert!(old.exists());
+    }
+}
@@ -1,25 +1,60 @@
 // Point d'entree principal d'IronCloak.
 // Le thread principal gere l'interface graphique (systray Windows ou fenetre egui Linux).
 // Un thread secondaire execute le runtime tokio pour le bootstrap Tor et le serveur SOCKS5.
+// A la fermeture, le client Tor est libere et le runtime arrete avant la fin du processus,
+// pour qu'arti termine l'ecriture de son etat (gardes, consensus).
 
 // En mode release sur Windows, masquer la console
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod audit;
+mod autostart;
+mod bench;
+mod bridges;
+mod buffer_pool;
+mod browser;
 mod config;
+mod control;
+mod dbus;
 mod gui;
+mod health;
 mod i18n;
+mod killswitch;
+mod logging;
+mod moat;
+mod network;
+mod onion;
+mod otel;
+mod pac;
+mod probe;
+mod quota;
+mod proxy_protocol;
+mod resources;
+mod sandbox;
+mod security;
 mod socks;
 mod tor;
+mod update;
+mod vault;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use chrono::Local;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use futures::future::Either;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 use config::IronCloakConfig;
-use gui::state::AppState;
+use gui::state::{AppState, Backend, FailedStage, ProxyStatus};
+
+/// Delai accorde au runtime tokio pour s'arreter (taches bloquantes d'arti : ecriture de
+/// l'etat et du cache) ; au-dela, le processus se termine quand meme
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Variable d'environnement fournissant la phrase secrete de l'etat chiffre sans la demander
+/// (lancement automatique, service)
+const PASSPHRASE_ENV: &str = "IRONCLOAK_STATE_PASSPHRASE";
 
 #[derive(Parser, Debug)]
 #[command(name = "ironcloak", about = "SOCKS5 proxy routing traffic through Tor")]
@@ -27,69 +62,223 @@ struct Cli {
     /// Chemin vers le fichier de configuration
     #[arg(short, long, default_value = "ironcloak.toml")]
     config: PathBuf,
+    /// Repertoire de travail (chemins relatifs de la configuration), utilise au lancement automatique
+    #[arg(long)]
+    workdir: Option<PathBuf>,
+    /// Importe des ponts (lignes torrc "Bridge" ou chaines Tor Browser) depuis un fichier,
+    /// ou "-" pour l'entree standard, dans la configuration puis quitte
+    #[arg(long, value_name = "FILE")]
+    import_bridges: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Banc de charge : connexions SOCKS simultanees a travers l'instance en cours d'execution,
+    /// percentiles des latences et du debit
+    Bench(bench::BenchArgs),
+    /// Vide le cache de l'annuaire Tor (consensus, descripteurs) ; differe au prochain
+    /// demarrage si IronCloak est en cours d'execution
+    ClearCache,
 }
 
 fn main() {
-    // Parser les arguments CLI
-    let cli = Cli::parse();
+    // Initialiser i18n avec la langue du systeme (avant le chargement de la config) : les
+    // erreurs d'arguments, de lecture de la configuration ou des journaux sont traduites
+    let system_language = i18n::system_language();
+    i18n::init(system_language);
+    i18n::init_log(system_language);
 
-    // Initialiser i18n avec l'anglais par defaut (avant le chargement de la config)
-    i18n::init("en");
+    // Parser les arguments CLI ; l'aide et la version restent affichees par clap
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            eprintln!("{}", t!("app.invalid_arguments", error = e.to_string().trim_end()));
+            std::process::exit(e.exit_code());
+        }
+    };
+    if let Some(ref dir) = cli.workdir {
+        if let Err(e) = std::env::set_current_dir(dir) {
+            eprintln!("{}", t!("app.workdir_failed", path = dir.display(), error = e));
+            std::process::exit(1);
+        }
+    }
 
     // Charger la configuration
     let config = match IronCloakConfig::load(&cli.config) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("Configuration error: {}", e);
+            eprintln!("{}", t!("app.config_error", error = format!("{:#}", e)));
             std::process::exit(1);
         }
     };
 
-    // Reinitialiser i18n avec la langue configuree
-    let language = config.logging.language.as_deref().unwrap_or("en");
+    // Paquets de langue deposes dans langs/ a cote de la configuration, puis
+    // reinitialiser i18n avec les langues configurees (interface et journaux)
+    let packs_dir = cli.config.parent().unwrap_or(Path::new("")).join("langs");
+    let packs = i18n::load_packs(&packs_dir);
+    let language = config.gui_language();
     i18n::init(language);
+    i18n::init_log(config.log_language());
 
-    // Initialiser le logging (fichier uniquement sur Windows release, stdout + fichier sinon)
-    let filter_str = &config.logging.level;
-
-    // Calculer le repertoire mensuel de logs : {log_dir}/AAAA/MM/
-    let now = Local::now();
-    let log_dir = PathBuf::from(&config.logging.log_dir)
-        .join(now.format("%Y").to_string())
-        .join(now.format("%m").to_string());
-    if let Err(e) = std::fs::create_dir_all(&log_dir) {
-        eprintln!("Failed to create log directory: {}", e);
-        std::process::exit(1);
+    // Import de ponts en ligne de commande : configuration mise a jour, pas de lancement
+    if let Some(ref source) = cli.import_bridges {
+        match import_bridges(&cli.config, config, source) {
+            Ok(summary) => {
+                println!("{}", summary);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Banc de charge contre l'instance deja lancee : rapport affiche, pas de lancement
+    if let Some(Command::Bench(ref args)) = cli.command {
+        match bench::run(&config, args) {
+            Ok(report) => {
+                println!("{}", report);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Vidage du cache en ligne de commande : pas de lancement
+    if let Some(Command::ClearCache) = cli.command {
+        match clear_cache(&config) {
+            Ok(summary) => {
+                println!("{}", summary);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
     }
 
-    // Appender de fichier avec rotation quotidienne dans le repertoire mensuel
-    let file_appender = tracing_appender::rolling::daily(&log_dir, "ironcloak");
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    // Initialiser le logging (fichier uniquement sur Windows release, stdout + fichier sinon)
+    let filter_str = config.logging.filter_directives();
 
-    let file_layer = fmt::layer()
-        .with_ansi(false)
-        .with_target(false)
-        .with_writer(non_blocking);
+    // Fichiers de journal dans le repertoire mensuel {log_dir}/AAAA/MM/, rotation quotidienne
+    // et par taille (logging.files = false : aucun fichier)
+    let (file_layer, _guard) = if config.logging.files {
+        let file_appender = match logging::RollingWriter::new(&config.logging) {
+            Ok(writer) => writer,
+            Err(e) => {
+                eprintln!("{}", t!("app.log_dir_failed", path = config.logging.log_dir, error = e));
+                std::process::exit(1);
+            }
+        };
+        let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+        let layer = fmt::layer()
+            .with_ansi(false)
+            .with_target(false)
+            .with_writer(non_blocking);
+        (Some(layer), Some(guard))
+    } else {
+        (None, None)
+    };
 
-    let filter = EnvFilter::try_new(filter_str)
-        .unwrap_or_else(|_| EnvFilter::new("info"));
+    // Filtre rechargeable : niveau modifiable depuis la fenetre ou le socket de controle
+    let filter = logging::reloadable_filter(EnvFilter::try_new(&filter_str).unwrap_or_else(|e| {
+        eprintln!("{}", t!("app.log_filter_invalid", filter = &filter_str, error = e));
+        EnvFilter::new("info")
+    }));
 
-    // Sur Linux (ou en mode debug), ajouter aussi la sortie stdout
+    // Export OpenTelemetry des spans et compteurs (fonctionnalite "otel", section [otel]) ;
+    // le garde envoie les donnees en attente a la fin de main
+    #[cfg(feature = "otel")]
+    let (otel_layer, _otel_guard) = if config.otel.enabled {
+        match otel::init(&config.otel) {
+            Ok((guard, tracer)) => (
+                Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+                Some(guard),
+            ),
+            Err(e) => {
+                eprintln!("{}", t!("app.otel_failed", error = format!("{:#}", e)));
+                (None, None)
+            }
+        }
+    } else {
+        (None, None)
+    };
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<tracing_subscriber::layer::Identity> = None;
+
+    // Sur Linux (ou en mode debug), ajouter aussi la sortie stdout, ou le journal systeme
+    // (logging.syslog) : la sortie stdout est alors omise, systemd l'y enverrait en double
     #[cfg(not(windows))]
     {
-        let stdout_layer = fmt::layer()
-            .with_ansi(false)
-            .with_target(false);
+        let syslog = if config.logging.syslog {
+            match logging::SyslogWriter::connect() {
+                Ok(writer) => Some(writer),
+                Err(e) => {
+                    eprintln!("{}", t!("app.syslog_failed", error = e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let stdout_layer = syslog.is_none().then(|| {
+            fmt::layer()
+                .with_ansi(false)
+                .with_target(false)
+        });
+        // Horodatage et niveau ajoutes par le journal systeme (priorite du message)
+        let syslog_layer = syslog.map(|writer| {
+            fmt::layer()
+                .with_ansi(false)
+                .with_target(false)
+                .with_level(false)
+                .without_time()
+                .with_writer(writer)
+        });
 
         tracing_subscriber::registry()
             .with(filter)
             .with(stdout_layer)
+            .with(syslog_layer)
             .with(file_layer)
+            .with(otel_layer)
             .init();
     }
 
+    // Sous Windows, avertissements et erreurs aussi dans le journal d'evenements
+    // (logging.event_log), source "IronCloak"
     #[cfg(windows)]
     {
+        use tracing_subscriber::{filter::LevelFilter, Layer};
+
+        let event_log_layer = if config.logging.event_log {
+            match logging::EventLogWriter::register() {
+                Ok(writer) => Some(
+                    fmt::layer()
+                        .with_ansi(false)
+                        .with_target(false)
+                        .with_level(false)
+                        .without_time()
+                        .with_writer(writer)
+                        .with_filter(LevelFilter::WARN),
+                ),
+                Err(e) => {
+                    eprintln!("{}", t!("app.event_log_failed", error = e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         #[cfg(debug_assertions)]
         {
             let stdout_layer = fmt::layer()
@@ -99,7 +288,9 @@ fn main() {
             tracing_subscriber::registry()
                 .with(filter)
                 .with(stdout_layer)
+                .with(event_log_layer)
                 .with(file_layer)
+                .with(otel_layer)
                 .init();
         }
 
@@ -107,15 +298,44 @@ fn main() {
         {
             tracing_subscriber::registry()
                 .with(filter)
+                .with(event_log_layer)
                 .with(file_layer)
+                .with(otel_layer)
                 .init();
         }
     }
 
-    tracing::info!("{}", t!("app.starting"));
+    // Paniques journalisees (et signalees a l'utilisateur sous Windows en release)
+    logging::install_panic_hook();
+
+    tracing::info!("{}", tl!("app.starting"));
     let bind_addr = format!("{}:{}", config.proxy.listen_addr, config.proxy.listen_port);
-    tracing::info!("{}", t!("app.proxy_will_listen", &bind_addr));
-    tracing::info!("{}", t!("app.config_loaded", language));
+    tracing::info!("{}", tl!("app.proxy_will_listen", addr = &bind_addr));
+    tracing::info!("{}", tl!("app.config_loaded", language = config.log_language()));
+    for (path, result) in &packs {
+        match result {
+            Ok(count) => tracing::info!("{}", tl!("app.language_pack_loaded", path = path.display(), count = count)),
+            Err(e) => tracing::warn!("{}", tl!("app.language_pack_invalid", path = path.display(), error = e)),
+        }
+    }
+
+    // Durcissement memoire : avant la creation de tout secret
+    security::init(&config.security);
+
+    // Proxy ouvert sur le reseau local sans protection : refuse, sauf confirmation explicite
+    if let Err(e) = config.check_exposure() {
+        tracing::error!("{:#}", e);
+        std::process::exit(1);
+    }
+
+    // Kill switch : installe avant toute connexion, retire apres la fermeture de l'interface
+    let kill_switch = match killswitch::enable(&config) {
+        Ok(ks) => ks,
+        Err(e) => {
+            tracing::error!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Creer l'etat partage entre GUI et tokio
     let state = Arc::new(AppState::new(
@@ -123,55 +343,373 @@ fn main() {
         cli.config.clone(),
         language.to_string(),
     ));
+    state.set_pause_closes_connections(config.proxy.pause_closes_connections);
+    state.set_tray_click_action(config.gui.tray_click_action);
+    state.set_history_size(config.history.size);
     let state_for_runtime = Arc::clone(&state);
 
     // Lancer le runtime tokio sur un thread secondaire
     let config_clone = config.clone();
-    std::thread::spawn(move || {
+    let config_path = cli.config.clone();
+    let backend_thread = std::thread::spawn(move || {
+        // Socket et cookie de controle crees avant le bac a sable, hors de ses regles
+        let control = if config_clone.control.enabled {
+            control::bind(&config_clone)
+                .map_err(|e| tracing::error!("{}", tl!("control.server_error", error = e)))
+                .ok()
+        } else {
+            None
+        };
+        // Bac a sable Linux avant le runtime : ses threads en heritent, pas l'interface
+        sandbox::apply(&config_clone, &config_path);
         let rt = tokio::runtime::Runtime::new().expect("Echec de creation du runtime tokio");
-        rt.block_on(async move {
-            run_backend(config_clone, state_for_runtime).await;
-        });
+        let mut vault = None;
+        rt.block_on(run_backend(config_clone, state_for_runtime, control, &mut vault));
+        // Les taches restantes (services, ports supplementaires) liberent leurs references au
+        // client Tor ; les ecritures bloquantes en cours ont le temps de se terminer
+        rt.shutdown_timeout(SHUTDOWN_TIMEOUT);
+        // Etat chiffre : rechiffrer une fois arti arrete
+        if let Some(vault) = vault {
+            if let Err(e) = vault.seal() {
+                tracing::error!("{}", tl!("vault.seal_failed", error = format!("{:#}", e)));
+            }
+        }
     });
 
     // Thread principal : lancer l'interface graphique (bloquant)
-    gui::run_gui(state);
+    gui::run_gui(Arc::clone(&state));
+
+    // Arret propre : arreter le backend (fenetre fermee sans demande d'arret comprise), puis
+    // attendre la fin de son thread avant de quitter
+    state.request_quit();
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT * 2;
+    while !backend_thread.is_finished() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    if backend_thread.is_finished() {
+        let _ = backend_thread.join();
+        tracing::info!("{}", tl!("app.shutdown_complete"));
+    } else {
+        tracing::warn!("{}", tl!("app.shutdown_timeout", secs = (SHUTDOWN_TIMEOUT * 2).as_secs()));
+    }
+
+    // Retirer les regles du kill switch
+    drop(kill_switch);
 }
 
-/// Logique backend : bootstrap Tor puis lance le serveur SOCKS5
-async fn run_backend(config: IronCloakConfig, state: Arc<AppState>) {
-    // Bootstrap Tor
-    let tor_client = match tor::bootstrap_tor(&config).await {
-        Ok(client) => {
-            // Marquer comme connecte pour l'interface graphique
-            state.set_connected(true);
-            client
-        }
-        Err(e) => {
-            tracing::error!("{}", t!("app.runtime_error", e));
-            return;
+/// Vide le cache de l'annuaire Tor. Instance en cours d'execution (port SOCKS occupe) : arti
+/// utilise le cache, le vidage est programme pour le prochain demarrage.
+fn clear_cache(config: &IronCloakConfig) -> anyhow::Result<String> {
+    let data_dir = &config.tor.data_dir;
+    let proxy = (bench::local_host(&config.proxy.listen_addr).trim_matches(['[', ']']), config.proxy.listen_port);
+    let running = std::net::ToSocketAddrs::to_socket_addrs(&proxy)
+        .into_iter()
+        .flatten()
+        .any(|addr| std::net::TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok());
+    if running {
+        tor::request_cache_clear(data_dir)?;
+        return Ok(t!("tor.cache_clear_scheduled"));
+    }
+    let bytes = tor::clear_cache(data_dir)?;
+    Ok(t!("tor.cache_cleared", bytes = bytes))
+}
+
+/// Importe les ponts d'un fichier (ou de l'entree standard avec "-") dans la configuration
+/// et retourne le resume a afficher
+fn import_bridges(config_path: &Path, mut config: IronCloakConfig, source: &Path) -> anyhow::Result<String> {
+    use anyhow::Context;
+    use std::io::Read;
+
+    let mut text = String::new();
+    if source == Path::new("-") {
+        std::io::stdin().read_to_string(&mut text)?;
+    } else {
+        text = std::fs::read_to_string(source).with_context(|| source.display().to_string())?;
+    }
+    let imported = bridges::parse(&text)?;
+    let missing = imported.missing_transports(&config);
+    let added = imported.merge_into(&mut config);
+    config.save(config_path)?;
+
+    let mut summary = t!("bridges.imported", count = added, total = config.tor.bridges.lines.len());
+    if !missing.is_empty() {
+        summary.push('\n');
+        summary.push_str(&t!("bridges.transport_missing", names = missing.join(", ")));
+    }
+    Ok(summary)
+}
+
+/// Dechiffre l'etat d'arti (tor.encrypt_state) avec la phrase secrete de l'environnement ou,
+/// a defaut, saisie dans la fenetre (redemandee tant qu'elle est incorrecte)
+async fn unlock_state(config: &IronCloakConfig, state: &AppState) -> anyhow::Result<vault::StateVault> {
+    let data_dir = Path::new(&config.tor.data_dir);
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return vault::open(data_dir, &passphrase);
+    }
+    let first_time = !vault::exists(data_dir);
+    let mut error = None;
+    loop {
+        let passphrase = state.ask_passphrase(first_time, error.take()).await;
+        match vault::open(data_dir, &passphrase) {
+            Ok(vault) => return Ok(vault),
+            Err(e) => error = Some(e.to_string()),
         }
-    };
+    }
+}
+
+/// Logique backend : bootstrap Tor puis lance le serveur SOCKS5.
+/// Avec l'etat chiffre, `vault` recoit l'etat dechiffre a rechiffrer apres l'arret du runtime.
+async fn run_backend(
+    config: IronCloakConfig,
+    state: Arc<AppState>,
+    control: Option<control::ControlListener>,
+    vault: &mut Option<vault::StateVault>,
+) {
+    // Socket de controle local (disponible des le debut de l'amorcage)
+    if let Some(control) = control {
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            if let Err(e) = control::run_control_server(control, state).await {
+                tracing::error!("{}", tl!("control.server_error", error = e));
+            }
+        });
+    }
+
+    // Service D-Bus pour les extensions GNOME et les scripts (Linux)
+    if config.control.dbus {
+        tokio::spawn(dbus::run_dbus_service(Arc::clone(&state)));
+    }
+
+    // Nettoyage des anciens journaux
+    if config.logging.keep_days.is_some() || config.logging.max_total_mb.is_some() {
+        tokio::spawn(logging::run_pruner(config.logging.clone()));
+    }
+
+    // Journal d'audit des connexions (audit.mode)
+    if let Err(e) = audit::init(&config.audit, Path::new(&config.tor.data_dir)) {
+        tracing::error!(
+            "{}",
+            tl!("audit.open_failed", path = config.audit.path().display(), error = format!("{:#}", e))
+        );
+    }
+
+    // Quota de donnees : compteurs journaliers/mensuels et pause au depassement
+    if config.quota.is_enabled() {
+        tokio::spawn(quota::run_quota_monitor(
+            config.quota.clone(),
+            PathBuf::from(&config.tor.data_dir),
+            Arc::clone(&state),
+        ));
+    }
+
+    // Detection de la perte du reseau : sommeil du client Tor et reprise de l'amorcage
+    if config.network.offline_detection {
+        tokio::spawn(network::run_network_monitor(config.network.clone(), Arc::clone(&state)));
+    }
+
+    // Fichier PAC pour les navigateurs
+    if config.pac.enabled {
+        let config = config.clone();
+        tokio::spawn(async move {
+            if let Err(e) = pac::run_pac_server(&config).await {
+                tracing::error!("{}", tl!("pac.server_error", error = e));
+            }
+        });
+    }
 
-    // Lancer le serveur SOCKS5 avec surveillance de l'arret
-    tokio::select! {
-        result = socks::run_socks_server(&config, tor_client) => {
-            if let Err(e) = result {
-                tracing::error!("{}", t!("socks.server_error", e));
+    // Etat chiffre au repos : dechiffrement avant tout acces d'arti. En cas d'echec, l'erreur
+    // est affichee par l'interface et l'etape relancee par "Reessayer"
+    if config.tor.encrypt_state {
+        loop {
+            let unlocked = tokio::select! {
+                result = unlock_state(&config, &state) => result,
+                _ = state.wait_for_quit() => {
+                    tracing::info!("{}", tl!("app.shutdown"));
+                    return;
+                }
+            };
+            match unlocked {
+                Ok(unlocked) => {
+                    *vault = Some(unlocked);
+                    break;
+                }
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    tracing::error!("{}", tl!("app.runtime_error", error = &error));
+                    state.set_failed(FailedStage::Unlock, error);
+                    if !state.wait_for_retry().await {
+                        tracing::info!("{}", tl!("app.shutdown"));
+                        return;
+                    }
+                }
             }
         }
-        _ = wait_for_quit(Arc::clone(&state)) => {
-            tracing::info!("{}", t!("app.shutdown"));
+    }
+
+    // File d'attente de l'amorcage (proxy.bootstrap_queue) : le port principal est ouvert des
+    // maintenant, les requetes SOCKS attendent le client Tor au lieu d'etre refusees. Un echec
+    // de l'ecoute est traite avec l'ecoute normale, apres l'amorcage
+    let (tor_sender, tor_source) = tokio::sync::watch::channel(None);
+    let bind_addr = format!("{}:{}", config.proxy.listen_addr, config.proxy.listen_port);
+    let mut early_server = (config.proxy.bootstrap_queue.size > 0).then(|| {
+        let config = config.clone();
+        let bind_addr = bind_addr.clone();
+        let tor_source = tor_source.clone();
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            socks::run_socks_server(&config, bind_addr, config.proxy.proxy_protocol, tor_source, state).await
+        })
+    });
+
+    // Bootstrap Tor (interrompu par une demande d'arret ou la perte du reseau, relance par
+    // "Reessayer" ou au retour du reseau)
+    let tor_client = loop {
+        let bootstrap = tokio::select! {
+            result = tor::bootstrap_tor(&config, Arc::clone(&state)) => result,
+            _ = state.wait_for_offline() => Err(anyhow::anyhow!("{}", t!("network.bootstrap_suspended"))),
+            _ = state.wait_for_quit() => {
+                tracing::info!("{}", tl!("app.shutdown"));
+                return;
+            }
+        };
+        match bootstrap {
+            Ok(client) => {
+                // Marquer comme connecte et rendre le client accessible a l'interface graphique
+                state.set_backend(Backend {
+                    runtime: tokio::runtime::Handle::current(),
+                    tor_client: Arc::clone(&client),
+                });
+                state.set_status(ProxyStatus::Connected);
+                state.set_captive_portal(None);
+                tor_sender.send_replace(Some(Arc::clone(&client)));
+                break client;
+            }
+            Err(e) => {
+                let error = format!("{:#}", e);
+                tracing::error!("{}", tl!("app.runtime_error", error = &error));
+                // Wi-Fi d'hotel, de gare... : proposer la page de connexion du portail
+                if config.network.captive_portal_check && !state.is_offline() {
+                    tokio::spawn(network::check_captive_portal(Arc::clone(&state)));
+                }
+                state.set_failed(FailedStage::Bootstrap, error);
+                if !state.wait_for_retry().await {
+                    tracing::info!("{}", tl!("app.shutdown"));
+                    return;
+                }
+            }
         }
+    };
+
+    // Cles d'autorisation client des services onion restreints
+    tor::install_onion_auth_keys(&config, &tor_client);
+
+    // Verification periodique des mises a jour (a travers Tor)
+    if config.updates.enabled {
+        let config = config.clone();
+        let tor = Arc::clone(&tor_client);
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            update::run_update_checker(&config, tor, state).await;
+        });
     }
-}
 
-/// Attend que l'etat passe en mode "quit" (demande depuis l'interface graphique)
-async fn wait_for_quit(state: Arc<AppState>) {
+    // Indicateur de sante : latence des circuits mesuree periodiquement
+    if config.health.enabled {
+        let config = config.clone();
+        let tor = Arc::clone(&tor_client);
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            health::run_health_probe(&config.health, tor, state).await;
+        });
+    }
+
+    // Reconstruction des circuits apres une nouvelle identite (requetes retenues ou refusees
+    // entre-temps, proxy.rebuild)
+    if config.proxy.rebuild.policy != config::RebuildPolicy::Off {
+        tokio::spawn(health::run_rebuild_monitor(Arc::clone(&tor_client), Arc::clone(&state)));
+    }
+
+    // Lancer les services onion heberges (chacun dans sa propre tache)
+    for service in config.onion_services.clone() {
+        let tor = Arc::clone(&tor_client);
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let nickname = service.nickname.clone();
+            if let Err(e) = onion::run_onion_service(service, tor, state).await {
+                tracing::error!("{}", tl!("onion.service_error", nickname = nickname, error = e));
+            }
+        });
+    }
+
+    // Ports d'ecoute supplementaires, chacun avec son client Tor : isole du client principal
+    // (circuits jamais partages) ou distinct avec son propre repertoire de donnees
+    for listener in config.listeners.clone() {
+        let config = config.clone();
+        let main_client = Arc::clone(&tor_client);
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let addr = listener.listen_addr.as_deref().unwrap_or(&config.proxy.listen_addr);
+            let bind_addr = format!("{}:{}", addr, listener.port);
+            let client = match &listener.data_dir {
+                Some(data_dir) => {
+                    tracing::info!("{}", tl!("socks.listener_separate", addr = &bind_addr, dir = data_dir));
+                    match tor::bootstrap_separate(&config, data_dir).await {
+                        Ok(client) => client,
+                        Err(e) => {
+                            tracing::error!("{}", tl!("socks.listener_failed", addr = &bind_addr, error = format!("{:#}", e)));
+                            return;
+                        }
+                    }
+                }
+                None => {
+                    tracing::info!("{}", tl!("socks.listener_isolated", addr = &bind_addr));
+                    Arc::new(main_client.isolated_client())
+                }
+            };
+            let tor_source = socks::ready(client);
+            if let Err(e) = socks::run_socks_server(&config, bind_addr.clone(), listener.proxy_protocol, tor_source, state).await {
+                tracing::error!("{}", tl!("socks.listener_failed", addr = &bind_addr, error = format!("{:#}", e)));
+            }
+        });
+    }
+
+    // Debit de l'ensemble des ports (infobulle du systray)
+    tokio::spawn(socks::measure_throughput(Arc::clone(&state)));
+
+    // Lancer le serveur SOCKS5 (ou suivre celui ouvert pendant l'amorcage) avec surveillance de
+    // l'arret ; un echec (port occupe...) est affiche par l'interface et l'ecoute relancee par
+    // "Reessayer"
     loop {
-        if state.should_quit() {
-            break;
+        let server = match early_server.take() {
+            Some(task) => Either::Left(async move { task.await.map_err(anyhow::Error::from).and_then(|result| result) }),
+            None => Either::Right(socks::run_socks_server(
+                &config,
+                bind_addr.clone(),
+                config.proxy.proxy_protocol,
+                tor_source.clone(),
+                Arc::clone(&state),
+            )),
+        };
+        tokio::select! {
+            result = server => {
+                let Err(e) = result else {
+                    break;
+                };
+                let error = format!("{:#}", e);
+                tracing::error!("{}", tl!("socks.server_error", error = &error));
+                state.set_failed(FailedStage::Listen, error);
+                if !state.wait_for_retry().await {
+                    tracing::info!("{}", tl!("app.shutdown"));
+                    break;
+                }
+            }
+            _ = state.wait_for_quit() => {
+                tracing::info!("{}", tl!("app.shutdown"));
+                break;
+            }
         }
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
+
+    // Liberer la reference de l'interface graphique au client Tor
+    state.clear_backend();
 }
@@ -0,0 +1,157 @@
+// Recuperation de ponts aupres de BridgeDB (API "moat" de bridges.torproject.org).
+// La requete part hors Tor, puisqu'un utilisateur censure ne peut justement pas s'y connecter.
+// Elle n'est envoyee que par un domaine frontal configure (moat.front, domain fronting) : le
+// reseau ne voit que ce domaine (DNS et SNI), l'hote de moat.url n'apparait que dans l'en-tete
+// Host, chiffre. Aucun domaine frontal n'est fourni par defaut (ils changent au gre des CDN) :
+// sans lui, la requete est refusee plutot qu'envoyee en clair a bridges.torproject.org.
+// Deux etapes : "fetch" retourne un captcha (image JPEG), "check" envoie sa solution et
+// retourne les ponts. Code bloquant : utilisable avant le demarrage du client Tor.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::config::MoatConfig;
+
+/// Type de contenu de l'API (JSON:API)
+const CONTENT_TYPE: &str = "application/vnd.api+json";
+
+/// Version du protocole moat
+const MOAT_VERSION: &str = "0.1.0";
+
+/// Delai de connexion, de lecture et d'ecriture
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Taille maximale d'une reponse (l'image du captcha fait quelques dizaines de Kio)
+const MAX_RESPONSE: u64 = 1024 * 1024;
+
+/// Captcha a resoudre pour obtenir des ponts
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    /// Transport des ponts demandes (obfs4...)
+    pub transport: String,
+    /// Image du captcha (JPEG)
+    pub image: Vec<u8>,
+    /// Jeton opaque a renvoyer avec la solution
+    pub challenge: String,
+}
+
+/// Demande un captcha pour le transport configure (moat.transport)
+pub fn fetch_challenge(config: &MoatConfig) -> Result<Challenge> {
+    let response = post(
+        config,
+        "fetch",
+        json!({
+            "data": [{
+                "version": MOAT_VERSION,
+                "type": "client-transports",
+                "supported": [config.transport],
+            }]
+        }),
+    )?;
+    let data = &response["data"][0];
+    let field = |name: &str| {
+        data[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("moat.bad_response")))
+    };
+    let image = data_encoding::BASE64
+        .decode(field("image")?.as_bytes())
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("moat.bad_response")))?;
+    Ok(Challenge {
+        transport: field("transport")?,
+        image,
+        challenge: field("challenge")?,
+    })
+}
+
+/// Envoie la solution du captcha ; retourne les lignes de pont attribuees
+pub fn check_solution(config: &MoatConfig, challenge: &Challenge, solution: &str) -> Result<Vec<String>> {
+    let response = post(
+        config,
+        "check",
+        json!({
+            "data": [{
+                "id": "2",
+                "version": MOAT_VERSION,
+                "type": "moat-solution",
+                "transport": challenge.transport,
+                "challenge": challenge.challenge,
+                "solution": solution.trim(),
+                "qrcode": "false",
+            }]
+        }),
+    )?;
+    let bridges: Vec<String> = response["data"][0]["bridges"]
+        .as_array()
+        .map(|list| list.iter().filter_map(|b| b.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    if bridges.is_empty() {
+        anyhow::bail!("{}", crate::t!("moat.bad_response"));
+    }
+    tracing::info!("{}", crate::tl!("moat.received", count = bridges.len()));
+    Ok(bridges)
+}
+
+/// Requete POST vers <moat.url>/<endpoint>, par le domaine frontal
+fn post(config: &MoatConfig, endpoint: &str, body: Value) -> Result<Value> {
+    let url = config.url.trim_end_matches('/');
+    let without_scheme = url
+        .strip_prefix("https://")
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("moat.bad_url", url = url)))?;
+    let (host, base_path) = without_scheme.split_once('/').unwrap_or((without_scheme, ""));
+    // Connexion directe : DNS et SNI reveleraient la demande de ponts, et bridges.torproject.org
+    // est justement bloque la ou les ponts sont necessaires
+    let front = config
+        .front
+        .as_deref()
+        .filter(|f| !f.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("moat.front_required")))?;
+    tracing::debug!("{}", crate::tl!("moat.request", endpoint = endpoint, front = front));
+
+    let addr = (front, 443)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("probe.connect_failed", host = front)))?;
+    let tcp = TcpStream::connect_timeout(&addr, TIMEOUT).with_context(|| crate::t!("probe.connect_failed", host = front))?;
+    tcp.set_read_timeout(Some(TIMEOUT))?;
+    tcp.set_write_timeout(Some(TIMEOUT))?;
+    // SNI du domaine frontal ; l'hote reel voyage dans l'en-tete Host
+    let mut tls = native_tls::TlsConnector::new()?
+        .connect(front, tcp)
+        .with_context(|| crate::t!("probe.tls_failed", host = front))?;
+
+    let body = body.to_string();
+    let request = format!(
+        "POST /{}{}{} HTTP/1.0\r\nHost: {}\r\nUser-Agent: IronCloak/{}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        base_path,
+        if base_path.is_empty() { "" } else { "/" },
+        endpoint,
+        host,
+        env!("CARGO_PKG_VERSION"),
+        CONTENT_TYPE,
+        body.len(),
+        body
+    );
+    tls.write_all(request.as_bytes())?;
+    tls.flush()?;
+
+    let mut raw = Vec::new();
+    (&mut tls).take(MAX_RESPONSE).read_to_end(&mut raw)?;
+    let response = crate::probe::parse_response(&raw, host)?;
+    let parsed: Value = serde_json::from_slice(&response.body).with_context(|| crate::t!("moat.bad_response"))?;
+
+    // Erreurs de l'API (captcha faux ou expire, transport inconnu...) : detail du serveur
+    if let Some(error) = parsed["errors"].get(0) {
+        let detail = error["detail"].as_str().unwrap_or_default();
+        anyhow::bail!("{}", crate::t!("moat.server_error", code = &error["code"], detail = detail));
+    }
+    if response.status != 200 {
+        anyhow::bail!("{}", crate::t!("probe.http_status", host = host, status = response.status));
+    }
+    Ok(parsed)
+}
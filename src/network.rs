@@ -0,0 +1,130 @@
+// Detection de la perte du reseau (network.offline_detection).
+// Deux signaux : la table de routage du systeme (plus de route vers Internet : cable debranche,
+// Wi-Fi coupe, mise en veille) et les circuits (network.offline_after_failures mesures de sante
+// consecutives sans reponse). Hors ligne, l'etat passe a "hors ligne", le client Tor est mis en
+// sommeil (plus de tentatives de circuits ni de telechargement de l'annuaire) et un amorcage en
+// cours est interrompu ; au retour du reseau, le client est reveille et l'amorcage relance.
+// Apres un echec de l'amorcage, une requete HTTP en clair vers la page de detection de Firefox
+// (network.captive_portal_check) revele un eventuel portail captif, dont la page de connexion
+// est proposee a l'utilisateur.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arti_client::DormantMode;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::config::NetworkConfig;
+use crate::gui::state::{AppState, FailedStage};
+
+/// Intervalle minimal entre deux verifications
+const MIN_CHECK_SECS: u64 = 1;
+
+/// Adresses de documentation (RFC 5737, RFC 3849) : seule la route compte, rien n'est envoye
+const ROUTE_PROBES: [(&str, &str); 2] = [("0.0.0.0:0", "192.0.2.1:9"), ("[::]:0", "[2001:db8::1]:9")];
+
+/// Page de detection des portails captifs de Firefox : "success" en acces direct
+const PORTAL_PROBE_HOST: &str = "detectportal.firefox.com";
+const PORTAL_PROBE_PATH: &str = "/success.txt";
+const PORTAL_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Taille maximale lue de la reponse (page de connexion d'un portail comprise)
+const PORTAL_MAX_RESPONSE: u64 = 64 * 1024;
+
+/// Surveille le reseau jusqu'a l'arret
+pub async fn run_network_monitor(config: NetworkConfig, state: Arc<AppState>) {
+    let interval = Duration::from_secs(config.check_secs.max(MIN_CHECK_SECS));
+    loop {
+        let route = has_route().await;
+        let circuits_failing =
+            config.offline_after_failures > 0 && state.health_failures() >= config.offline_after_failures;
+        let offline = !route || circuits_failing;
+
+        if offline != state.is_offline() {
+            let tor_client = state.get_backend().map(|backend| backend.tor_client);
+            if offline {
+                if route {
+                    tracing::warn!("{}", crate::tl!("network.offline_circuits", count = state.health_failures()));
+                } else {
+                    tracing::warn!("{}", crate::tl!("network.offline_no_route"));
+                }
+                if let Some(tor_client) = &tor_client {
+                    tor_client.set_dormant(DormantMode::Soft);
+                }
+                state.set_offline(true);
+            } else {
+                tracing::info!("{}", crate::tl!("network.online"));
+                if let Some(tor_client) = &tor_client {
+                    tor_client.set_dormant(DormantMode::Normal);
+                }
+                state.set_offline(false);
+                // Amorcage abandonne pendant la coupure : relance sans attendre "Reessayer"
+                if state.last_error().is_some_and(|error| error.stage == FailedStage::Bootstrap) {
+                    state.request_retry();
+                }
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Recherche un portail captif et, s'il y en a un, propose sa page de connexion a la fenetre
+pub async fn check_captive_portal(state: Arc<AppState>) {
+    if let Some(url) = detect_captive_portal().await {
+        tracing::warn!("{}", crate::tl!("network.captive_portal", url = &url));
+        state.set_captive_portal(Some(url));
+    }
+}
+
+/// Page de connexion d'un portail captif : redirection du portail, ou la page de detection
+/// elle-meme quand le portail en remplace le contenu. None sans portail ou sans reponse.
+async fn detect_captive_portal() -> Option<String> {
+    let raw = tokio::time::timeout(PORTAL_PROBE_TIMEOUT, async {
+        let mut stream = tokio::net::TcpStream::connect((PORTAL_PROBE_HOST, 80)).await?;
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            PORTAL_PROBE_PATH, PORTAL_PROBE_HOST
+        );
+        stream.write_all(request.as_bytes()).await?;
+        let mut raw = Vec::new();
+        stream.take(PORTAL_MAX_RESPONSE).read_to_end(&mut raw).await?;
+        Ok::<_, std::io::Error>(raw)
+    })
+    .await
+    .ok()?
+    .ok()?;
+
+    let response = crate::probe::parse_response(&raw, PORTAL_PROBE_HOST).ok()?;
+    let probe_url = format!("http://{}{}", PORTAL_PROBE_HOST, PORTAL_PROBE_PATH);
+    match response.status {
+        200 if String::from_utf8_lossy(&response.body).trim() == "success" => None,
+        300..=399 => Some(redirect_location(&raw).unwrap_or(probe_url)),
+        // 511 : "Network Authentication Required", la reponse normalisee des portails
+        200..=299 | 511 => Some(probe_url),
+        _ => None,
+    }
+}
+
+/// En-tete Location d'une redirection, limite aux pages web
+fn redirect_location(raw: &[u8]) -> Option<String> {
+    let header_end = raw.windows(4).position(|w| w == b"\r\n\r\n")?;
+    String::from_utf8_lossy(&raw[..header_end])
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("location"))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+/// Route vers Internet connue du systeme, en IPv4 ou IPv6 : "connexion" d'un socket UDP, qui ne
+/// fait que consulter la table de routage
+async fn has_route() -> bool {
+    for (local, remote) in ROUTE_PROBES {
+        if let Ok(socket) = tokio::net::UdpSocket::bind(local).await {
+            if socket.connect(remote).await.is_ok() {
+                return true;
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,229 @@
+// Hebergement de services onion (mode inverse).
+// Chaque service declare dans [[onion_services]] est lance via arti : le descripteur est publie,
+// les flux de rendez-vous sont acceptes puis relayes vers la cible TCP locale configuree.
+// Les cles du service sont conservees dans le keystore d'arti (sous le repertoire d'etat) ;
+// ce module permet aussi de les sauvegarder, les importer ou les regenerer.
+// Un service peut restreindre sa decouverte a une liste de clients autorises.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arti_client::config::onion_service::OnionServiceConfigBuilder;
+use arti_client::TorClient;
+use futures::StreamExt;
+use safelog::DisplayRedacted;
+use tokio::net::TcpStream;
+use tokio_util::compat::{FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt};
+use tor_cell::relaycell::msg::{Connected, End, EndReason};
+use tor_hscrypto::pk::HsClientDescEncKey;
+use tor_hsservice::config::restricted_discovery::HsClientNickname;
+use tor_hsservice::{handle_rend_requests, HsNickname, StreamRequest};
+use tor_proto::stream::IncomingStreamRequest;
+use tor_rtcompat::PreferredRuntime;
+
+use crate::config::OnionServiceConfig;
+use crate::gui::state::{AppState, OnionServiceStatus};
+
+/// Lance un service onion et relaie ses flux entrants jusqu'a l'arret du client Tor.
+pub async fn run_onion_service(
+    service: OnionServiceConfig,
+    tor_client: Arc<TorClient<PreferredRuntime>>,
+    state: Arc<AppState>,
+) -> Result<()> {
+    let nickname: HsNickname = service
+        .nickname
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{}", crate::t!("onion.invalid_nickname", nickname = &service.nickname, error = e)))?;
+
+    let mut hs_builder = OnionServiceConfigBuilder::default();
+    hs_builder.nickname(nickname);
+
+    // Decouverte restreinte : seuls les clients autorises peuvent dechiffrer le descripteur
+    if !service.authorized_clients.is_empty() {
+        let restricted = hs_builder.restricted_discovery();
+        restricted.enabled(true);
+        for (client, key) in &service.authorized_clients {
+            let client_nickname: HsClientNickname = client.parse().map_err(|e| {
+                anyhow::anyhow!("{}", crate::t!("onion.invalid_client", nickname = &service.nickname, client = client, error = e))
+            })?;
+            let client_key: HsClientDescEncKey = key.trim().parse().map_err(|e| {
+                anyhow::anyhow!("{}", crate::t!("onion.invalid_client_key", nickname = &service.nickname, client = client, error = e))
+            })?;
+            restricted.static_keys().insert(client_nickname, client_key);
+        }
+        tracing::info!(
+            "{}",
+            crate::tl!("onion.restricted_discovery", nickname = &service.nickname, count = service.authorized_clients.len())
+        );
+    }
+
+    let hs_config = hs_builder
+        .build()
+        .with_context(|| crate::t!("onion.build_config_failed", nickname = &service.nickname))?;
+
+    // Le service (et ses cles) reste actif tant que `running` n'est pas libere
+    let (running, rend_requests) = tor_client
+        .launch_onion_service(hs_config)
+        .with_context(|| crate::t!("onion.launch_failed", nickname = &service.nickname))?
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("onion.disabled", nickname = &service.nickname)))?;
+
+    let address = running
+        .onion_address()
+        .map(|hsid| hsid.display_unredacted().to_string())
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("onion.no_address", nickname = &service.nickname)))?;
+
+    tracing::info!(
+        "{}",
+        crate::tl!("onion.published", nickname = &service.nickname, address = &address, port = service.port, target = &service.target)
+    );
+    state.set_onion_service(OnionServiceStatus {
+        nickname: service.nickname.clone(),
+        address,
+        port: service.port,
+        target: service.target.clone(),
+    });
+
+    // Boucle d'acceptation des flux de rendez-vous
+    let mut stream_requests = handle_rend_requests(rend_requests);
+    while let Some(request) = stream_requests.next().await {
+        let port_matches = matches!(
+            request.request(),
+            IncomingStreamRequest::Begin(begin) if begin.port() == service.port
+        );
+        if !port_matches {
+            tracing::debug!("{}", crate::tl!("onion.unexpected_request", nickname = &service.nickname));
+            let _ = request.shutdown_circuit();
+            continue;
+        }
+
+        let nickname = service.nickname.clone();
+        let target = service.target.clone();
+        tokio::spawn(async move {
+            if let Err(e) = relay_onion_stream(request, &target).await {
+                tracing::warn!("{}", crate::tl!("onion.stream_error", nickname = nickname, error = e));
+            }
+        });
+    }
+
+    drop(running);
+    Ok(())
+}
+
+/// Connecte la cible locale, accepte le flux onion puis relaie dans les deux sens
+async fn relay_onion_stream(request: StreamRequest, target: &str) -> Result<()> {
+    // Refuser proprement le flux si la cible locale est injoignable
+    let local_stream = match TcpStream::connect(target).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = request
+                .reject(End::new_with_reason(EndReason::CONNECTREFUSED))
+                .await;
+            anyhow::bail!("{}", crate::t!("onion.target_unreachable", target = target, error = e));
+        }
+    };
+
+    let onion_stream = request
+        .accept(Connected::new_empty())
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", crate::t!("onion.accept_failed", error = e)))?;
+
+    let (onion_reader, onion_writer) = onion_stream.split();
+    let mut onion_read = onion_reader.compat();
+    let mut onion_write = onion_writer.compat_write();
+    let (mut local_read, mut local_write) = local_stream.into_split();
+
+    let (to_local, to_onion) = tokio::join!(
+        tokio::io::copy(&mut onion_read, &mut local_write),
+        tokio::io::copy(&mut local_read, &mut onion_write),
+    );
+    tracing::debug!(
+        "{}",
+        crate::tl!(
+            "onion.relay_complete",
+            target = target,
+            received = to_local.unwrap_or_default(),
+            sent = to_onion.unwrap_or_default()
+        )
+    );
+
+    Ok(())
+}
+
+/// Repertoire du keystore d'arti contenant les cles d'un service onion
+pub fn service_key_dir(data_dir: &str, nickname: &str) -> PathBuf {
+    Path::new(data_dir)
+        .join("state")
+        .join("keystore")
+        .join("hss")
+        .join(nickname)
+}
+
+/// Sauvegarde les cles d'un service dans `dest/<nickname>`. Retourne le nombre de fichiers copies.
+pub fn export_keys(data_dir: &str, nickname: &str, dest: &Path) -> Result<usize> {
+    let src = service_key_dir(data_dir, nickname);
+    if !src.is_dir() {
+        anyhow::bail!("{}", crate::t!("onion.no_keys", nickname = nickname));
+    }
+    copy_key_files(&src, &dest.join(nickname))
+}
+
+/// Importe les cles d'un service depuis une sauvegarde (pris en compte au prochain demarrage).
+/// Le dossier source doit contenir la cle d'identite du service.
+pub fn import_keys(data_dir: &str, nickname: &str, src: &Path) -> Result<usize> {
+    let has_identity_key = std::fs::read_dir(src)
+        .with_context(|| crate::t!("onion.read_dir_failed", path = src.display()))?
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().to_string_lossy().starts_with("ks_hs_id"));
+    if !has_identity_key {
+        anyhow::bail!("{}", crate::t!("onion.no_identity_key", path = src.display()));
+    }
+    copy_key_files(src, &service_key_dir(data_dir, nickname))
+}
+
+/// Supprime les cles d'un service : arti en generera de nouvelles (nouvelle adresse) au demarrage
+pub fn delete_keys(data_dir: &str, nickname: &str) -> Result<()> {
+    let dir = service_key_dir(data_dir, nickname);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .with_context(|| crate::t!("onion.delete_keys_failed", path = dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Copie les fichiers de cles d'un dossier a l'autre en restreignant les permissions
+fn copy_key_files(src: &Path, dest: &Path) -> Result<usize> {
+    std::fs::create_dir_all(dest)
+        .with_context(|| crate::t!("onion.create_dir_failed", path = dest.display()))?;
+    restrict_permissions(dest, true)?;
+
+    let mut copied = 0;
+    for entry in std::fs::read_dir(src)
+        .with_context(|| crate::t!("onion.read_dir_failed", path = src.display()))?
+    {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let target = dest.join(entry.file_name());
+        std::fs::copy(entry.path(), &target)?;
+        restrict_permissions(&target, false)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Cles privees : dossiers en 0700 et fichiers en 0600 (lisibles par l'utilisateur seul)
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, is_dir: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if is_dir { 0o700 } else { 0o600 };
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+/// Sous Windows, les ACL heritees du profil utilisateur protegent deja le repertoire de donnees
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _is_dir: bool) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,146 @@
+// Export OpenTelemetry (OTLP/HTTP), fonctionnalite cargo "otel".
+// Les spans du cycle de vie des connexions SOCKS (handshake, connexion Tor, relais) sont
+// exportes vers le collecteur avec des compteurs (connexions, erreurs, octets relayes, flux
+// par pays de sortie), pour les installations partagees. Sans la fonctionnalite, traced!() laisse le futur intact
+// et aucun span n'est cree : le format des journaux ne change pas.
+
+/// Execute un futur dans un span exporte en OTLP (fonctionnalite "otel"), tel quel sinon.
+/// Usage : traced!(futur, "tor_connect", host = %host, port = port)
+#[cfg(feature = "otel")]
+#[macro_export]
+macro_rules! traced {
+    ($fut:expr, $($span:tt)+) => {
+        ::tracing::Instrument::instrument($fut, ::tracing::info_span!($($span)+))
+    };
+}
+
+#[cfg(not(feature = "otel"))]
+#[macro_export]
+macro_rules! traced {
+    ($fut:expr, $($span:tt)+) => {
+        $fut
+    };
+}
+
+#[cfg(feature = "otel")]
+pub use export::{init, record_bytes, record_connection, record_error, record_exit_country, OtelGuard};
+
+#[cfg(feature = "otel")]
+mod export {
+    use std::sync::OnceLock;
+
+    use anyhow::{Context, Result};
+    use opentelemetry::metrics::{Counter, MeterProvider};
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+
+    use crate::config::OtelConfig;
+
+    /// Compteurs exportes, crees par init()
+    struct Metrics {
+        connections: Counter<u64>,
+        errors: Counter<u64>,
+        bytes: Counter<u64>,
+        exit_countries: Counter<u64>,
+    }
+
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+    /// Fournisseurs OTLP : leur liberation (fin de main) envoie les donnees en attente
+    pub struct OtelGuard {
+        tracer_provider: SdkTracerProvider,
+        meter_provider: SdkMeterProvider,
+    }
+
+    impl Drop for OtelGuard {
+        fn drop(&mut self) {
+            let _ = self.tracer_provider.shutdown();
+            let _ = self.meter_provider.shutdown();
+        }
+    }
+
+    /// Cree les exportateurs OTLP/HTTP vers `otel.endpoint` (/v1/traces et /v1/metrics) et
+    /// retourne le traceur a brancher sur tracing (couche tracing-opentelemetry)
+    pub fn init(config: &OtelConfig) -> Result<(OtelGuard, SdkTracer)> {
+        let endpoint = config.endpoint.trim_end_matches('/');
+        let resource = Resource::builder().with_service_name(config.service_name.clone()).build();
+
+        let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .build()
+            .context("OTLP span exporter")?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", endpoint))
+            .build()
+            .context("OTLP metric exporter")?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metric_exporter)
+            .with_resource(resource)
+            .build();
+
+        let meter = meter_provider.meter("ironcloak");
+        let _ = METRICS.set(Metrics {
+            connections: meter
+                .u64_counter("ironcloak.socks.connections")
+                .with_description("SOCKS connections accepted")
+                .build(),
+            errors: meter
+                .u64_counter("ironcloak.socks.errors")
+                .with_description("SOCKS connections ended by an error")
+                .build(),
+            bytes: meter
+                .u64_counter("ironcloak.socks.bytes")
+                .with_description("Bytes relayed through Tor")
+                .with_unit("By")
+                .build(),
+            exit_countries: meter
+                .u64_counter("ironcloak.socks.exit_country")
+                .with_description("Streams established, by exit relay country")
+                .build(),
+        });
+
+        let tracer = tracer_provider.tracer("ironcloak");
+        Ok((OtelGuard { tracer_provider, meter_provider }, tracer))
+    }
+
+    /// Nouvelle connexion SOCKS acceptee
+    pub fn record_connection() {
+        if let Some(metrics) = METRICS.get() {
+            metrics.connections.add(1, &[]);
+        }
+    }
+
+    /// Connexion SOCKS terminee par une erreur
+    pub fn record_error() {
+        if let Some(metrics) = METRICS.get() {
+            metrics.errors.add(1, &[]);
+        }
+    }
+
+    /// Octets relayes par une connexion, dans chaque sens
+    pub fn record_bytes(up: u64, down: u64) {
+        if let Some(metrics) = METRICS.get() {
+            metrics.bytes.add(up, &[KeyValue::new("direction", "up")]);
+            metrics.bytes.add(down, &[KeyValue::new("direction", "down")]);
+        }
+    }
+
+    /// Flux etabli, par pays du relais de sortie (GeoIP embarquee, "??" si inconnu)
+    pub fn record_exit_country(country: Option<&str>) {
+        if let Some(metrics) = METRICS.get() {
+            let country = country.unwrap_or("??").to_string();
+            metrics.exit_countries.add(1, &[KeyValue::new("country", country)]);
+        }
+    }
+}
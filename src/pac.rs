@@ -0,0 +1,131 @@
+// Serveur PAC (proxy auto-config) sur un port HTTP local.
+// Les navigateurs pointes sur http://127.0.0.1:<port>/proxy.pac utilisent le proxy SOCKS5
+// d'IronCloak, sauf pour les hotes listes dans pac.bypass (acces direct).
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::config::IronCloakConfig;
+
+/// Taille maximale lue de la requete HTTP (seule la premiere ligne nous interesse)
+const MAX_REQUEST: usize = 8192;
+
+/// Lance le serveur PAC et repond a chaque requete avec le meme script.
+pub async fn run_pac_server(config: &IronCloakConfig) -> Result<()> {
+    let bind_addr = format!("127.0.0.1:{}", config.pac.port);
+    let listener = TcpListener::bind(&bind_addr)
+        .await
+        .with_context(|| crate::t!("pac.bind_failed", addr = &bind_addr))?;
+
+    let script = pac_script(config);
+    tracing::info!("{}", crate::tl!("pac.listening", addr = &bind_addr));
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let script = script.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve(stream, &script).await {
+                tracing::debug!("{}", crate::tl!("pac.request_error", error = e));
+            }
+        });
+    }
+}
+
+/// Genere le script PAC : exceptions directes puis proxy SOCKS5 d'IronCloak
+fn pac_script(config: &IronCloakConfig) -> String {
+    // Une ecoute sur toutes les interfaces reste joignable via le loopback de la meme famille
+    let proxy = match crate::bench::local_host(&config.proxy.listen_addr) {
+        host if host.contains(':') && !host.starts_with('[') => {
+            format!("SOCKS5 [{}]:{}", host, config.proxy.listen_port)
+        }
+        host => format!("SOCKS5 {}:{}", host, config.proxy.listen_port),
+    };
+
+    let mut script = String::from("function FindProxyForURL(url, host) {\n");
+    for pattern in &config.pac.bypass {
+        // Litteral JSON = chaine JavaScript correctement echappee
+        let literal = serde_json::to_string(pattern).unwrap_or_default();
+        script.push_str(&format!(
+            "  if (shExpMatch(host, {literal})) return \"DIRECT\";\n"
+        ));
+    }
+    // Pas de repli "DIRECT" : si IronCloak est arrete, le navigateur echoue au lieu de fuiter
+    script.push_str(&format!("  return \"{proxy}\";\n}}\n"));
+    script
+}
+
+/// Lit la requete HTTP et renvoie le script PAC (ou 404 hors de "/" et "/proxy.pac")
+async fn serve(mut stream: TcpStream, script: &str) -> Result<()> {
+    let mut buf = vec![0u8; MAX_REQUEST];
+    let mut len = 0;
+    while len < buf.len() {
+        let n = stream.read(&mut buf[len..]).await?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+        if buf[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("");
+
+    let response = match path {
+        "/" | "/proxy.pac" => format!(
+            "HTTP/1.0 200 OK\r\n\
+             Content-Type: application/x-ns-proxy-autoconfig\r\n\
+             Content-Length: {}\r\n\
+             Cache-Control: no-cache\r\n\
+             Connection: close\r\n\r\n{}",
+            script.len(),
+            script
+        ),
+        _ => "HTTP/1.0 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy_line(listen_addr: &str) -> String {
+        let mut config = IronCloakConfig::default();
+        config.proxy.listen_addr = listen_addr.to_string();
+        config.proxy.listen_port = 9150;
+        config.pac.bypass.clear();
+        pac_script(&config)
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("return ").map(str::to_string))
+            .unwrap()
+    }
+
+    #[test]
+    fn proxy_address_is_reachable_and_bracketed() {
+        assert_eq!(proxy_line("127.0.0.1"), "\"SOCKS5 127.0.0.1:9150\";");
+        assert_eq!(proxy_line("0.0.0.0"), "\"SOCKS5 127.0.0.1:9150\";");
+        assert_eq!(proxy_line("::1"), "\"SOCKS5 [::1]:9150\";");
+        assert_eq!(proxy_line("[::1]"), "\"SOCKS5 [::1]:9150\";");
+        assert_eq!(proxy_line("::"), "\"SOCKS5 [::1]:9150\";");
+        assert_eq!(proxy_line("[::]"), "\"SOCKS5 [::1]:9150\";");
+        assert_eq!(proxy_line("localhost"), "\"SOCKS5 localhost:9150\";");
+    }
+
+    #[test]
+    fn bypass_patterns_are_escaped() {
+        let mut config = IronCloakConfig::default();
+        config.pac.bypass = vec!["*.lan".to_string(), "a\"b".to_string()];
+        let script = pac_script(&config);
+        assert!(script.contains("if (shExpMatch(host, \"*.lan\")) return \"DIRECT\";"));
+        assert!(script.contains("if (shExpMatch(host, \"a\\\"b\")) return \"DIRECT\";"));
+    }
+}
@@ -0,0 +1,257 @@
+// Sondes HTTPS a travers le client Tor (jamais en clair sur le reseau local).
+// Fournit une requete GET minimale, le test de bout en bout check.torproject.org, la mesure
+// de latence des circuits (indicateur de sante) et le test de debit.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use arti_client::{IsolationToken, StreamPrefs, TorClient};
+use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tor_geoip::GeoipDb;
+use tor_rtcompat::PreferredRuntime;
+
+/// Hote de l'API de verification du projet Tor
+const CHECK_HOST: &str = "check.torproject.org";
+const CHECK_PATH: &str = "/api/ip";
+
+/// Point de telechargement du test de debit : charge utile de taille fixe
+const SPEED_HOST: &str = "speed.cloudflare.com";
+const SPEED_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Taille maximale d'une reponse lue par defaut (les API interrogees sont minuscules)
+const DEFAULT_MAX_BODY: usize = 64 * 1024;
+
+/// Reponse HTTP simplifiee
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// Resultat du test "Verifier Tor"
+#[derive(Debug, Clone)]
+pub struct TorCheck {
+    /// Le serveur de verification voit une connexion provenant d'un relais de sortie Tor
+    pub is_tor: bool,
+    /// Adresse IP de sortie vue par le serveur
+    pub ip: String,
+    /// Pays de l'adresse de sortie (base GeoIP embarquee)
+    pub country: Option<String>,
+}
+
+/// Resultat du test de debit
+#[derive(Debug, Clone)]
+pub struct SpeedTest {
+    /// Ouverture du flux jusqu'au point de telechargement
+    pub latency: Duration,
+    /// Octets du corps recus et duree du telechargement
+    pub bytes: u64,
+    pub duration: Duration,
+}
+
+impl SpeedTest {
+    /// Debit en megabits par seconde
+    pub fn mbps(&self) -> f64 {
+        self.bytes as f64 * 8.0 / self.duration.as_secs_f64().max(f64::EPSILON) / 1_000_000.0
+    }
+}
+
+/// Format de reponse de check.torproject.org/api/ip
+#[derive(Deserialize)]
+struct CheckResponse {
+    #[serde(rename = "IsTor")]
+    is_tor: bool,
+    #[serde(rename = "IP")]
+    ip: String,
+}
+
+/// Effectue une requete HTTPS GET a travers Tor et retourne le statut et le corps.
+/// HTTP/1.0 est utilise pour eviter l'encodage "chunked" et lire jusqu'a la fermeture.
+pub async fn https_get(
+    tor_client: &TorClient<PreferredRuntime>,
+    host: &str,
+    path: &str,
+    timeout: Duration,
+    max_body: usize,
+) -> Result<HttpResponse> {
+    tokio::time::timeout(timeout, async {
+        let stream = tor_client
+            .connect((host, 443))
+            .await
+            .with_context(|| crate::t!("probe.connect_failed", host = host))?;
+
+        let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+        let mut tls = connector
+            .connect(host, stream.compat())
+            .await
+            .with_context(|| crate::t!("probe.tls_failed", host = host))?;
+
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: IronCloak/{}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            path,
+            host,
+            env!("CARGO_PKG_VERSION")
+        );
+        tls.write_all(request.as_bytes()).await?;
+        tls.flush().await?;
+
+        // Lire la reponse complete en limitant la taille
+        let mut raw = Vec::new();
+        let mut limited = (&mut tls).take((max_body + 16 * 1024) as u64);
+        limited.read_to_end(&mut raw).await?;
+
+        parse_response(&raw, host)
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("{}", crate::t!("probe.timeout", host = host, secs = timeout.as_secs())))?
+}
+
+/// Decoupe une reponse HTTP brute en statut et corps
+pub fn parse_response(raw: &[u8], host: &str) -> Result<HttpResponse> {
+    let header_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("probe.bad_response", host = host)))?;
+    let head = String::from_utf8_lossy(&raw[..header_end]);
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("probe.bad_response", host = host)))?;
+
+    Ok(HttpResponse {
+        status,
+        body: raw[header_end + 4..].to_vec(),
+    })
+}
+
+/// Temps d'ouverture d'un flux vers check.torproject.org:443 : un aller-retour jusqu'au relais
+/// de sortie et la connexion TCP de celui-ci, sans rien envoyer. Le jeton d'isolation choisit
+/// les circuits mesures.
+pub async fn stream_latency(
+    tor_client: &TorClient<PreferredRuntime>,
+    isolation: IsolationToken,
+    timeout: Duration,
+) -> Result<Duration> {
+    let mut prefs = StreamPrefs::new();
+    prefs.set_isolation(isolation);
+    let started = Instant::now();
+    tokio::time::timeout(timeout, tor_client.connect_with_prefs((CHECK_HOST, 443), &prefs))
+        .await
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("probe.timeout", host = CHECK_HOST, secs = timeout.as_secs())))?
+        .with_context(|| crate::t!("probe.connect_failed", host = CHECK_HOST))?;
+    Ok(started.elapsed())
+}
+
+/// Test de bout en bout : interroge check.torproject.org a travers Tor
+/// et retourne l'IP de sortie observee avec son pays.
+pub async fn check_tor(tor_client: &TorClient<PreferredRuntime>) -> Result<TorCheck> {
+    let response = https_get(
+        tor_client,
+        CHECK_HOST,
+        CHECK_PATH,
+        Duration::from_secs(60),
+        DEFAULT_MAX_BODY,
+    )
+    .await?;
+    if response.status != 200 {
+        anyhow::bail!("{}", crate::t!("probe.http_status", host = CHECK_HOST, status = response.status));
+    }
+
+    let parsed: CheckResponse = serde_json::from_slice(&response.body)
+        .with_context(|| crate::t!("probe.bad_response", host = CHECK_HOST))?;
+
+    let country = parsed
+        .ip
+        .parse()
+        .ok()
+        .and_then(|ip| GeoipDb::new_embedded().lookup_country_code(ip))
+        .map(|cc| cc.to_string());
+
+    let check = TorCheck {
+        is_tor: parsed.is_tor,
+        ip: parsed.ip,
+        country,
+    };
+    if check.is_tor {
+        tracing::info!(
+            "{}",
+            crate::tl!("probe.check_ok", ip = &check.ip, country = check.country.as_deref().unwrap_or("??"))
+        );
+    } else {
+        tracing::error!("{}", crate::tl!("probe.check_not_tor", ip = &check.ip));
+    }
+    Ok(check)
+}
+
+/// Test de debit : telecharge une charge utile de taille fixe en HTTPS a travers les circuits
+/// courants et mesure la latence d'ouverture du flux et le debit du telechargement
+pub async fn speed_test(tor_client: &TorClient<PreferredRuntime>) -> Result<SpeedTest> {
+    let timeout = Duration::from_secs(120);
+    let test = tokio::time::timeout(timeout, async {
+        let started = Instant::now();
+        let stream = tor_client
+            .connect((SPEED_HOST, 443))
+            .await
+            .with_context(|| crate::t!("probe.connect_failed", host = SPEED_HOST))?;
+        let latency = started.elapsed();
+
+        let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+        let mut tls = connector
+            .connect(SPEED_HOST, stream.compat())
+            .await
+            .with_context(|| crate::t!("probe.tls_failed", host = SPEED_HOST))?;
+
+        let downloading = Instant::now();
+        let request = format!(
+            "GET /__down?bytes={} HTTP/1.0\r\nHost: {}\r\nUser-Agent: IronCloak/{}\r\nConnection: close\r\n\r\n",
+            SPEED_BYTES,
+            SPEED_HOST,
+            env!("CARGO_PKG_VERSION")
+        );
+        tls.write_all(request.as_bytes()).await?;
+        tls.flush().await?;
+
+        // En-tetes conserves jusqu'a leur fin (statut verifie), le corps est seulement compte
+        let mut head = Vec::new();
+        let mut bytes = None;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = tls.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            match bytes.as_mut() {
+                Some(bytes) => *bytes += n as u64,
+                None => {
+                    head.extend_from_slice(&buf[..n]);
+                    if head.windows(4).any(|w| w == b"\r\n\r\n") {
+                        let response = parse_response(&head, SPEED_HOST)?;
+                        if response.status != 200 {
+                            anyhow::bail!("{}", crate::t!("probe.http_status", host = SPEED_HOST, status = response.status));
+                        }
+                        bytes = Some(response.body.len() as u64);
+                    }
+                }
+            }
+        }
+        let bytes = bytes.ok_or_else(|| anyhow::anyhow!("{}", crate::t!("probe.bad_response", host = SPEED_HOST)))?;
+        anyhow::Ok(SpeedTest { latency, bytes, duration: downloading.elapsed() })
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("{}", crate::t!("probe.timeout", host = SPEED_HOST, secs = timeout.as_secs())))??;
+
+    tracing::info!(
+        "{}",
+        crate::tl!(
+            "probe.speed_test",
+            mbps = format!("{:.2}", test.mbps()),
+            latency = test.latency.as_millis(),
+            mb = format!("{:.1}", test.bytes as f64 / 1_048_576.0)
+        )
+    );
+    Ok(test)
+}
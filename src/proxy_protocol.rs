@@ -0,0 +1,253 @@
+// En-tete PROXY de HAProxy (versions 1 texte et 2 binaire), envoye en tete de connexion par
+// un repartiteur de charge ou sslh place devant IronCloak : il transporte l'adresse du vrai
+// client, utilisee ensuite pour l'isolation et les journaux.
+// Active par port d'ecoute (proxy.proxy_protocol, listeners[].proxy_protocol) : l'en-tete est
+// alors obligatoire, une connexion sans en-tete valide est refusee.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Signature de la version 2
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Longueur maximale d'un en-tete v1, CRLF compris
+const V1_MAX_LEN: usize = 107;
+
+/// Longueur maximale acceptee pour les adresses et extensions (TLV) d'un en-tete v2
+const V2_MAX_LEN: usize = 4096;
+
+/// Delai accorde au repartiteur pour envoyer l'en-tete
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lit l'en-tete PROXY en tete du flux, sans consommer la suite (requete SOCKS).
+/// Retourne l'adresse du client d'origine, ou `peer` pour les connexions locales du
+/// repartiteur (v2 LOCAL, v1 UNKNOWN : verifications de sante).
+pub async fn read_header<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> Result<SocketAddr> {
+    match tokio::time::timeout(HEADER_TIMEOUT, read_header_inner(stream, peer)).await {
+        Ok(result) => result,
+        Err(_) => bail!("{}", crate::t!("socks.proxy_header_timeout")),
+    }
+}
+
+async fn read_header_inner<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> Result<SocketAddr> {
+    // Les deux versions font au moins 12 octets ("PROXY UNKNOWN\r\n" en fait 15)
+    let mut start = [0u8; 12];
+    stream.read_exact(&mut start).await?;
+    if start == V2_SIGNATURE {
+        read_v2(stream, peer).await
+    } else if start.starts_with(b"PROXY ") {
+        read_v1(stream, &start, peer).await
+    } else {
+        bail!("{}", crate::t!("socks.proxy_header_invalid"))
+    }
+}
+
+/// Version 1 : "PROXY TCP4 <source> <destination> <port source> <port destination>\r\n"
+async fn read_v1<S: AsyncRead + Unpin>(stream: &mut S, start: &[u8], peer: SocketAddr) -> Result<SocketAddr> {
+    let mut line = start.to_vec();
+    // Octet par octet : rien ne doit etre lu au-dela du CRLF
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LEN {
+            bail!("{}", crate::t!("socks.proxy_header_invalid"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2])
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("socks.proxy_header_invalid")))?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(peer),
+        ["PROXY", "TCP4" | "TCP6", source, _, source_port, _] => {
+            let ip: IpAddr = source
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{}", crate::t!("socks.proxy_header_invalid")))?;
+            let port: u16 = source_port
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{}", crate::t!("socks.proxy_header_invalid")))?;
+            Ok(SocketAddr::new(ip, port))
+        }
+        _ => bail!("{}", crate::t!("socks.proxy_header_invalid")),
+    }
+}
+
+/// Version 2 : version/commande, famille/protocole, longueur, puis adresses et TLV
+async fn read_v2<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> Result<SocketAddr> {
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    let [version_command, family, len_high, len_low] = head;
+    let len = u16::from_be_bytes([len_high, len_low]) as usize;
+    // Version 2, commande LOCAL (0) ou PROXY (1)
+    if version_command >> 4 != 2 || version_command & 0x0F > 1 || len > V2_MAX_LEN {
+        bail!("{}", crate::t!("socks.proxy_header_invalid"));
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+
+    // Commande LOCAL (0) : connexion du repartiteur lui-meme
+    if version_command & 0x0F == 0 {
+        return Ok(peer);
+    }
+    match family >> 4 {
+        // AF_INET : source (4), destination (4), port source (2), port destination (2)
+        1 if body.len() >= 12 => {
+            let ip = Ipv4Addr::new(body[0], body[1], body[2], body[3]);
+            let port = u16::from_be_bytes([body[8], body[9]]);
+            Ok(SocketAddr::new(IpAddr::V4(ip), port))
+        }
+        // AF_INET6 : source (16), destination (16), port source (2), port destination (2)
+        2 if body.len() >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&body[..16]);
+            let port = u16::from_be_bytes([body[32], body[33]]);
+            Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+        }
+        // AF_UNSPEC ou AF_UNIX : pas d'adresse IP exploitable
+        0 | 3 => Ok(peer),
+        _ => bail!("{}", crate::t!("socks.proxy_header_invalid")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn socket(addr: &str) -> SocketAddr {
+        addr.parse().unwrap()
+    }
+
+    fn peer() -> SocketAddr {
+        socket("127.0.0.1:40000")
+    }
+
+    /// Analyse `input` ; rend l'adresse lue et les octets laisses dans le flux
+    async fn parse(input: &[u8]) -> Result<(SocketAddr, Vec<u8>)> {
+        crate::i18n::init("en");
+        let mut stream = input;
+        let addr = read_header(&mut stream, peer()).await?;
+        Ok((addr, stream.to_vec()))
+    }
+
+    fn v2(version_command: u8, family: u8, body: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[version_command, family]);
+        header.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        header.extend_from_slice(body);
+        header
+    }
+
+    #[tokio::test]
+    async fn v1_tcp4_keeps_following_bytes() {
+        let (addr, rest) = parse(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 1080\r\n\x05\x01\x00").await.unwrap();
+        assert_eq!(addr, socket("192.0.2.1:56324"));
+        assert_eq!(rest, [5, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn v1_tcp6() {
+        let (addr, rest) = parse(b"PROXY TCP6 2001:db8::1 2001:db8::2 443 1080\r\n").await.unwrap();
+        assert_eq!(addr, socket("[2001:db8::1]:443"));
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn v1_unknown_uses_peer() {
+        let (addr, rest) = parse(b"PROXY UNKNOWN\r\n\x05").await.unwrap();
+        assert_eq!(addr, peer());
+        assert_eq!(rest, [5]);
+    }
+
+    #[tokio::test]
+    async fn v1_rejects_oversized_line() {
+        let mut line = b"PROXY TCP4 ".to_vec();
+        line.extend(std::iter::repeat(b'1').take(200));
+        line.extend_from_slice(b"\r\n");
+        assert!(parse(&line).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn v1_rejects_truncated_and_malformed_lines() {
+        assert!(parse(b"PROXY TCP4 192.0.2.1 198.51").await.is_err());
+        assert!(parse(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324\r\n").await.is_err());
+        assert!(parse(b"PROXY TCP4 not-an-ip 198.51.100.1 56324 1080\r\n").await.is_err());
+        assert!(parse(b"PROXY TCP4 192.0.2.1 198.51.100.1 99999 1080\r\n").await.is_err());
+        assert!(parse(b"PROXY UDP4 192.0.2.1 198.51.100.1 56324 1080\r\n").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_header() {
+        assert!(parse(b"\x05\x01\x00\x05\x01\x00\x01\x7f\x00\x00\x01\x00").await.is_err());
+        assert!(parse(b"\x05\x01\x00").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn v2_inet_keeps_following_bytes() {
+        let body = [192, 0, 2, 1, 198, 51, 100, 1, 0xDC, 0x04, 0x04, 0x38];
+        let mut input = v2(0x21, 0x11, &body);
+        input.extend_from_slice(&[5, 1, 0]);
+        let (addr, rest) = parse(&input).await.unwrap();
+        assert_eq!(addr, socket("192.0.2.1:56324"));
+        assert_eq!(rest, [5, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn v2_inet6_with_tlv() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        body.extend_from_slice(&"2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+        body.extend_from_slice(&[0x01, 0xBB, 0x04, 0x38]);
+        // Extension (TLV) ignoree
+        body.extend_from_slice(&[0x04, 0x00, 0x02, 0xAA, 0xBB]);
+        let (addr, rest) = parse(&v2(0x21, 0x21, &body)).await.unwrap();
+        assert_eq!(addr, socket("[2001:db8::1]:443"));
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn v2_local_and_unix_use_peer() {
+        let (addr, _) = parse(&v2(0x20, 0x00, &[])).await.unwrap();
+        assert_eq!(addr, peer());
+        // LOCAL avec des adresses : ignorees
+        let (addr, _) = parse(&v2(0x20, 0x11, &[192, 0, 2, 1, 198, 51, 100, 1, 0, 1, 0, 2])).await.unwrap();
+        assert_eq!(addr, peer());
+        // AF_UNIX : deux chemins de 108 octets
+        let (addr, rest) = parse(&v2(0x21, 0x31, &[0u8; 216])).await.unwrap();
+        assert_eq!(addr, peer());
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn v2_rejects_truncated_header() {
+        // Longueur annoncee superieure aux octets recus
+        let mut input = v2(0x21, 0x11, &[192, 0, 2, 1, 198, 51, 100, 1, 0xDC, 0x04, 0x04, 0x38]);
+        input.truncate(input.len() - 6);
+        assert!(parse(&input).await.is_err());
+        // En-tete fixe incomplet
+        let mut input = V2_SIGNATURE.to_vec();
+        input.push(0x21);
+        assert!(parse(&input).await.is_err());
+        // Adresses plus courtes que la famille annoncee
+        assert!(parse(&v2(0x21, 0x11, &[192, 0, 2, 1])).await.is_err());
+        assert!(parse(&v2(0x21, 0x21, &[0u8; 20])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn v2_rejects_oversized_header() {
+        let mut input = V2_SIGNATURE.to_vec();
+        input.extend_from_slice(&[0x21, 0x11]);
+        input.extend_from_slice(&((V2_MAX_LEN + 1) as u16).to_be_bytes());
+        input.extend(std::iter::repeat(0).take(V2_MAX_LEN + 1));
+        assert!(parse(&input).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn v2_rejects_bad_version_command_and_family() {
+        let body = [192, 0, 2, 1, 198, 51, 100, 1, 0xDC, 0x04, 0x04, 0x38];
+        assert!(parse(&v2(0x11, 0x11, &body)).await.is_err());
+        assert!(parse(&v2(0x22, 0x11, &body)).await.is_err());
+        assert!(parse(&v2(0x21, 0x41, &body)).await.is_err());
+    }
+}
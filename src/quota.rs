@@ -0,0 +1,125 @@
+// Quota de donnees pour les connexions facturees au volume : les octets relayes (dans les deux
+// sens) sont totalises par jour et par mois, dans le repertoire de donnees pour survivre aux
+// redemarrages. Au depassement de quota.daily_mb ou quota.monthly_mb, le proxy se met en pause
+// et l'interface le signale ; une reprise manuelle reste possible jusqu'a la periode suivante.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::config::QuotaConfig;
+use crate::gui::state::{AppState, QuotaPeriod};
+
+/// Fichier des compteurs, dans le repertoire de donnees de Tor
+const USAGE_FILE: &str = "usage.json";
+
+/// Intervalle de mise a jour des compteurs
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+const MIB: u64 = 1024 * 1024;
+
+/// Octets relayes sur le jour et le mois en cours
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Usage {
+    /// Jour des compteurs quotidiens (AAAA-MM-JJ, heure locale)
+    day: String,
+    day_bytes: u64,
+    /// Mois des compteurs mensuels (AAAA-MM)
+    month: String,
+    month_bytes: u64,
+}
+
+impl Usage {
+    /// Remet a zero les compteurs d'une periode terminee
+    fn roll(&mut self, day: &str, month: &str) {
+        if self.day != day {
+            self.day = day.to_string();
+            self.day_bytes = 0;
+        }
+        if self.month != month {
+            self.month = month.to_string();
+            self.month_bytes = 0;
+        }
+    }
+
+    /// Periode dont le quota est atteint (le mois l'emporte : la pause dure plus longtemps)
+    fn exceeded(&self, config: &QuotaConfig) -> Option<QuotaPeriod> {
+        let over = |limit: Option<u64>, bytes: u64| limit.is_some_and(|mb| bytes >= mb.saturating_mul(MIB));
+        if over(config.monthly_mb, self.month_bytes) {
+            Some(QuotaPeriod::Monthly)
+        } else if over(config.daily_mb, self.day_bytes) {
+            Some(QuotaPeriod::Daily)
+        } else {
+            None
+        }
+    }
+}
+
+/// Totalise le trafic relaye et met le proxy en pause au depassement du quota
+pub async fn run_quota_monitor(config: QuotaConfig, data_dir: PathBuf, state: Arc<AppState>) {
+    let path = data_dir.join(USAGE_FILE);
+    let mut usage = load(&path);
+    let (up, down) = state.traffic_totals();
+    let mut last = up + down;
+    // Periode (jour ou mois) pour laquelle la pause a deja ete imposee : si l'utilisateur
+    // reprend la main, le proxy n'est pas remis en pause avant la periode suivante
+    let mut paused_for: Option<String> = None;
+
+    loop {
+        let now = Local::now();
+        let (day, month) = (now.format("%Y-%m-%d").to_string(), now.format("%Y-%m").to_string());
+        usage.roll(&day, &month);
+
+        let (up, down) = state.traffic_totals();
+        let delta = (up + down).saturating_sub(last);
+        last = up + down;
+        if delta > 0 {
+            usage.day_bytes += delta;
+            usage.month_bytes += delta;
+            if let Err(e) = save(&path, &usage) {
+                tracing::warn!("{}", crate::tl!("quota.save_failed", path = path.display(), error = e));
+            }
+        }
+
+        let exceeded = usage.exceeded(&config);
+        state.set_quota_reached(exceeded);
+        if let Some(period) = exceeded {
+            let key = match period {
+                QuotaPeriod::Daily => day,
+                QuotaPeriod::Monthly => month,
+            };
+            if paused_for.as_deref() != Some(key.as_str()) {
+                let used = match period {
+                    QuotaPeriod::Daily => usage.day_bytes,
+                    QuotaPeriod::Monthly => usage.month_bytes,
+                };
+                tracing::warn!(
+                    "{}",
+                    crate::tl!("quota.reached", period = period.as_str(), used_mb = used / MIB)
+                );
+                state.set_paused(true);
+                paused_for = Some(key);
+            }
+        }
+
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Lit les compteurs enregistres (zero si le fichier est absent ou illisible)
+fn load(path: &Path) -> Usage {
+    std::fs::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, usage: &Usage) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_vec(usage)?)
+}
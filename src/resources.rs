@@ -0,0 +1,69 @@
+// Ressources consommees par le processus (memoire, descripteurs ou handles ouverts, threads,
+// taches tokio), pour reperer les fuites des installations qui tournent longtemps.
+// Affichees dans la section "A propos" de la fenetre et la commande "status" du socket de
+// controle. Linux : /proc/self ; Windows : API psapi et processthreadsapi.
+
+/// Releve ponctuel ; None pour une mesure indisponible sur la plateforme
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceUsage {
+    /// Memoire residente, en octets
+    pub memory_bytes: Option<u64>,
+    /// Descripteurs de fichiers (Linux) ou handles (Windows) ouverts
+    pub open_handles: Option<u64>,
+    /// Threads du processus
+    pub threads: Option<u64>,
+    /// Taches tokio vivantes sur le runtime du backend
+    pub tasks: Option<usize>,
+}
+
+/// Mesure les ressources du processus ; les taches ne sont comptees qu'avec le runtime
+pub fn snapshot(runtime: Option<&tokio::runtime::Handle>) -> ResourceUsage {
+    let mut usage = platform_usage();
+    usage.tasks = runtime.map(|handle| handle.metrics().num_alive_tasks());
+    usage
+}
+
+#[cfg(target_os = "linux")]
+fn platform_usage() -> ResourceUsage {
+    // VmRSS et Threads de /proc/self/status (memoire en kio)
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.split_whitespace().next())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+    ResourceUsage {
+        memory_bytes: field("VmRSS:").map(|kib| kib * 1024),
+        open_handles: std::fs::read_dir("/proc/self/fd").ok().map(|dir| dir.count() as u64),
+        threads: field("Threads:"),
+        tasks: None,
+    }
+}
+
+#[cfg(windows)]
+fn platform_usage() -> ResourceUsage {
+    use winapi::um::processthreadsapi::{GetCurrentProcess, GetProcessHandleCount};
+    use winapi::um::psapi::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+
+    let mut usage = ResourceUsage::default();
+    unsafe {
+        let process = GetCurrentProcess();
+        let mut counters: PROCESS_MEMORY_COUNTERS = std::mem::zeroed();
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        if GetProcessMemoryInfo(process, &mut counters, size) != 0 {
+            usage.memory_bytes = Some(counters.WorkingSetSize as u64);
+        }
+        let mut handles = 0u32;
+        if GetProcessHandleCount(process, &mut handles) != 0 {
+            usage.open_handles = Some(u64::from(handles));
+        }
+    }
+    usage
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn platform_usage() -> ResourceUsage {
+    ResourceUsage::default()
+}
@@ -0,0 +1,166 @@
+// Bac a sable Linux du backend (sandbox.enabled) : defense en profondeur pour le code expose
+// au reseau (SOCKS, services onion, client Tor).
+// Applique au thread du backend avant la creation du runtime tokio : les threads de tokio et
+// d'arti en heritent, l'interface graphique (dialogues de fichiers, navigateur) n'est pas
+// concernee.
+// - Landlock : ecriture limitee aux repertoires de donnees et de journaux, lecture seule des
+//   fichiers systeme (bibliotheques, certificats, /proc), du fichier de configuration et des
+//   cles d'authentification onion ; sans effet sur un noyau trop ancien. Le socket et le
+//   cookie de controle sont crees avant (control::bind).
+// - seccomp : appels systeme sans usage legitime ici refuses (EPERM) : ptrace, montages,
+//   modules noyau, bpf, espaces de noms... et execve sans transport enfichable configure.
+
+#[cfg(target_os = "linux")]
+pub use linux::apply;
+
+use crate::config::IronCloakConfig;
+
+/// Autres plateformes : pas de bac a sable
+#[cfg(not(target_os = "linux"))]
+pub fn apply(_config: &IronCloakConfig, _config_path: &std::path::Path) {}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+
+    use anyhow::Result;
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, RulesetStatus, ABI,
+    };
+    use seccompiler::{BpfProgram, SeccompAction, SeccompFilter, SeccompRule};
+
+    use super::IronCloakConfig;
+    use crate::config::AuditMode;
+
+    /// Fichiers systeme lus par le backend : bibliotheques, certificats, /proc et /sys
+    /// (ressources, nombre de processeurs), /dev (aleas, /dev/null)
+    const READ_ONLY: &[&str] = &["/usr", "/lib", "/lib64", "/etc", "/proc", "/sys", "/dev"];
+
+    /// Applique Landlock puis seccomp au thread courant (et aux threads qu'il creera)
+    pub fn apply(config: &IronCloakConfig, config_path: &Path) {
+        if !config.sandbox.enabled {
+            tracing::warn!("{}", crate::tl!("sandbox.disabled"));
+            return;
+        }
+        match apply_landlock(config, config_path) {
+            Ok(RulesetStatus::FullyEnforced) => tracing::info!("{}", crate::tl!("sandbox.landlock_enforced")),
+            Ok(RulesetStatus::PartiallyEnforced) => tracing::info!("{}", crate::tl!("sandbox.landlock_partial")),
+            Ok(RulesetStatus::NotEnforced) => tracing::warn!("{}", crate::tl!("sandbox.landlock_unsupported")),
+            Err(e) => tracing::warn!("{}", crate::tl!("sandbox.landlock_failed", error = format!("{:#}", e))),
+        }
+        match apply_seccomp(config) {
+            Ok(()) => tracing::info!("{}", crate::tl!("sandbox.seccomp_enforced")),
+            Err(e) => tracing::warn!("{}", crate::tl!("sandbox.seccomp_failed", error = format!("{:#}", e))),
+        }
+    }
+
+    fn apply_landlock(config: &IronCloakConfig, config_path: &Path) -> Result<RulesetStatus> {
+        let abi = ABI::V3;
+
+        // Repertoires ecrits par le backend, crees d'avance (une regle Landlock porte sur un
+        // chemin existant)
+        let mut read_write: Vec<PathBuf> = vec![
+            PathBuf::from(&config.tor.data_dir),
+            PathBuf::from(&config.logging.log_dir),
+        ];
+        read_write.extend(config.listeners.iter().filter_map(|l| l.data_dir.as_ref().map(PathBuf::from)));
+        for dir in &read_write {
+            std::fs::create_dir_all(dir)?;
+        }
+        if config.audit.mode != AuditMode::Off {
+            // Fichier seul (pas tout son repertoire) : cree d'avance s'il n'existe pas
+            let path = config.audit.path();
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+            read_write.push(path);
+        }
+
+        // Lecture et execution : fichiers systeme, fichier de configuration, cles
+        // d'authentification onion (tor.onion_auth_dir), clients des transports enfichables
+        let mut read_only: Vec<PathBuf> = READ_ONLY.iter().map(PathBuf::from).filter(|p| p.exists()).collect();
+        read_only.extend(
+            std::iter::once(config_path.to_path_buf())
+                .chain(config.tor.onion_auth_dir.as_ref().map(PathBuf::from))
+                .filter(|p| p.exists()),
+        );
+        // resolv.conf lien vers /run (systemd-resolved, NetworkManager) : sans son repertoire
+        // reel, getaddrinfo echoue (detection directe des portails captifs)
+        if let Some(dir) = std::fs::canonicalize("/etc/resolv.conf").ok().and_then(|p| p.parent().map(PathBuf::from)) {
+            if !dir.starts_with("/etc") {
+                read_only.push(dir);
+            }
+        }
+        if config.tor.bridges.enabled {
+            read_only.extend(
+                config
+                    .tor
+                    .bridges
+                    .transports
+                    .values()
+                    .filter_map(|command| command.split_whitespace().next())
+                    .map(PathBuf::from)
+                    .filter(|p| p.exists()),
+            );
+        }
+
+        let status = Ruleset::default()
+            .handle_access(AccessFs::from_all(abi))?
+            .create()?
+            .add_rules(path_beneath_rules(&read_write, AccessFs::from_all(abi)))?
+            .add_rules(path_beneath_rules(&read_only, AccessFs::from_read(abi)))?
+            .restrict_self()?;
+        Ok(status.ruleset)
+    }
+
+    fn apply_seccomp(config: &IronCloakConfig) -> Result<()> {
+        let mut denied = vec![
+            libc::SYS_ptrace,
+            libc::SYS_process_vm_readv,
+            libc::SYS_process_vm_writev,
+            libc::SYS_mount,
+            libc::SYS_umount2,
+            libc::SYS_pivot_root,
+            libc::SYS_chroot,
+            libc::SYS_unshare,
+            libc::SYS_setns,
+            libc::SYS_init_module,
+            libc::SYS_finit_module,
+            libc::SYS_delete_module,
+            libc::SYS_kexec_load,
+            libc::SYS_bpf,
+            libc::SYS_perf_event_open,
+            libc::SYS_userfaultfd,
+            libc::SYS_keyctl,
+            libc::SYS_add_key,
+            libc::SYS_request_key,
+            libc::SYS_open_by_handle_at,
+            libc::SYS_personality,
+            libc::SYS_acct,
+            libc::SYS_swapon,
+            libc::SYS_swapoff,
+            libc::SYS_reboot,
+            libc::SYS_settimeofday,
+            libc::SYS_clock_settime,
+        ];
+        // Les transports enfichables sont des programmes lances par arti
+        let transports = config.tor.bridges.enabled && !config.tor.bridges.transports.is_empty();
+        if !transports {
+            denied.extend([libc::SYS_execve, libc::SYS_execveat]);
+        }
+
+        // Regle vide : l'appel systeme est refuse quels que soient ses arguments
+        let rules: BTreeMap<i64, Vec<SeccompRule>> = denied.into_iter().map(|call| (call, Vec::new())).collect();
+        let filter = SeccompFilter::new(
+            rules,
+            SeccompAction::Allow,
+            SeccompAction::Errno(libc::EPERM as u32),
+            std::env::consts::ARCH.try_into()?,
+        )?;
+        let program: BpfProgram = filter.try_into()?;
+        seccompiler::apply_filter(&program)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+// Durcissement memoire des secrets (security.harden_memory).
+// Les secrets manipules par IronCloak (cle de l'etat chiffre, cles d'autorisation des services
+// onion, secret du socket de controle) sont conserves dans des SecretBytes : pages verrouillees
+// en memoire (mlock / VirtualLock, jamais ecrites dans le fichier d'echange) quand le mode est
+// actif, et toujours effacees a la liberation. Le mode desactive aussi les vidages memoire
+// (core dumps sous Unix, Rapport d'erreurs Windows).
+// Le mot de passe des reglages de la fenetre (gui.lock_password) n'est conserve que sous forme
+// d'empreinte Argon2id.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::SecurityConfig;
+
+/// Mode durci actif (verrouillage des pages des secrets)
+static HARDENED: AtomicBool = AtomicBool::new(false);
+
+/// Active le mode durci : a appeler au demarrage, avant la creation de tout secret
+pub fn init(config: &SecurityConfig) {
+    if !config.harden_memory {
+        return;
+    }
+    HARDENED.store(true, Ordering::Relaxed);
+    match disable_core_dumps() {
+        Ok(()) => tracing::info!("{}", crate::tl!("security.hardened")),
+        Err(e) => tracing::warn!("{}", crate::tl!("security.core_dumps_failed", error = e)),
+    }
+}
+
+/// Octets secrets : verrouilles en memoire en mode durci, effaces a la liberation
+pub struct SecretBytes {
+    data: Box<[u8]>,
+    locked: bool,
+}
+
+impl SecretBytes {
+    /// Reprend le contenu de `data` (le vecteur d'origine est efface)
+    pub fn new(mut data: Vec<u8>) -> Self {
+        let secret = Self::from_slice(&data);
+        zeroize::Zeroize::zeroize(&mut data);
+        secret
+    }
+
+    /// Copie `data` dans une zone dediee (verrouillee en mode durci)
+    pub fn from_slice(data: &[u8]) -> Self {
+        let data: Box<[u8]> = data.into();
+        let hardened = HARDENED.load(Ordering::Relaxed) && !data.is_empty();
+        let locked = hardened && lock(&data);
+        if hardened && !locked {
+            tracing::debug!("{}", crate::tl!("security.lock_failed", bytes = data.len()));
+        }
+        Self { data, locked }
+    }
+
+    /// Secret de `len` octets nuls, a remplir sur place (derivation de cle)
+    pub fn zeroed(len: usize) -> Self {
+        Self::from_slice(&vec![0u8; len])
+    }
+}
+
+impl std::ops::Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl std::ops::DerefMut for SecretBytes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut *self.data);
+        if self.locked {
+            unlock(&self.data);
+        }
+    }
+}
+
+impl std::fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretBytes({} bytes)", self.data.len())
+    }
+}
+
+#[cfg(unix)]
+fn lock(data: &[u8]) -> bool {
+    unsafe { libc::mlock(data.as_ptr().cast(), data.len()) == 0 }
+}
+
+#[cfg(unix)]
+fn unlock(data: &[u8]) {
+    unsafe {
+        libc::munlock(data.as_ptr().cast(), data.len());
+    }
+}
+
+#[cfg(windows)]
+fn lock(data: &[u8]) -> bool {
+    unsafe { winapi::um::memoryapi::VirtualLock(data.as_ptr() as *mut _, data.len()) != 0 }
+}
+
+#[cfg(windows)]
+fn unlock(data: &[u8]) {
+    unsafe {
+        winapi::um::memoryapi::VirtualUnlock(data.as_ptr() as *mut _, data.len());
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock(_data: &[u8]) -> bool {
+    false
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock(_data: &[u8]) {}
+
+/// Empreinte Argon2id d'un mot de passe (format PHC, sel aleatoire), a stocker dans la
+/// configuration
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+    let salt = SaltString::generate(&mut OsRng);
+    argon2::Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Verifie un mot de passe contre son empreinte (refuse si l'empreinte est illisible)
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    PasswordHash::new(hash)
+        .map(|parsed| argon2::Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+/// Unix : taille maximale des core dumps a zero ; Linux : processus non "dumpable" (ni core
+/// dump ni lecture de sa memoire par un autre processus du meme utilisateur)
+#[cfg(unix)]
+fn disable_core_dumps() -> std::io::Result<()> {
+    let limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[cfg(target_os = "linux")]
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Windows : pas de Rapport d'erreurs (ni fenetre, ni vidage) pour ce processus
+#[cfg(windows)]
+fn disable_core_dumps() -> std::io::Result<()> {
+    use winapi::um::errhandlingapi::SetErrorMode;
+    use winapi::um::winbase::{SEM_FAILCRITICALERRORS, SEM_NOGPFAULTERRORBOX};
+    unsafe {
+        SetErrorMode(SEM_FAILCRITICALERRORS | SEM_NOGPFAULTERRORBOX);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn disable_core_dumps() -> std::io::Result<()> {
+    Ok(())
+}
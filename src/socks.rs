@@ -2,86 +2,639 @@
 // Chaque connexion entrante est traitee dans une tache tokio separee.
 // Le flux bidirectionnel est assure entre le client et le circuit Tor.
 
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use arti_client::{StreamPrefs, TorClient};
-use fast_socks5::server::{Config as SocksConfig, DenyAuthentication, Socks5Server, Socks5Socket};
+use arti_client::{DataStream, ErrorKind, HasKind, IsolationToken, StreamPrefs, TorClient};
+use fast_socks5::server::{Config as SocksConfig, DenyAuthentication, Socks5Socket};
 use fast_socks5::util::target_addr::TargetAddr;
-use tokio::io::AsyncWriteExt;
-use tokio::net::TcpStream;
-use tokio_stream::StreamExt;
+use fast_socks5::SocksError;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+use tokio::sync::{watch, Semaphore};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
 use tokio_util::compat::FuturesAsyncWriteCompatExt;
 use tor_rtcompat::PreferredRuntime;
 
-use crate::config::IronCloakConfig;
+use crate::buffer_pool::BufferPool;
+use crate::config::{IpFamily, IronCloakConfig, RebuildPolicy, Redaction, RetryOn, TcpConfig};
+use crate::gui::state::{AppState, ConnectionEntry, HistoryEntry, IsolationKey, StateEvent};
 
 // Compteur atomique pour identifier chaque connexion
 static CONNECTION_COUNTER: AtomicU64 = AtomicU64::new(0);
 
-/// Lance le serveur SOCKS5 et accepte les connexions en boucle.
-/// Chaque connexion est traitee dans une tache tokio independante.
+/// Plafond de proxy.stream.parallel_connects (chaque tentative peut construire un circuit)
+const MAX_PARALLEL_CONNECTS: u8 = 4;
+
+/// Echecs d'acceptation consecutifs au-dela desquels l'ecoute est jugee hors service
+const MAX_ACCEPT_FAILURES: u32 = 10;
+
+/// Pause apres un echec d'acceptation (descripteurs epuises : laisser des connexions se fermer)
+const ACCEPT_FAILURE_PAUSE: Duration = Duration::from_millis(100);
+
+/// Delai maximal entre deux tentatives de reouverture de l'ecoute
+const MAX_REBIND_DELAY: Duration = Duration::from_secs(60);
+
+/// Part minimale des relais de sortie acceptant le port de destination : en dessous (port 25
+/// par exemple), la connexion est refusee aussitot au lieu d'attendre l'expiration du delai
+const MIN_EXIT_PORT_SHARE: f64 = 0.005;
+
+/// Code de reponse SOCKS5 etendu de Tor (proposition 304) : adresse .onion invalide
+const SOCKS_ONION_INVALID_ADDRESS: u8 = 0xF6;
+
+/// Longueur de l'adresse (base32, sans ".onion") des services onion v2, abandonnes par Tor
+const ONION_V2_LEN: usize = 16;
+
+/// Intervalle de verification des ecritures bloquees du relais (proxy.tcp.stall_timeout_secs)
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Version et methodes d'authentification SOCKS5 : "sans authentification", seule acceptee, et
+/// "aucune methode acceptable" (refus)
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS_NO_AUTH: u8 = 0x00;
+const SOCKS_NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+
+/// Code de reponse SOCKS5 "echec general" (file d'attente de l'amorcage pleine ou expiree)
+const SOCKS_GENERAL_FAILURE: u8 = 0x01;
+
+/// Code de reponse SOCKS5 "connexion interdite par les regles" : proxy en pause, ou port que
+/// presque aucune sortie n'accepte (erreur definitive, inutile de reessayer)
+const SOCKS_NOT_ALLOWED: u8 = 0x02;
+
+/// Delai accorde a un client pour envoyer sa salutation et sa requete quand le proxy est en
+/// pause, avant de recevoir le refus
+const PAUSED_REJECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Code de reponse SOCKS5 "reseau injoignable" : refus temporaire pendant la reconstruction des
+/// circuits (proxy.rebuild.policy = "fail_fast"), le client peut reessayer
+const SOCKS_NETWORK_UNREACHABLE: u8 = 0x03;
+
+/// Intervalle de journalisation des connexions en attente de l'amorcage
+const BOOTSTRAP_QUEUE_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Intervalle de mesure du debit (infobulle du systray)
+const THROUGHPUT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Client Tor d'un port d'ecoute : None tant que l'amorcage n'est pas termine (port principal
+/// ouvert pendant l'amorcage, proxy.bootstrap_queue)
+pub type TorSource = watch::Receiver<Option<Arc<TorClient<PreferredRuntime>>>>;
+
+/// Client deja pret (ports d'ecoute supplementaires, ouverts apres l'amorcage)
+pub fn ready(tor_client: Arc<TorClient<PreferredRuntime>>) -> TorSource {
+    watch::channel(Some(tor_client)).1
+}
+
+/// Options appliquees a chaque connexion, extraites une fois de la configuration
+#[derive(Debug, Clone)]
+struct ClientOptions {
+    dns_reject_ip: bool,
+    /// Delai total pour obtenir le flux Tor
+    attach_timeout: std::time::Duration,
+    /// Famille d'adresses demandee au relais de sortie
+    ip_family: IpFamily,
+    /// Masquage des destinations dans les traces
+    redact: Redaction,
+    /// Circuits distincts par hote / par port de destination
+    isolate_dest_addr: bool,
+    isolate_dest_port: bool,
+    /// Circuits distincts par adresse IP du client SOCKS
+    isolate_client_addr: bool,
+    /// Donnees optimistes (proxy.stream.optimistic_data)
+    optimistic_data: bool,
+    /// Tentatives de connexion en parallele (au moins 1)
+    parallel_connects: u8,
+    /// Nouvelles tentatives sur un circuit neuf et echecs concernes (proxy.retry)
+    retry_attempts: u8,
+    retry_on: RetryOn,
+    /// Correspondances d'hotes de [rules.hosts], cles en minuscules sans point final
+    hosts: Arc<HashMap<String, String>>,
+    /// Delai du handshake SOCKS (proxy.tcp.handshake_timeout_secs, None : aucun)
+    handshake_timeout: Option<Duration>,
+    /// Duree maximale d'une ecriture bloquee du relais (proxy.tcp.stall_timeout_secs)
+    stall_timeout: Option<Duration>,
+    /// Tampons de copie du relais, partages par les connexions du port ([proxy.buffers])
+    buffers: Arc<BufferPool>,
+    /// Places de la file d'attente de l'amorcage et attente maximale (proxy.bootstrap_queue)
+    bootstrap_queue: Arc<Semaphore>,
+    bootstrap_wait: Duration,
+    /// Requetes recues pendant la reconstruction des circuits (proxy.rebuild)
+    rebuild_policy: RebuildPolicy,
+    rebuild_hold: Duration,
+    /// BND.ADDR/BND.PORT a zero dans la reponse de succes (proxy.zero_bind_addr)
+    zero_bind_addr: bool,
+}
+
+impl ClientOptions {
+    fn from_config(config: &IronCloakConfig) -> Self {
+        Self {
+            dns_reject_ip: config.proxy.dns_reject_ip,
+            attach_timeout: std::time::Duration::from_secs(config.tor.timeouts.stream_attach_secs),
+            ip_family: config.proxy.ip_family,
+            redact: config.logging.redact_destinations,
+            isolate_dest_addr: config.proxy.isolate_dest_addr,
+            isolate_dest_port: config.proxy.isolate_dest_port,
+            isolate_client_addr: config.proxy.isolate_client_addr,
+            optimistic_data: config.proxy.stream.optimistic_data,
+            parallel_connects: config.proxy.stream.parallel_connects.clamp(1, MAX_PARALLEL_CONNECTS),
+            retry_attempts: config.proxy.retry.attempts,
+            retry_on: config.proxy.retry.on,
+            hosts: Arc::new(
+                config
+                    .rules
+                    .hosts
+                    .iter()
+                    .map(|(name, target)| (normalize_host(name), target.trim().to_string()))
+                    .collect(),
+            ),
+            handshake_timeout: Some(Duration::from_secs(config.proxy.tcp.handshake_timeout_secs))
+                .filter(|timeout| !timeout.is_zero()),
+            stall_timeout: Some(Duration::from_secs(config.proxy.tcp.stall_timeout_secs))
+                .filter(|timeout| !timeout.is_zero()),
+            buffers: BufferPool::new(&config.proxy.buffers),
+            bootstrap_queue: Arc::new(Semaphore::new(config.proxy.bootstrap_queue.size)),
+            bootstrap_wait: Duration::from_secs(config.proxy.bootstrap_queue.timeout_secs),
+            rebuild_policy: config.proxy.rebuild.policy,
+            rebuild_hold: Duration::from_secs(config.proxy.rebuild.hold_secs),
+            zero_bind_addr: config.proxy.zero_bind_addr,
+        }
+    }
+
+    /// Preferences de flux arti correspondant a ces options
+    fn stream_prefs(&self) -> StreamPrefs {
+        let mut prefs = StreamPrefs::new();
+        match self.ip_family {
+            IpFamily::Default => {}
+            IpFamily::Ipv4Only => {
+                prefs.ipv4_only();
+            }
+            IpFamily::Ipv4Preferred => {
+                prefs.ipv4_preferred();
+            }
+            IpFamily::Ipv6Preferred => {
+                prefs.ipv6_preferred();
+            }
+            IpFamily::Ipv6Only => {
+                prefs.ipv6_only();
+            }
+        }
+        if self.optimistic_data {
+            prefs.optimistic();
+        }
+        prefs
+    }
+}
+
+/// Ouvre l'ecoute TCP sur bind_addr avec la file d'attente demandee (proxy.tcp.backlog)
+async fn bind_listener(bind_addr: &str, backlog: u32) -> std::io::Result<TcpListener> {
+    let addr = tokio::net::lookup_host(bind_addr).await?.next().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, bind_addr.to_string())
+    })?;
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    // Comme TcpListener::bind : redemarrage immediat malgre les connexions en TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    socket.listen(backlog)
+}
+
+/// Rouvre l'ecoute perdue (interface disparue, socket invalide), avec un delai croissant
+/// entre les tentatives, jusqu'a ce qu'elle aboutisse
+async fn rebind_listener(bind_addr: &str, backlog: u32) -> TcpListener {
+    let mut delay = Duration::from_secs(1);
+    loop {
+        tokio::time::sleep(delay).await;
+        match bind_listener(bind_addr, backlog).await {
+            Ok(listener) => {
+                tracing::info!("{}", crate::tl!("socks.listening", addr = bind_addr));
+                return listener;
+            }
+            Err(e) => {
+                delay = (delay * 2).min(MAX_REBIND_DELAY);
+                tracing::warn!(
+                    "{}",
+                    crate::tl!("socks.rebind_failed", addr = bind_addr, error = e, secs = delay.as_secs())
+                );
+            }
+        }
+    }
+}
+
+/// Erreur propre a une connexion (abandonnee par le client avant son acceptation) :
+/// l'ecoute elle-meme n'est pas en cause
+fn is_connection_error(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::Interrupted
+            | std::io::ErrorKind::WouldBlock
+    )
+}
+
+/// Applique les options TCP de proxy.tcp (keepalive, TCP_NODELAY) a une connexion acceptee
+fn tune_socket(stream: &TcpStream, tcp: &TcpConfig) -> std::io::Result<()> {
+    if tcp.nodelay {
+        stream.set_nodelay(true)?;
+    }
+    if tcp.keepalive {
+        let keepalive = socket2::TcpKeepalive::new()
+            .with_time(Duration::from_secs(tcp.keepalive_time_secs.max(1)))
+            .with_interval(Duration::from_secs(tcp.keepalive_interval_secs.max(1)));
+        socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive)?;
+    }
+    Ok(())
+}
+
+/// Lance le serveur SOCKS5 sur bind_addr et accepte les connexions en boucle.
+/// Chaque connexion est traitee dans une tache tokio independante, avec le client Tor du port
+/// (attendu dans la file de l'amorcage s'il n'est pas encore pret).
+/// Avec proxy_protocol, chaque connexion commence par un en-tete PROXY (repartiteur de charge).
 pub async fn run_socks_server(
     config: &IronCloakConfig,
-    tor_client: Arc<TorClient<PreferredRuntime>>,
+    bind_addr: String,
+    proxy_protocol: bool,
+    tor_source: TorSource,
+    state: Arc<AppState>,
 ) -> Result<()> {
-    let bind_addr = format!("{}:{}", config.proxy.listen_addr, config.proxy.listen_port);
-    let dns_reject_ip = config.proxy.dns_reject_ip;
+    let options = ClientOptions::from_config(config);
+    if tor_source.borrow().is_none() {
+        tokio::spawn(log_bootstrap_queue(
+            tor_source.clone(),
+            Arc::clone(&options.bootstrap_queue),
+            config.proxy.bootstrap_queue.size,
+            Arc::clone(&state),
+        ));
+    }
 
     // Configuration du serveur SOCKS5 : pas de resolution DNS ni d'execution de commandes
     let mut socks_config = SocksConfig::<DenyAuthentication>::default();
     socks_config.set_dns_resolve(false);
     socks_config.set_execute_command(false);
+    // Salutation (choix de la methode) lue et acquittee par handle_client : la bibliotheque
+    // ne traite plus que la requete
+    socks_config.set_skip_auth(true);
 
-    let server = Socks5Server::<DenyAuthentication>::bind(&bind_addr)
-        .await
-        .with_context(|| crate::t!("socks.bind_failed", &bind_addr))?
-        .with_config(socks_config);
+    let socks_config = Arc::new(socks_config);
 
-    tracing::info!("{}", crate::t!("socks.listening", &bind_addr));
+    // Ecoute TCP geree ici (et non par Socks5Server) pour connaitre l'adresse du client
+    let tcp = config.proxy.tcp.clone();
+    let mut listener = bind_listener(&bind_addr, tcp.backlog)
+        .await
+        .with_context(|| crate::t!("socks.bind_failed", addr = &bind_addr))?;
 
-    // Boucle d'acceptation des connexions entrantes
-    let mut incoming = server.incoming();
+    tracing::info!("{}", crate::tl!("socks.listening", addr = &bind_addr));
 
-    while let Some(socket_result) = incoming.next().await {
-        let socket = match socket_result {
-            Ok(socket) => socket,
+    // Boucle d'acceptation des connexions entrantes ; une ecoute hors service est rouverte
+    // au lieu d'arreter le proxy
+    let mut failures = 0;
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(accepted) => {
+                failures = 0;
+                accepted
+            }
+            Err(e) if is_connection_error(&e) => {
+                tracing::debug!("{}", crate::tl!("socks.accept_failed", error = e));
+                continue;
+            }
             Err(e) => {
-                tracing::warn!("{}", crate::t!("socks.accept_failed", e));
+                tracing::warn!("{}", crate::tl!("socks.accept_failed", error = e));
+                failures += 1;
+                if failures < MAX_ACCEPT_FAILURES {
+                    tokio::time::sleep(ACCEPT_FAILURE_PAUSE).await;
+                } else {
+                    tracing::error!("{}", crate::tl!("socks.listener_lost", addr = &bind_addr));
+                    // Fermer l'ancienne ecoute avant de rouvrir le meme port
+                    drop(listener);
+                    listener = rebind_listener(&bind_addr, tcp.backlog).await;
+                    failures = 0;
+                }
                 continue;
             }
         };
+        if let Err(e) = tune_socket(&stream, &tcp) {
+            tracing::debug!("{}", crate::tl!("socks.tcp_options_failed", error = e));
+        }
+        // Proxy en pause : refuser sans ouvrir de flux Tor, avec une reponse SOCKS5 plutot
+        // qu'une connexion coupee sans explication
+        if state.is_paused() {
+            tracing::debug!("{}", crate::tl!("socks.paused_rejected"));
+            tokio::spawn(reject_paused(stream, peer, proxy_protocol, Arc::clone(&socks_config)));
+            continue;
+        }
 
         let conn_id = CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed);
-        let tor = Arc::clone(&tor_client);
+        let tor = tor_source.clone();
+        let state = Arc::clone(&state);
+        let socks_config = Arc::clone(&socks_config);
+        let options = options.clone();
+
+        #[cfg(feature = "otel")]
+        crate::otel::record_connection();
+
+        // Span "connection" (conn, client, hote et port une fois connus) : contexte de toutes les traces
+        // de la connexion, exporte aussi en OTLP (fonctionnalite "otel")
+        let span = tracing::info_span!(
+            "connection",
+            conn = conn_id,
+            client = tracing::field::Empty,
+            host = tracing::field::Empty,
+            port = tracing::field::Empty
+        );
+        let task = async move {
+            let accepted = Instant::now();
+            tracing::debug!("{}", crate::tl!("socks.new_connection", conn = conn_id));
+
+            // Adresse du vrai client : en-tete PROXY, sinon l'adresse de la connexion TCP
+            let peer = if proxy_protocol {
+                match crate::proxy_protocol::read_header(&mut stream, peer).await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        tracing::warn!("{}", crate::tl!("socks.connection_error", conn = conn_id, error = e));
+                        return;
+                    }
+                }
+            } else {
+                peer
+            };
+            tracing::Span::current().record("client", tracing::field::display(peer.ip()));
+
+            let mut destination = None;
+            let handled = handle_client(
+                stream,
+                socks_config,
+                peer,
+                tor,
+                Arc::clone(&state),
+                options,
+                conn_id,
+                &mut destination,
+            )
+            .await;
+            let summary = match handled {
+                Ok(summary) => summary,
+                Err(e) => {
+                    tracing::warn!("{}", crate::tl!("socks.connection_error", conn = conn_id, error = e));
+                    #[cfg(feature = "otel")]
+                    crate::otel::record_error();
+                    RelaySummary { up: 0, down: 0, outcome: "error" }
+                }
+            };
+            state.remove_connection(conn_id);
 
-        tokio::spawn(async move {
-            tracing::debug!("{}", crate::t!("socks.new_connection", conn_id));
-            if let Err(e) = handle_client(socket, tor, dns_reject_ip, conn_id).await {
-                tracing::warn!("{}", crate::t!("socks.connection_error", conn_id, e));
+            // Historique des connexions fermees et trafic par domaine (destination connue :
+            // handshake termine)
+            let duration = accepted.elapsed();
+            if let Some(destination) = destination {
+                state.record_destination(&destination.domain, summary.up, summary.down);
+                let entry = HistoryEntry {
+                    conn_id,
+                    host: destination.host,
+                    port: destination.port,
+                    closed: chrono::Local::now(),
+                    duration,
+                    up: summary.up,
+                    down: summary.down,
+                    outcome: summary.outcome,
+                };
+                state.publish(StateEvent::ConnectionClosed(entry.clone()));
+                state.record_history(entry);
             }
-            tracing::debug!("{}", crate::t!("socks.connection_closed", conn_id));
-        });
+
+            // Evenement de synthese unique, champs structures pour l'analyse des journaux
+            tracing::info!(
+                duration_ms = duration.as_millis() as u64,
+                bytes_up = summary.up,
+                bytes_down = summary.down,
+                outcome = summary.outcome,
+                "{}",
+                crate::tl!(
+                    "socks.connection_summary",
+                    conn = conn_id,
+                    secs = format!("{:.1}", duration.as_secs_f64()),
+                    up = summary.up,
+                    down = summary.down
+                )
+            );
+        };
+        tokio::spawn(tracing::Instrument::instrument(task, span));
     }
+}
 
-    Ok(())
+/// Destination demandee par le client, masquee comme dans les traces
+struct Destination {
+    host: String,
+    port: u16,
+    /// Domaine enregistrable, cle du trafic par domaine
+    domain: String,
+}
+
+/// Bilan d'une connexion relayee, emis a sa fermeture
+struct RelaySummary {
+    up: u64,
+    down: u64,
+    /// completed | relay_error | closed_by_pause (error : echec avant le relais)
+    outcome: &'static str,
+}
+
+/// Forme canonique d'un nom d'hote pour [rules.hosts] (casse et point final ignores)
+fn normalize_host(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Client Tor du port, attendu pendant l'amorcage dans la file (proxy.bootstrap_queue).
+/// None si la file est pleine ou l'attente trop longue.
+async fn ready_client(
+    tor_source: &mut TorSource,
+    options: &ClientOptions,
+    state: &AppState,
+    conn_id: u64,
+) -> Option<Arc<TorClient<PreferredRuntime>>> {
+    let current = tor_source.borrow().clone();
+    if current.is_some() {
+        return current;
+    }
+    let Ok(_slot) = options.bootstrap_queue.try_acquire() else {
+        tracing::warn!("{}", crate::tl!("socks.bootstrap_queue_full", conn = conn_id));
+        return None;
+    };
+    tracing::info!("{}", crate::tl!("socks.bootstrap_waiting", conn = conn_id, percent = state.bootstrap_percent()));
+    let started = Instant::now();
+    let wait = async { tor_source.wait_for(Option::is_some).await.ok().and_then(|client| client.clone()) };
+    match tokio::time::timeout(options.bootstrap_wait, wait).await {
+        Ok(Some(client)) => {
+            let secs = format!("{:.1}", started.elapsed().as_secs_f64());
+            tracing::debug!("{}", crate::tl!("socks.bootstrap_released", conn = conn_id, secs = secs));
+            Some(client)
+        }
+        _ => {
+            let secs = options.bootstrap_wait.as_secs();
+            tracing::warn!("{}", crate::tl!("socks.bootstrap_wait_timeout", conn = conn_id, secs = secs));
+            None
+        }
+    }
+}
+
+/// Lit la salutation SOCKS5 du client (version, nombre de methodes, puis les methodes) et
+/// rend les methodes d'authentification proposees
+async fn read_greeting(stream: &mut TcpStream) -> std::result::Result<Vec<u8>, SocksError> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    if header[0] != SOCKS5_VERSION {
+        return Err(SocksError::UnsupportedSocksVersion(header[0]));
+    }
+    let mut methods = vec![0u8; usize::from(header[1])];
+    stream.read_exact(&mut methods).await?;
+    Ok(methods)
+}
+
+/// Journalise periodiquement les connexions en attente, jusqu'a la fin de l'amorcage
+async fn log_bootstrap_queue(mut tor_source: TorSource, queue: Arc<Semaphore>, size: usize, state: Arc<AppState>) {
+    loop {
+        tokio::select! {
+            _ = async { tor_source.wait_for(Option::is_some).await.is_ok() } => return,
+            _ = tokio::time::sleep(BOOTSTRAP_QUEUE_LOG_INTERVAL) => {}
+        }
+        let waiting = size.saturating_sub(queue.available_permits());
+        if waiting > 0 {
+            tracing::info!(
+                "{}",
+                crate::tl!("socks.bootstrap_queue", count = waiting, percent = state.bootstrap_percent())
+            );
+        }
+    }
+}
+
+/// Verifie une adresse .onion (sous-domaines admis) : version 3, longueur et somme de controle.
+/// Retourne la raison du refus, traduite.
+fn check_onion_address(host: &str) -> std::result::Result<(), String> {
+    let host = host.to_ascii_lowercase();
+    let name = host.trim_end_matches('.').trim_end_matches(".onion");
+    let label = name.rsplit('.').next().unwrap_or(name);
+    if label.len() == ONION_V2_LEN {
+        return Err(crate::t!("socks.onion_v2"));
+    }
+    format!("{}.onion", label)
+        .parse::<tor_hscrypto::pk::HsId>()
+        .map(|_| ())
+        .map_err(|e| crate::t!("socks.onion_malformed", error = e))
+}
+
+/// Reponse SOCKS5 de succes : VER=5, REP=0, RSV=0, puis ATYP (1 : IPv4, 4 : IPv6), BND.ADDR et
+/// BND.PORT ; 0.0.0.0:0 sans adresse. Une adresse IPv4 vue a travers une ecoute IPv6 double pile
+/// (::ffff:a.b.c.d) est rendue en IPv4.
+fn success_reply(bind: Option<SocketAddr>) -> Vec<u8> {
+    let mut reply = vec![SOCKS5_VERSION, 0x00, 0x00];
+    let (ip, port) = match bind {
+        Some(addr) => (addr.ip().to_canonical(), addr.port()),
+        None => (IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+    };
+    match ip {
+        IpAddr::V4(ip) => {
+            reply.push(0x01);
+            reply.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            reply.push(0x04);
+            reply.extend_from_slice(&ip.octets());
+        }
+    }
+    reply.extend_from_slice(&port.to_be_bytes());
+    reply
+}
+
+/// Repond a une requete refusee avant l'ouverture du flux (code REP), puis ferme la connexion
+async fn reply_failure(socket: Socks5Socket<TcpStream, DenyAuthentication>, rep: u8) {
+    let mut client_stream = socket.into_inner();
+    let reply = [0x05, rep, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    if client_stream.write_all(&reply).await.is_ok() {
+        let _ = client_stream.flush().await;
+    }
+}
+
+/// Proxy en pause : salutation menee a son terme, requete lue, puis refus "connexion
+/// interdite" (0x02), le tout dans un delai borne
+async fn reject_paused(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    proxy_protocol: bool,
+    socks_config: Arc<SocksConfig<DenyAuthentication>>,
+) {
+    let reject = async move {
+        if proxy_protocol {
+            crate::proxy_protocol::read_header(&mut stream, peer).await.ok()?;
+        }
+        let methods = read_greeting(&mut stream).await.ok()?;
+        if !methods.contains(&SOCKS_NO_AUTH) {
+            let _ = stream.write_all(&[SOCKS5_VERSION, SOCKS_NO_ACCEPTABLE_METHODS]).await;
+            return None;
+        }
+        stream.write_all(&[SOCKS5_VERSION, SOCKS_NO_AUTH]).await.ok()?;
+        let socket = Socks5Socket::new(stream, socks_config).upgrade_to_socks5().await.ok()?;
+        reply_failure(socket, SOCKS_NOT_ALLOWED).await;
+        Some(())
+    };
+    let _ = tokio::time::timeout(PAUSED_REJECT_TIMEOUT, reject).await;
 }
 
 /// Traite une connexion client individuelle :
 /// handshake SOCKS5, connexion via Tor, puis relais bidirectionnel.
+/// `destination` recoit l'hote (masque comme dans les traces), son domaine et le port demandes,
+/// pour l'historique et le trafic par domaine, meme si la connexion echoue ensuite.
+#[allow(clippy::too_many_arguments)]
 async fn handle_client(
-    socket: Socks5Socket<TcpStream, DenyAuthentication>,
-    tor_client: Arc<TorClient<PreferredRuntime>>,
-    dns_reject_ip: bool,
+    mut stream: TcpStream,
+    socks_config: Arc<SocksConfig<DenyAuthentication>>,
+    peer: SocketAddr,
+    mut tor_source: TorSource,
+    state: Arc<AppState>,
+    options: ClientOptions,
     conn_id: u64,
-) -> Result<()> {
-    // Completer le handshake SOCKS5
-    let socket = socket
-        .upgrade_to_socks5()
-        .await
-        .map_err(|e| anyhow::anyhow!("{}", crate::t!("socks.handshake_failed", e)))?;
+    destination: &mut Option<Destination>,
+) -> Result<RelaySummary> {
+    // Completer le handshake SOCKS5, dans le delai imparti (client lent ou muet : connexion
+    // fermee et comptee). Un client qui ne propose pas la methode "sans authentification"
+    // (GSSAPI seul...) recoit le refus normalise 0xFF, sans autre bruit qu'une trace de debogage
+    let handshake = async move {
+        let methods = read_greeting(&mut stream).await?;
+        if !methods.contains(&SOCKS_NO_AUTH) {
+            let _ = stream.write_all(&[SOCKS5_VERSION, SOCKS_NO_ACCEPTABLE_METHODS]).await;
+            return Ok(Err(methods));
+        }
+        stream.write_all(&[SOCKS5_VERSION, SOCKS_NO_AUTH]).await?;
+        crate::traced!(Socks5Socket::new(stream, socks_config).upgrade_to_socks5(), "handshake")
+            .await
+            .map(Ok)
+    };
+    let upgraded = match options.handshake_timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, handshake).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::warn!("{}", crate::tl!("socks.handshake_timeout", conn = conn_id, secs = timeout.as_secs()));
+                state.record_handshake_timeout();
+                return Ok(RelaySummary { up: 0, down: 0, outcome: "handshake_timeout" });
+            }
+        },
+        None => handshake.await,
+    };
+    let socket = match upgraded.map_err(|e| anyhow::anyhow!("{}", crate::t!("socks.handshake_failed", error = e)))? {
+        Ok(socket) => socket,
+        Err(methods) => {
+            let methods = methods.iter().map(|m| format!("0x{:02x}", m)).collect::<Vec<_>>().join(", ");
+            tracing::debug!("{}", crate::tl!("socks.no_acceptable_method", conn = conn_id, methods = methods));
+            return Ok(RelaySummary { up: 0, down: 0, outcome: "no_acceptable_method" });
+        }
+    };
 
     let target = match socket.target_addr() {
         Some(addr) => addr.clone(),
@@ -93,65 +646,429 @@ async fn handle_client(
     // Extraire l'hote et le port de l'adresse cible
     let (host, port) = match &target {
         TargetAddr::Ip(sock_addr) => {
-            if dns_reject_ip {
-                tracing::warn!("{}", crate::t!("socks.ip_rejected", conn_id, sock_addr));
+            if options.dns_reject_ip {
+                let ip = crate::logging::redact_host(options.redact, &sock_addr.ip().to_string());
+                tracing::warn!("{}", crate::tl!("socks.ip_rejected", conn = conn_id, ip = ip));
                 anyhow::bail!("{}", crate::t!("socks.ip_rejected_bail"));
             }
             (sock_addr.ip().to_string(), sock_addr.port())
         }
-        TargetAddr::Domain(domain, port) => (domain.clone(), *port),
+        TargetAddr::Domain(domain, port) => match options.hosts.get(&normalize_host(domain)) {
+            // [rules.hosts] : destination remplacee avant la connexion Tor
+            Some(mapped) => {
+                tracing::debug!(
+                    "{}",
+                    crate::tl!(
+                        "socks.host_mapped",
+                        conn = conn_id,
+                        host = crate::logging::redact_host(options.redact, domain),
+                        target = crate::logging::redact_host(options.redact, mapped)
+                    )
+                );
+                (mapped.clone(), *port)
+            }
+            None => (domain.clone(), *port),
+        },
     };
+    // Hote tel qu'affiche dans les traces et les erreurs (logging.redact_destinations)
+    let shown = crate::logging::redact_host(options.redact, &host);
+    *destination = Some(Destination {
+        host: shown.clone(),
+        port,
+        domain: crate::logging::redact_host(options.redact, crate::audit::domain_only(&host)),
+    });
+    state.publish(StateEvent::ConnectionOpened { conn_id, host: shown.clone(), port });
+    let span = tracing::Span::current();
+    span.record("host", shown.as_str());
+    span.record("port", port);
+
+    tracing::info!("{}", crate::tl!("socks.connecting", conn = conn_id, host = &shown, port = port));
+
+    // Adresse .onion verifiee avant toute construction de circuit : une adresse v2 ou mal formee
+    // est refusee aussitot, avec le code SOCKS etendu de Tor, au lieu d'echouer apres le delai
+    let onion = host.to_ascii_lowercase().trim_end_matches('.').ends_with(".onion");
+    if onion {
+        if let Err(reason) = check_onion_address(&host) {
+            tracing::warn!("{}", crate::tl!("socks.onion_rejected", conn = conn_id, host = &shown, reason = &reason));
+            reply_failure(socket, SOCKS_ONION_INVALID_ADDRESS).await;
+            anyhow::bail!("{}", crate::t!("socks.onion_rejected_bail", host = &shown, reason = &reason));
+        }
+    }
 
-    tracing::info!("{}", crate::t!("socks.connecting", conn_id, &host, port));
+    // Amorcage en cours : attente du client Tor dans la file (places et delai limites)
+    let Some(tor_client) = ready_client(&mut tor_source, &options, &state, conn_id).await else {
+        reply_failure(socket, SOCKS_GENERAL_FAILURE).await;
+        anyhow::bail!("{}", crate::t!("socks.bootstrap_not_ready"));
+    };
 
-    let prefs = StreamPrefs::new();
+    // Nouvelle identite ou reseau perdu : requete retenue le temps de la reconstruction des
+    // circuits, ou refusee aussitot, plutot que d'attendre l'expiration du delai
+    if state.is_rebuilding() {
+        match options.rebuild_policy {
+            RebuildPolicy::Off => {}
+            RebuildPolicy::Hold => {
+                tracing::debug!("{}", crate::tl!("socks.rebuild_holding", conn = conn_id));
+                if tokio::time::timeout(options.rebuild_hold, state.wait_until_rebuilt()).await.is_err() {
+                    tracing::debug!(
+                        "{}",
+                        crate::tl!("socks.rebuild_hold_expired", conn = conn_id, secs = options.rebuild_hold.as_secs())
+                    );
+                }
+            }
+            RebuildPolicy::FailFast => {
+                tracing::info!("{}", crate::tl!("socks.rebuild_rejected", conn = conn_id));
+                reply_failure(socket, SOCKS_NETWORK_UNREACHABLE).await;
+                anyhow::bail!("{}", crate::t!("socks.rebuild_rejected_bail"));
+            }
+        }
+    }
 
-    // Ouvrir un flux Tor vers la destination avec un timeout de 60 secondes
-    tracing::debug!("{}", crate::t!("socks.opening_stream", conn_id, &host, port));
-    let tor_stream = tokio::time::timeout(
-        std::time::Duration::from_secs(60),
-        tor_client.connect_with_prefs((&*host, port), &prefs),
+    // Politiques de sortie : inutile de construire des circuits si presque aucune sortie
+    // n'accepte ce port (les services onion n'en dependent pas)
+    if !onion {
+        let ipv6 = host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_ipv6());
+        if let Some(share) = crate::tor::exit_port_share(&tor_client, port, ipv6) {
+            if share < MIN_EXIT_PORT_SHARE {
+                let percent = format!("{:.1}", share * 100.0);
+                tracing::warn!("{}", crate::tl!("socks.exit_port_blocked", conn = conn_id, port = port, percent = &percent));
+                reply_failure(socket, SOCKS_NOT_ALLOWED).await;
+                anyhow::bail!("{}", crate::t!("socks.exit_port_blocked_bail", port = port));
+            }
+        }
+    }
+
+    // Le jeton d'isolation change a chaque "nouvelle identite" : circuits neufs.
+    // Isolation par client ou par destination : un jeton par adresse du client, hote et/ou
+    // port (renouveles eux aussi)
+    let mut prefs = options.stream_prefs();
+    if options.isolate_client_addr || options.isolate_dest_addr || options.isolate_dest_port {
+        let key = IsolationKey {
+            client: options.isolate_client_addr.then(|| peer.ip()),
+            host: options.isolate_dest_addr.then(|| host.to_ascii_lowercase()),
+            port: options.isolate_dest_port.then_some(port),
+        };
+        prefs.set_isolation(state.keyed_isolation_token(key));
+    } else {
+        prefs.set_isolation(state.isolation_token());
+    }
+
+    // Ouvrir un flux Tor vers la destination avec le delai configure (tor.timeouts)
+    tracing::debug!("{}", crate::tl!("socks.opening_stream", conn = conn_id, host = &shown, port = port));
+    let tor_stream = crate::traced!(
+        tokio::time::timeout(
+            options.attach_timeout,
+            connect_with_retry(&tor_client, &host, port, prefs, &options, conn_id)
+        ),
+        "tor_connect",
+        host = %shown,
+        port = port
     )
     .await
-    .map_err(|_| anyhow::anyhow!("{}", crate::t!("socks.connect_timeout", conn_id, &host, port)))?
-    .map_err(|e| anyhow::anyhow!("{}", crate::t!("socks.connect_failed", &host, port, e)))?;
+    .map_err(|_| {
+        anyhow::anyhow!(
+            "{}",
+            crate::t!("socks.connect_timeout", conn = conn_id, host = &shown, port = port, secs = options.attach_timeout.as_secs())
+        )
+    })?
+    .map_err(|e| anyhow::anyhow!("{}", crate::t!("socks.connect_failed", host = &shown, port = port, error = e)))?;
+
+    tracing::info!("{}", crate::tl!("socks.stream_established", conn = conn_id, host = &shown, port = port));
 
-    tracing::info!("{}", crate::t!("socks.stream_established", conn_id, &host, port));
+    // Attribuer le flux a son circuit : journal, onglets "Circuits" et "Connexions"
+    let circuit = crate::tor::stream_circuit(&tor_stream);
+    let exit = circuit.as_ref().and_then(|c| c.relays.last().cloned());
+    match (&circuit, &exit) {
+        (Some(c), Some(relay)) => {
+            let country = relay.country.as_deref().unwrap_or("??");
+            tracing::info!(
+                "{}",
+                crate::tl!("socks.stream_circuit", conn = conn_id, circuit = &c.id, exit = &relay.fingerprint, country = country)
+            );
+        }
+        _ => tracing::debug!("{}", crate::tl!("socks.stream_circuit_unknown", conn = conn_id)),
+    }
+    if let Some(relay) = &exit {
+        state.record_exit_country(relay.country.as_deref());
+        #[cfg(feature = "otel")]
+        crate::otel::record_exit_country(relay.country.as_deref());
+    }
+    let started = Instant::now();
+    state.add_connection(ConnectionEntry {
+        conn_id,
+        host: host.clone(),
+        port,
+        circuit_id: circuit.as_ref().map(|c| c.id.clone()),
+        exit,
+        started,
+    });
+    if let Some(circuit) = circuit {
+        state.record_circuit(circuit);
+    }
 
     // Recuperer le flux TCP sous-jacent et envoyer la reponse SOCKS5 manuellement
     // (necessaire car execute_command=false signifie que la bibliotheque ne l'envoie pas)
     let mut client_stream = socket.into_inner();
 
-    // Reponse SOCKS5 : VER=5, REP=0 (succes), RSV=0, ATYP=1 (IPv4), BND.ADDR=0.0.0.0, BND.PORT=0
-    let reply = [0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-    client_stream.write_all(&reply).await?;
+    // Reponse SOCKS5 de succes, BND.ADDR/BND.PORT = adresse locale de la connexion
+    let bind = if options.zero_bind_addr { None } else { client_stream.local_addr().ok() };
+    client_stream.write_all(&success_reply(bind)).await?;
     client_stream.flush().await?;
 
-    tracing::debug!("{}", crate::t!("socks.socks_reply_sent", conn_id));
+    tracing::debug!("{}", crate::tl!("socks.socks_reply_sent", conn = conn_id));
 
     // Separer le DataStream en lecteur et ecrivain
     let (tor_reader, tor_writer) = tor_stream.split();
 
     // Convertir les AsyncRead/Write de futures en AsyncRead/Write de tokio
     let mut tor_read = tor_reader.compat();
-    let mut tor_write = tor_writer.compat_write();
+    let tor_write = tor_writer.compat_write();
 
     // Relais bidirectionnel entre le client et Tor
-    let (mut client_read, mut client_write) = tokio::io::split(client_stream);
+    let (mut client_read, client_write) = tokio::io::split(client_stream);
 
-    let (client_to_tor, tor_to_client) = tokio::join!(
-        tokio::io::copy(&mut client_read, &mut tor_write),
-        tokio::io::copy(&mut tor_read, &mut client_write),
-    );
+    // Compter les octets relayes dans chaque sens (debit affiche dans le systray) et reperer
+    // les ecritures bloquees (pair qui ne lit plus)
+    let up_stall = StallWatch::new();
+    let down_stall = StallWatch::new();
+    let mut tor_write = Counted::new(tor_write, &state.bytes_up, &up_stall);
+    let mut client_write = Counted::new(client_write, &state.bytes_down, &down_stall);
 
-    match (client_to_tor, tor_to_client) {
-        (Ok(up), Ok(down)) => {
-            tracing::debug!("{}", crate::t!("socks.relay_complete", conn_id, up, down));
+    // Interrompu si le proxy est mis en pause avec fermeture des connexions
+    let closed = state.close_connections.notified();
+    let outcome = tokio::select! {
+        (client_to_tor, tor_to_client) = crate::traced!(
+            async {
+                tokio::join!(
+                    crate::buffer_pool::copy(&mut client_read, &mut tor_write, &options.buffers),
+                    crate::buffer_pool::copy(&mut tor_read, &mut client_write, &options.buffers),
+                )
+            },
+            "relay"
+        ) => match (client_to_tor, tor_to_client) {
+            (Ok(up), Ok(down)) => {
+                tracing::debug!("{}", crate::tl!("socks.relay_complete", conn = conn_id, up = up, down = down));
+                #[cfg(feature = "otel")]
+                crate::otel::record_bytes(up, down);
+                "completed"
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::debug!("{}", crate::tl!("socks.relay_ended", conn = conn_id, error = e));
+                "relay_error"
+            }
+        },
+        _ = closed => {
+            tracing::info!("{}", crate::tl!("socks.closed_by_pause", conn = conn_id));
+            "closed_by_pause"
+        }
+        peer = watch_stalls(&up_stall, &down_stall, options.stall_timeout.unwrap_or_default()),
+            if options.stall_timeout.is_some() =>
+        {
+            let secs = options.stall_timeout.unwrap_or_default().as_secs();
+            tracing::warn!("{}", crate::tl!("socks.relay_stalled", conn = conn_id, peer = peer, secs = secs));
+            "stalled"
         }
-        (Err(e), _) | (_, Err(e)) => {
-            tracing::debug!("{}", crate::t!("socks.relay_ended", conn_id, e));
+    };
+
+    let (up, down) = (tor_write.written, client_write.written);
+    crate::audit::record(conn_id, &host, port, started.elapsed(), up, down);
+
+    Ok(RelaySummary { up, down, outcome })
+}
+
+/// Ouvre le flux Tor, en reessayant sur un circuit neuf (jeton d'isolation unique) apres un
+/// echec reessayable (proxy.retry) ; le delai tor.timeouts.stream_attach_secs couvre le tout
+async fn connect_with_retry(
+    tor_client: &TorClient<PreferredRuntime>,
+    host: &str,
+    port: u16,
+    mut prefs: StreamPrefs,
+    options: &ClientOptions,
+    conn_id: u64,
+) -> Result<DataStream, arti_client::Error> {
+    let mut retries = 0;
+    loop {
+        match connect_stream(tor_client, host, port, &prefs, options.parallel_connects).await {
+            Err(e) if retries < options.retry_attempts && is_retriable(&e, options.retry_on) => {
+                retries += 1;
+                tracing::debug!("{}", crate::tl!("socks.connect_retry", conn = conn_id, attempt = retries, error = &e));
+                prefs.set_isolation(IsolationToken::new());
+            }
+            result => return result,
         }
     }
+}
 
-    Ok(())
+/// Echec lie au circuit ou au relais de sortie, qu'un autre circuit peut eviter
+fn is_retriable(error: &arti_client::Error, on: RetryOn) -> bool {
+    match error.kind() {
+        ErrorKind::CircuitCollapse
+        | ErrorKind::TorNetworkTimeout
+        | ErrorKind::RemoteStreamClosed
+        | ErrorKind::RemoteStreamReset => true,
+        ErrorKind::ExitPolicyRejected | ErrorKind::ExitTimeout => on == RetryOn::CircuitAndExit,
+        _ => false,
+    }
+}
+
+/// Ouvre le flux Tor vers host:port. Avec plusieurs tentatives en parallele, les suivantes
+/// partent sur des circuits neufs (jeton d'isolation unique) et la premiere reussie l'emporte ;
+/// en cas d'echec de toutes, la derniere erreur est retournee.
+async fn connect_stream(
+    tor_client: &TorClient<PreferredRuntime>,
+    host: &str,
+    port: u16,
+    prefs: &StreamPrefs,
+    parallel: u8,
+) -> Result<DataStream, arti_client::Error> {
+    if parallel <= 1 {
+        return tor_client.connect_with_prefs((host, port), prefs).await;
+    }
+    let attempts = (0..parallel).map(|attempt| {
+        let mut prefs = prefs.clone();
+        if attempt > 0 {
+            prefs.set_isolation(IsolationToken::new());
+        }
+        Box::pin(async move { tor_client.connect_with_prefs((host, port), &prefs).await })
+    });
+    futures::future::select_ok(attempts).await.map(|(stream, _)| stream)
+}
+
+/// Met a jour periodiquement le debit a partir des compteurs d'octets (tous ports confondus)
+pub async fn measure_throughput(state: Arc<AppState>) {
+    let mut last = state.traffic_totals();
+    let mut last_at = Instant::now();
+    loop {
+        tokio::time::sleep(THROUGHPUT_INTERVAL).await;
+        let totals = state.traffic_totals();
+        let secs = last_at.elapsed().as_secs_f64().max(0.001);
+        state.set_throughput(
+            ((totals.0 - last.0) as f64 / secs) as u64,
+            ((totals.1 - last.1) as f64 / secs) as u64,
+        );
+        last = totals;
+        last_at = Instant::now();
+    }
+}
+
+/// Attend qu'une ecriture du relais reste bloquee plus de `timeout` ; retourne le pair qui ne
+/// lit plus ("tor" ou "client")
+async fn watch_stalls(up: &StallWatch, down: &StallWatch, timeout: Duration) -> &'static str {
+    loop {
+        tokio::time::sleep(STALL_CHECK_INTERVAL).await;
+        if up.blocked_for().is_some_and(|blocked| blocked >= timeout) {
+            return "tor";
+        }
+        if down.blocked_for().is_some_and(|blocked| blocked >= timeout) {
+            return "client";
+        }
+    }
+}
+
+/// Ecriture bloquee d'un sens du relais : debut du blocage en millisecondes depuis la creation,
+/// plus un (0 : pas de blocage). Atomique : lu par la surveillance pendant la copie.
+struct StallWatch {
+    origin: Instant,
+    blocked_at: AtomicU64,
+}
+
+impl StallWatch {
+    fn new() -> Self {
+        Self { origin: Instant::now(), blocked_at: AtomicU64::new(0) }
+    }
+
+    /// Ecriture refusee faute de place : debut du blocage, s'il n'est pas deja en cours
+    fn blocked(&self) {
+        let now = self.origin.elapsed().as_millis() as u64 + 1;
+        let _ = self.blocked_at.compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    fn unblocked(&self) {
+        self.blocked_at.store(0, Ordering::Relaxed);
+    }
+
+    /// Duree du blocage en cours
+    fn blocked_for(&self) -> Option<Duration> {
+        match self.blocked_at.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(self.origin.elapsed().saturating_sub(Duration::from_millis(at - 1))),
+        }
+    }
+}
+
+/// Ecrivain qui ajoute a un compteur partage le nombre d'octets ecrits
+/// (et les totalise pour la connexion : journal d'audit), et signale ses blocages
+struct Counted<'a, W> {
+    inner: W,
+    bytes: &'a AtomicU64,
+    written: u64,
+    stall: &'a StallWatch,
+}
+
+impl<'a, W> Counted<'a, W> {
+    fn new(inner: W, bytes: &'a AtomicU64, stall: &'a StallWatch) -> Self {
+        Self { inner, bytes, written: 0, stall }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for Counted<'_, W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        match poll {
+            Poll::Pending => self.stall.blocked(),
+            Poll::Ready(Ok(n)) => {
+                self.stall.unblocked();
+                self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+                self.written += n as u64;
+            }
+            Poll::Ready(Err(_)) => self.stall.unblocked(),
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONION_V3: &str = "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad";
+
+    #[test]
+    fn check_onion_address_accepts_v3() {
+        crate::i18n::init("en");
+        assert_eq!(check_onion_address(&format!("{}.onion", ONION_V3)), Ok(()));
+        assert_eq!(check_onion_address(&format!("www.{}.onion", ONION_V3)), Ok(()));
+        assert_eq!(check_onion_address(&format!("{}.ONION.", ONION_V3.to_ascii_uppercase())), Ok(()));
+    }
+
+    #[test]
+    fn check_onion_address_rejects_v2() {
+        crate::i18n::init("en");
+        assert_eq!(check_onion_address("expyuzz4wqqyqhjn.onion"), Err(crate::t!("socks.onion_v2")));
+        assert_eq!(check_onion_address("www.expyuzz4wqqyqhjn.onion"), Err(crate::t!("socks.onion_v2")));
+    }
+
+    #[test]
+    fn check_onion_address_rejects_malformed() {
+        crate::i18n::init("en");
+        let v2 = Err(crate::t!("socks.onion_v2"));
+        // Somme de controle fausse (dernier caractere modifie)
+        let bad_checksum = format!("{}id.onion", &ONION_V3[..ONION_V3.len() - 2]);
+        for host in [bad_checksum.as_str(), "abc.onion", ".onion", "not-base32-01890!.onion"] {
+            let result = check_onion_address(host);
+            assert!(result.is_err(), "{}", host);
+            assert_ne!(result, v2, "{}", host);
+        }
+    }
 }
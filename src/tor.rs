@@ -1,21 +1,286 @@
 // Bootstrap du client Tor via arti-client.
-// Configure les repertoires de cache et d'etat, puis demarre la connexion au reseau Tor.
+// Configure les repertoires de cache et d'etat (et les ponts eventuels), puis demarre la
+// connexion au reseau Tor.
+// Fournit aussi l'introspection des circuits utilises par les flux (chemin, pays des relais),
+// la part des sorties acceptant un port (politiques de sortie du consensus) et l'installation des cles d'autorisation client des services onion restreints.
+// L'effacement des donnees d'identite (gardes, cache, cles d'autorisation client) est fait au
+// demarrage suivant, avant la creation du client.
 
+use std::io::Read;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use arti_client::{TorClient, TorClientConfig};
+use futures::StreamExt;
+use arti_client::config::pt::TransportConfigBuilder;
+use arti_client::config::BridgeConfigBuilder;
+use arti_client::{DataStream, TorClient, TorClientConfig};
+use tor_chanmgr::PaddingLevel;
+use tor_config::ExplicitOrAuto;
 use tor_config_path::CfgPath;
+use tor_geoip::GeoipDb;
+use tor_guardmgr::VanguardMode;
+use tor_hscrypto::pk::{HsClientDescEncKey, HsClientDescEncKeypair, HsClientDescEncSecretKey, HsId};
+use tor_keymgr::KeystoreSelector;
+use tor_llcrypto::pk::curve25519;
+use tor_netdir::NetDirProvider;
+use tor_netdoc::types::policy::AddrPortPattern;
+use tor_linkspec::{HasAddrs, HasRelayIds};
 use tor_rtcompat::PreferredRuntime;
 
-use crate::config::IronCloakConfig;
+use crate::config::{IronCloakConfig, PaddingMode, VanguardsMode};
+use crate::gui::state::AppState;
+use crate::security::SecretBytes;
+
+/// Relais d'un circuit tel que vu par le client.
+/// Les microdescripteurs ne contiennent pas le surnom des relais : on affiche l'empreinte.
+#[derive(Debug, Clone)]
+pub struct RelayInfo {
+    /// Empreinte RSA du relais (format "$hex")
+    pub fingerprint: String,
+    /// Premiere adresse annoncee par le relais
+    pub addr: Option<IpAddr>,
+    /// Code pays ISO (base GeoIP embarquee)
+    pub country: Option<String>,
+}
+
+/// Circuit auquel un flux a ete attache
+#[derive(Debug, Clone)]
+pub struct CircuitInfo {
+    /// Identifiant unique du circuit dans ce processus
+    pub id: String,
+    /// Relais du circuit, de la garde vers la sortie
+    pub relays: Vec<RelayInfo>,
+}
+
+/// Marqueur depose dans le repertoire de donnees pour demander la rotation des gardes
+const ROTATE_GUARDS_MARKER: &str = "rotate_guards";
+
+/// Fichier d'etat d'arti contenant les gardes selectionnees (sous state_dir/state/)
+fn guards_state_file(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join("state").join("state").join("guards.json")
+}
+
+/// Demande la rotation des gardes au prochain demarrage.
+/// L'etat d'arti ne peut pas etre modifie pendant que le client tourne (il le reecrit) :
+/// on depose un marqueur, traite par bootstrap_tor avant la creation du client.
+pub fn request_guard_rotation(data_dir: &str) -> Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(Path::new(data_dir).join(ROTATE_GUARDS_MARKER), b"")?;
+    Ok(())
+}
+
+/// Supprime l'etat des gardes si une rotation a ete demandee
+fn apply_pending_guard_rotation(data_dir: &str) {
+    let marker = Path::new(data_dir).join(ROTATE_GUARDS_MARKER);
+    if !marker.exists() {
+        return;
+    }
+
+    let guards_file = guards_state_file(data_dir);
+    match std::fs::remove_file(&guards_file) {
+        Ok(()) => tracing::warn!("{}", crate::tl!("tor.guards_rotated")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::error!("{}", crate::tl!("tor.guards_rotate_failed", error = e)),
+    }
+    let _ = std::fs::remove_file(&marker);
+}
+
+/// Marqueur depose dans le repertoire de donnees pour demander le vidage du cache
+const CLEAR_CACHE_MARKER: &str = "clear_cache";
+
+/// Cache de l'annuaire d'arti (consensus, descripteurs des relais), retelecharge au besoin
+pub fn cache_dir(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join("cache")
+}
+
+/// Taille du cache sur le disque, en octets
+pub fn cache_size(data_dir: &str) -> u64 {
+    dir_size(&cache_dir(data_dir))
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Demande le vidage du cache au prochain demarrage : arti l'utilise (base SQLite ouverte)
+/// tant que le client tourne, comme pour la rotation des gardes
+pub fn request_cache_clear(data_dir: &str) -> Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(Path::new(data_dir).join(CLEAR_CACHE_MARKER), b"")?;
+    Ok(())
+}
+
+/// Vide le cache, client Tor arrete uniquement ; retourne le nombre d'octets liberes
+pub fn clear_cache(data_dir: &str) -> Result<u64> {
+    let dir = cache_dir(data_dir);
+    let size = dir_size(&dir);
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(size),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e).with_context(|| dir.display().to_string()),
+    }
+}
+
+/// Vide le cache si c'est demande
+fn apply_pending_cache_clear(data_dir: &str) {
+    let marker = Path::new(data_dir).join(CLEAR_CACHE_MARKER);
+    if !marker.exists() {
+        return;
+    }
+
+    match clear_cache(data_dir) {
+        Ok(bytes) => tracing::info!("{}", crate::tl!("tor.cache_cleared", bytes = bytes)),
+        Err(e) => tracing::error!("{}", crate::tl!("tor.cache_clear_failed", error = format!("{:#}", e))),
+    }
+    let _ = std::fs::remove_file(&marker);
+}
+
+/// Marqueur depose dans le repertoire de donnees pour demander l'effacement de l'identite
+const WIPE_IDENTITY_MARKER: &str = "wipe_identity";
+
+/// Demande l'effacement des donnees d'identite au prochain demarrage, comme pour la rotation des
+/// gardes : arti les utilise tant que le client tourne
+pub fn request_identity_wipe(data_dir: &str) -> Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(Path::new(data_dir).join(WIPE_IDENTITY_MARKER), b"")?;
+    Ok(())
+}
+
+/// Efface ce qui relie une session a la precedente : etat d'arti (gardes), cache de l'annuaire
+/// et cles d'autorisation client du keystore. Les cles des services onion heberges (leur
+/// adresse) sont conservees. Les cles de [tor.onion_auth] sont reinstallees apres l'amorcage,
+/// la configuration restant la reference (ce que dit l'avertissement de la fenetre).
+/// Retourne le nombre de fichiers effaces.
+pub fn wipe_identity(data_dir: &str) -> Result<usize> {
+    let state = Path::new(data_dir).join("state");
+    let mut wiped = 0;
+    for dir in [state.join("state"), state.join("keystore").join("client"), cache_dir(data_dir)] {
+        wiped += secure_remove_dir(&dir).with_context(|| dir.display().to_string())?;
+    }
+    Ok(wiped)
+}
+
+/// Ecrase chaque fichier (zeros, synchronises sur le disque) avant de le supprimer.
+/// Sans garantie sur un SSD ou un systeme de fichiers a copie sur ecriture, qui peuvent
+/// conserver les anciens blocs : l'etat chiffre (tor.encrypt_state) reste la vraie protection.
+fn secure_remove_dir(dir: &Path) -> std::io::Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            removed += secure_remove_dir(&path)?;
+            continue;
+        }
+        let len = entry.metadata()?.len();
+        if let Ok(mut file) = std::fs::OpenOptions::new().write(true).open(&path) {
+            std::io::copy(&mut std::io::repeat(0).take(len), &mut file)?;
+            file.sync_all()?;
+        }
+        std::fs::remove_file(&path)?;
+        removed += 1;
+    }
+    std::fs::remove_dir(dir)?;
+    Ok(removed)
+}
+
+/// Efface les donnees d'identite si c'est demande. Un effacement incomplet conserve le
+/// marqueur (nouvel essai au prochain demarrage) et empeche l'amorcage : repartir avec les
+/// anciennes gardes et cles serait pire qu'un proxy arrete.
+fn apply_pending_identity_wipe(data_dir: &str) -> Result<()> {
+    let marker = Path::new(data_dir).join(WIPE_IDENTITY_MARKER);
+    if !marker.exists() {
+        return Ok(());
+    }
+
+    let files = wipe_identity(data_dir)
+        .map_err(|e| anyhow::anyhow!("{}", crate::t!("tor.identity_wipe_failed", error = format!("{:#}", e))))?;
+    tracing::warn!("{}", crate::tl!("tor.identity_wiped", files = files));
+    let _ = std::fs::remove_file(&marker);
+    Ok(())
+}
 
 /// Demarre et connecte le client Tor avec la configuration fournie.
+/// La progression de l'amorcage est publiee dans l'etat partage (infobulle du systray).
 /// Retourne un client Tor pret a l'emploi, enveloppe dans un Arc pour le partage entre threads.
-pub async fn bootstrap_tor(config: &IronCloakConfig) -> Result<Arc<TorClient<PreferredRuntime>>> {
-    tracing::info!("{}", crate::t!("tor.configuring"));
+pub async fn bootstrap_tor(
+    config: &IronCloakConfig,
+    state: Arc<AppState>,
+) -> Result<Arc<TorClient<PreferredRuntime>>> {
+    tracing::info!("{}", crate::tl!("tor.configuring"));
 
     let data_dir = &config.tor.data_dir;
+    apply_pending_identity_wipe(data_dir)?;
+    apply_pending_guard_rotation(data_dir);
+    apply_pending_cache_clear(data_dir);
+
+    let tor_config = client_config(config, data_dir)?;
+
+    tracing::info!("{}", crate::tl!("tor.bootstrapping"));
+
+    // Creer le client puis l'amorcer (peut prendre plusieurs secondes)
+    let tor_client = TorClient::create_unbootstrapped(tor_config)
+        .context(crate::t!("tor.bootstrap_failed").to_string())?;
+
+    // Suivre la progression de l'amorcage pendant qu'il se deroule
+    let mut events = tor_client.bootstrap_events();
+    let progress_state = Arc::clone(&state);
+    let progress = tokio::spawn(async move {
+        while let Some(status) = events.next().await {
+            let percent = (status.as_frac() * 100.0).round().clamp(0.0, 100.0) as u8;
+            if percent != progress_state.bootstrap_percent() {
+                tracing::debug!("{}", crate::tl!("tor.bootstrap_progress", status = &status));
+                progress_state.set_bootstrap_percent(percent);
+            }
+        }
+    });
+    let result = tor_client.bootstrap().await;
+    progress.abort();
+    result.context(crate::t!("tor.bootstrap_failed").to_string())?;
+    state.set_bootstrap_percent(100);
+
+    tracing::info!("{}", crate::tl!("tor.bootstrap_complete"));
+
+    Ok(Arc::new(tor_client))
+}
+
+/// Client Tor distinct pour un port d'ecoute supplementaire ([[listeners]] avec data_dir) :
+/// etat, gardes et circuits propres, amorce sans suivi de progression dans l'interface
+pub async fn bootstrap_separate(
+    config: &IronCloakConfig,
+    data_dir: &str,
+) -> Result<Arc<TorClient<PreferredRuntime>>> {
+    apply_pending_identity_wipe(data_dir)?;
+    apply_pending_guard_rotation(data_dir);
+    apply_pending_cache_clear(data_dir);
+    let tor_config = client_config(config, data_dir)?;
+    let tor_client = TorClient::create_bootstrapped(tor_config)
+        .await
+        .context(crate::t!("tor.bootstrap_failed").to_string())?;
+    Ok(Arc::new(tor_client))
+}
+
+/// Configuration arti : stockage dans data_dir, remplissage, vanguards, pare-feu et delais de la
+/// configuration
+fn client_config(config: &IronCloakConfig, data_dir: &str) -> Result<TorClientConfig> {
     let cache_path = format!("{}/cache", data_dir);
     let state_path = format!("{}/state", data_dir);
 
@@ -26,18 +291,234 @@ pub async fn bootstrap_tor(config: &IronCloakConfig) -> Result<Arc<TorClient<Pre
         .cache_dir(CfgPath::new(cache_path))
         .state_dir(CfgPath::new(state_path));
 
-    let tor_config = builder
+    // Remplissage des canaux : moins de trafic en echange d'une resistance moindre a l'analyse
+    let padding = match config.tor.padding {
+        PaddingMode::Normal => PaddingLevel::Normal,
+        PaddingMode::Reduced => PaddingLevel::Reduced,
+        PaddingMode::Off => PaddingLevel::None,
+    };
+    builder.channel().padding(padding);
+    if config.tor.padding != PaddingMode::Normal {
+        tracing::info!("{}", crate::tl!("tor.padding_mode", mode = format!("{:?}", config.tor.padding).to_lowercase()));
+    }
+
+    // Protection vanguards des circuits vers les services onion
+    let vanguard_mode = match config.tor.security.vanguards {
+        VanguardsMode::Off => VanguardMode::Disabled,
+        VanguardsMode::Lite => VanguardMode::Lite,
+        VanguardsMode::Full => VanguardMode::Full,
+    };
+    builder
+        .vanguards()
+        .mode(ExplicitOrAuto::Explicit(vanguard_mode));
+    tracing::debug!("{}", crate::tl!("tor.vanguards_mode", mode = format!("{:?}", config.tor.security.vanguards)));
+
+    // Pare-feu restrictif : ne choisir que des relais joignables sur les ports autorises
+    let firewall = &config.tor.firewall;
+    if firewall.enabled {
+        let patterns = firewall
+            .reachable_ports
+            .iter()
+            .map(|port| format!("*:{}", port).parse::<AddrPortPattern>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context(crate::t!("tor.firewall_invalid").to_string())?;
+        let reachable = builder.path_rules().reachable_addrs();
+        reachable.clear();
+        reachable.extend(patterns);
+        tracing::info!("{}", crate::tl!("tor.firewall_enabled", ports = format!("{:?}", firewall.reachable_ports)));
+    }
+
+    // Ponts et transports enfichables ([tor.bridges]) : arti n'utilise alors plus que les
+    // ponts comme premier saut
+    let bridges = &config.tor.bridges;
+    if bridges.enabled && !bridges.lines.is_empty() {
+        for line in &bridges.lines {
+            let bridge: BridgeConfigBuilder = line
+                .parse()
+                .with_context(|| crate::t!("tor.bridge_invalid", line = line))?;
+            builder.bridges().bridges().push(bridge);
+        }
+        for (name, command) in &bridges.transports {
+            let mut parts = command.split_whitespace();
+            let Some(path) = parts.next() else {
+                continue;
+            };
+            let mut transport = TransportConfigBuilder::default();
+            transport
+                .protocols(vec![name
+                    .parse()
+                    .with_context(|| crate::t!("tor.transport_invalid", name = name))?])
+                .path(CfgPath::new(path.to_string()))
+                .arguments(parts.map(str::to_string).collect())
+                .run_on_startup(false);
+            builder.bridges().transports().push(transport);
+        }
+        tracing::info!(
+            "{}",
+            crate::tl!("tor.bridges_enabled", count = bridges.lines.len(), transports = bridges.transports.len())
+        );
+    }
+
+    // Delais de construction des circuits et d'ouverture des flux
+    let timeouts = &config.tor.timeouts;
+    if let Some(secs) = timeouts.circuit_build_secs {
+        // Parametres reseau du consensus (en millisecondes) : valeur initiale et plancher
+        // de l'estimateur adaptatif, pour les liaisons tres lentes
+        let millis = i32::try_from(secs.saturating_mul(1000)).unwrap_or(i32::MAX);
+        let net_params = builder.override_net_params();
+        net_params.insert("cbtinitialtimeout".to_string(), millis);
+        net_params.insert("cbtmintimeout".to_string(), millis);
+        tracing::info!("{}", crate::tl!("tor.circuit_build_timeout", secs = secs));
+    }
+    if let Some(secs) = timeouts.circuit_request_secs {
+        builder
+            .circuit_timing()
+            .request_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = timeouts.stream_connect_secs {
+        builder
+            .stream_timeouts()
+            .connect_timeout(Duration::from_secs(secs));
+    }
+
+    builder
         .build()
-        .context(crate::t!("tor.build_config_failed").to_string())?;
+        .context(crate::t!("tor.build_config_failed").to_string())
+}
 
-    tracing::info!("{}", crate::t!("tor.bootstrapping"));
+/// Decrit le circuit auquel un flux Tor est attache.
+/// Retourne None si le circuit est deja ferme ou si le flux n'expose pas son controle.
+pub fn stream_circuit(stream: &DataStream) -> Option<CircuitInfo> {
+    let circ = stream.client_stream_ctrl()?.circuit()?;
+    let path = circ.path_ref().ok()?;
+    let geoip = GeoipDb::new_embedded();
 
-    // Creer et amorcer le client Tor (peut prendre plusieurs secondes)
-    let tor_client = TorClient::create_bootstrapped(tor_config)
-        .await
-        .context(crate::t!("tor.bootstrap_failed").to_string())?;
+    let relays = path
+        .iter()
+        .filter_map(|hop| hop.as_chan_target())
+        .map(|target| {
+            let addr = target.addrs().first().map(|a| a.ip());
+            RelayInfo {
+                fingerprint: target
+                    .rsa_identity()
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                addr,
+                country: addr
+                    .and_then(|ip| geoip.lookup_country_code(ip))
+                    .map(|cc| cc.to_string()),
+            }
+        })
+        .collect();
 
-    tracing::info!("{}", crate::t!("tor.bootstrap_complete"));
+    Some(CircuitInfo {
+        id: circ.unique_id().to_string(),
+        relays,
+    })
+}
 
-    Ok(Arc::new(tor_client))
+/// Part des relais de sortie dont la politique accepte ce port, en IPv6 pour une destination
+/// IPv6 et en IPv4 sinon. None sans annuaire a jour (amorcage en cours) ou sans sortie.
+pub fn exit_port_share(tor_client: &TorClient<PreferredRuntime>, port: u16, ipv6: bool) -> Option<f64> {
+    let netdir = tor_client.dirmgr().timely_netdir().ok()?;
+    let (mut exits, mut allowed) = (0u32, 0u32);
+    for relay in netdir.relays() {
+        let details = relay.low_level_details();
+        if !details.policies_allow_some_port() {
+            continue;
+        }
+        exits += 1;
+        let allows = if ipv6 {
+            details.supports_exit_port_ipv6(port)
+        } else {
+            details.supports_exit_port_ipv4(port)
+        };
+        if allows {
+            allowed += 1;
+        }
+    }
+    (exits > 0).then(|| f64::from(allowed) / f64::from(exits))
+}
+
+/// Installe dans le keystore d'arti les cles d'autorisation client configurees
+/// ([tor.onion_auth] et fichiers *.auth_private de tor.onion_auth_dir).
+/// Les cles invalides sont journalisees et ignorees. Retourne le nombre de cles installees.
+pub fn install_onion_auth_keys(config: &IronCloakConfig, tor_client: &TorClient<PreferredRuntime>) -> usize {
+    let mut entries: Vec<(String, String)> = config
+        .tor
+        .onion_auth
+        .iter()
+        .map(|(addr, key)| (addr.clone(), key.clone()))
+        .collect();
+    if let Some(ref dir) = config.tor.onion_auth_dir {
+        entries.extend(read_auth_dir(Path::new(dir)));
+    }
+
+    let mut installed = 0;
+    for (addr, key) in entries {
+        let result = parse_onion_auth(&addr, &key).and_then(|(hsid, keypair)| {
+            tor_client
+                .insert_service_discovery_key(KeystoreSelector::Primary, hsid, keypair)
+                .map_err(anyhow::Error::from)
+        });
+        match result {
+            Ok(()) => installed += 1,
+            Err(e) => tracing::warn!("{}", crate::tl!("tor.onion_auth_invalid", address = &addr, error = e)),
+        }
+    }
+    if installed > 0 {
+        tracing::info!("{}", crate::tl!("tor.onion_auth_installed", count = installed));
+    }
+    installed
+}
+
+/// Lit les fichiers *.auth_private au format C Tor : "<adresse sans .onion>:descriptor:x25519:<cle>"
+fn read_auth_dir(dir: &Path) -> Vec<(String, String)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("{}", crate::tl!("tor.onion_auth_dir_failed", path = dir.display(), error = e));
+            return Vec::new();
+        }
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "auth_private"))
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| {
+            let line = content.lines().find(|l| !l.trim().is_empty())?.trim().to_string();
+            let (addr, key) = line.split_once(':')?;
+            Some((format!("{}.onion", addr.trim_end_matches(".onion")), key.to_string()))
+        })
+        .collect()
+}
+
+/// Decode une adresse .onion et une cle privee "descriptor:x25519:BASE32" en paire de cles arti
+fn parse_onion_auth(addr: &str, key: &str) -> Result<(HsId, HsClientDescEncKeypair)> {
+    let hsid: HsId = addr
+        .trim()
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{}", crate::t!("tor.onion_auth_bad_address", error = e)))?;
+
+    let encoded = key
+        .trim()
+        .strip_prefix("descriptor:x25519:")
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("tor.onion_auth_bad_format")))?;
+    let decoded = data_encoding::BASE32_NOPAD
+        .decode(encoded.to_ascii_uppercase().as_bytes())
+        .map(SecretBytes::new)
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("tor.onion_auth_bad_format")))?;
+    let bytes: [u8; 32] = (*decoded)
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("tor.onion_auth_bad_format")))?;
+
+    let secret = curve25519::StaticSecret::from(bytes);
+    let public = curve25519::PublicKey::from(&secret);
+    let keypair = HsClientDescEncKeypair::new(
+        HsClientDescEncKey::from(public),
+        HsClientDescEncSecretKey::from(secret),
+    );
+    Ok((hsid, keypair))
 }
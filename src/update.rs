@@ -0,0 +1,136 @@
+// Verification des nouvelles versions, toujours a travers le client Tor (jamais en clair).
+// Interroge l'API des releases (format GitHub : champ "tag_name") au demarrage puis
+// periodiquement ; le resultat est affiche discretement dans la fenetre et le systray.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use arti_client::TorClient;
+use serde::Deserialize;
+use tor_rtcompat::PreferredRuntime;
+
+use crate::config::IronCloakConfig;
+use crate::gui::state::{AppState, UpdateState};
+
+/// Version de l'executable en cours
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Delai avant la premiere verification (laisser les premiers circuits aux flux de l'utilisateur)
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(120);
+
+/// Taille maximale de la reponse (les notes de version peuvent etre longues)
+const MAX_BODY: usize = 512 * 1024;
+
+/// Champ utile de la reponse de l'API des releases
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// Verifie periodiquement la presence d'une nouvelle version (updates.enabled).
+pub async fn run_update_checker(
+    config: &IronCloakConfig,
+    tor_client: Arc<TorClient<PreferredRuntime>>,
+    state: Arc<AppState>,
+) {
+    let interval = Duration::from_secs(config.updates.interval_hours.max(1) * 3600);
+    tokio::time::sleep(FIRST_CHECK_DELAY).await;
+    loop {
+        let result = check(&config.updates.release_url, &tor_client).await;
+        state.set_update_state(result);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Interroge l'API des releases et compare la derniere version a la version courante
+pub async fn check(release_url: &str, tor_client: &TorClient<PreferredRuntime>) -> UpdateState {
+    match latest_version(release_url, tor_client).await {
+        Ok(latest) if is_newer(&latest, CURRENT_VERSION) => {
+            tracing::info!("{}", crate::tl!("update.available", version = &latest, current = CURRENT_VERSION));
+            UpdateState::Available(latest)
+        }
+        Ok(_) => {
+            tracing::debug!("{}", crate::tl!("update.up_to_date", version = CURRENT_VERSION));
+            UpdateState::UpToDate
+        }
+        Err(e) => {
+            tracing::warn!("{}", crate::tl!("update.check_failed", error = format!("{:#}", e)));
+            UpdateState::Failed(e.to_string())
+        }
+    }
+}
+
+/// Derniere version publiee (sans le prefixe "v")
+async fn latest_version(release_url: &str, tor_client: &TorClient<PreferredRuntime>) -> Result<String> {
+    let rest = release_url
+        .strip_prefix("https://")
+        .ok_or_else(|| anyhow::anyhow!("{}", crate::t!("update.bad_url", url = release_url)))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+
+    let response =
+        crate::probe::https_get(tor_client, host, path, Duration::from_secs(90), MAX_BODY).await?;
+    if response.status != 200 {
+        anyhow::bail!("{}", crate::t!("probe.http_status", host = host, status = response.status));
+    }
+    let release: Release = serde_json::from_slice(&response.body)
+        .with_context(|| crate::t!("probe.bad_response", host = host))?;
+    Ok(release.tag_name.trim_start_matches('v').to_string())
+}
+
+/// Compare deux versions "x.y.z" numeriquement (les suffixes de pre-version sont ignores)
+fn is_newer(candidate: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        let mut parts = version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect::<Vec<_>>();
+        // "1.2" et "1.2.0" sont la meme version
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        parts
+    }
+    parse(candidate) > parse(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_numerically() {
+        assert!(is_newer("0.10.0", "0.9.0"));
+        assert!(is_newer("1.0.0", "0.99.99"));
+        assert!(is_newer("0.2.1", "0.2.0"));
+        assert!(!is_newer("0.9.0", "0.10.0"));
+        assert!(!is_newer("0.2.0", "0.2.0"));
+    }
+
+    #[test]
+    fn ignores_missing_trailing_zeros() {
+        assert!(!is_newer("1.2.0", "1.2"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(is_newer("1.2.1", "1.2"));
+    }
+
+    #[test]
+    fn ignores_prerelease_and_build_suffixes() {
+        assert!(!is_newer("0.3.0-beta.1", "0.3.0"));
+        assert!(!is_newer("0.3.0+build.5", "0.3.0"));
+        assert!(is_newer("0.4.0-rc.1", "0.3.0"));
+    }
+
+    #[test]
+    fn treats_garbage_as_zero() {
+        assert!(!is_newer("", "0.1.0"));
+        assert!(!is_newer("x.y.z", "0.1.0"));
+        assert!(is_newer("0.1.0", "garbage"));
+    }
+}
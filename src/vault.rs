@@ -0,0 +1,358 @@
+// Chiffrement au repos du repertoire d'etat d'arti (tor.encrypt_state) : gardes, keystore des
+// services onion et cles d'autorisation client.
+// Hors execution, l'etat n'existe que sous forme d'archive chiffree (<data_dir>/state.vault) :
+// cle derivee de la phrase secrete (Argon2id), chiffrement ChaCha20-Poly1305. Au demarrage,
+// l'archive est dechiffree dans <data_dir>/state ; a l'arret, une fois le client Tor libere,
+// l'etat est rechiffre puis le repertoire en clair supprime.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use zeroize::Zeroizing;
+
+use crate::security::SecretBytes;
+
+/// Archive chiffree de l'etat, dans le repertoire de donnees
+const VAULT_FILE: &str = "state.vault";
+
+/// Identifiant et version du format de l'archive
+const MAGIC: &[u8; 8] = b"ICVAULT1";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Etat dechiffre pendant l'execution, a rechiffrer a l'arret
+pub struct StateVault {
+    data_dir: PathBuf,
+    salt: [u8; SALT_LEN],
+    key: SecretBytes,
+}
+
+/// Archive chiffree presente (sinon, la phrase secrete saisie la creera a l'arret)
+pub fn exists(data_dir: &Path) -> bool {
+    data_dir.join(VAULT_FILE).exists()
+}
+
+/// Dechiffre l'archive dans le repertoire d'etat (ou prepare sa creation au premier lancement).
+/// Une phrase secrete incorrecte est refusee sans toucher au disque.
+pub fn open(data_dir: &Path, passphrase: &str) -> Result<StateVault> {
+    let vault_path = data_dir.join(VAULT_FILE);
+    let state_dir = data_dir.join("state");
+
+    let content = match std::fs::read(&vault_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // Premier lancement chiffre : nouveau sel, l'etat existant sera chiffre a l'arret
+            let salt: [u8; SALT_LEN] = rand::random();
+            let key = derive_key(passphrase, &salt)?;
+            tracing::info!("{}", crate::tl!("vault.created", path = vault_path.display()));
+            return Ok(StateVault { data_dir: data_dir.to_path_buf(), salt, key });
+        }
+        // Archive presente mais illisible (droits, erreur disque...) : la traiter comme un
+        // premier lancement la remplacerait a l'arret, et l'etat serait perdu
+        Err(e) => {
+            return Err(e).with_context(|| crate::t!("vault.unreadable", path = vault_path.display()));
+        }
+    };
+
+    let (salt, key, plaintext) = decrypt(&content, passphrase, &vault_path)?;
+
+    // Repertoire en clair laisse par un arret brutal : plus recent que l'archive, conserve
+    if state_dir.exists() {
+        tracing::warn!("{}", crate::tl!("vault.stale_plaintext", path = state_dir.display()));
+    } else {
+        unpack(&plaintext, &state_dir)?;
+    }
+    tracing::info!("{}", crate::tl!("vault.opened", path = vault_path.display()));
+    Ok(StateVault { data_dir: data_dir.to_path_buf(), salt, key })
+}
+
+impl StateVault {
+    /// Rechiffre le repertoire d'etat dans l'archive puis supprime sa copie en clair.
+    /// A appeler apres la liberation du client Tor (plus aucune ecriture d'arti).
+    pub fn seal(self) -> Result<()> {
+        let state_dir = self.data_dir.join("state");
+        let vault_path = self.data_dir.join(VAULT_FILE);
+
+        let mut archive = Zeroizing::new(Vec::new());
+        if state_dir.exists() {
+            pack(&state_dir, &state_dir, &mut archive)?;
+        }
+        let content = encrypt(&self.key, &self.salt, &archive)?;
+
+        // Ecriture atomique : l'ancienne archive reste valide jusqu'au renommage
+        let tmp_path = vault_path.with_extension("vault.tmp");
+        std::fs::write(&tmp_path, &content).with_context(|| tmp_path.display().to_string())?;
+        std::fs::rename(&tmp_path, &vault_path).with_context(|| vault_path.display().to_string())?;
+        if state_dir.exists() {
+            std::fs::remove_dir_all(&state_dir).with_context(|| state_dir.display().to_string())?;
+        }
+        tracing::info!("{}", crate::tl!("vault.sealed", path = vault_path.display()));
+        Ok(())
+    }
+}
+
+/// Chiffre l'etat serialise : en-tete (identifiant, sel, nonce) puis texte chiffre
+fn encrypt(key: &[u8], salt: &[u8; SALT_LEN], archive: &[u8]) -> Result<Vec<u8>> {
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&nonce), archive)
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("vault.encrypt_failed")))?;
+
+    let mut content = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    content.extend_from_slice(MAGIC);
+    content.extend_from_slice(salt);
+    content.extend_from_slice(&nonce);
+    content.extend_from_slice(&ciphertext);
+    Ok(content)
+}
+
+/// Verifie l'en-tete de l'archive et la dechiffre : sel, cle derivee et etat serialise
+fn decrypt(content: &[u8], passphrase: &str, path: &Path) -> Result<([u8; SALT_LEN], SecretBytes, SecretBytes)> {
+    if content.len() < MAGIC.len() + SALT_LEN + NONCE_LEN || !content.starts_with(MAGIC) {
+        bail!("{}", crate::t!("vault.corrupt", path = path.display()));
+    }
+    let (salt, rest) = content[MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let salt: [u8; SALT_LEN] = salt.try_into().expect("longueur du sel");
+    let key = derive_key(passphrase, &salt)?;
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map(SecretBytes::new)
+        .map_err(|_| anyhow::anyhow!("{}", crate::t!("vault.wrong_passphrase")))?;
+    Ok((salt, key, plaintext))
+}
+
+/// Cle de chiffrement derivee de la phrase secrete (Argon2id, parametres par defaut)
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<SecretBytes> {
+    let mut key = SecretBytes::zeroed(32);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(key)
+}
+
+/// Serialise les fichiers du repertoire : pour chacun, chemin relatif (u16 + UTF-8),
+/// permissions Unix (u32), taille (u64) et contenu
+fn pack(root: &Path, dir: &Path, out: &mut Vec<u8>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| dir.display().to_string())? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            pack(root, &path, out)?;
+            continue;
+        }
+        if !file_type.is_file() {
+            continue;
+        }
+        let relative = path.strip_prefix(root)?.to_string_lossy().replace('\\', "/");
+        let data = Zeroizing::new(std::fs::read(&path).with_context(|| path.display().to_string())?);
+        out.extend_from_slice(&(relative.len() as u16).to_be_bytes());
+        out.extend_from_slice(relative.as_bytes());
+        out.extend_from_slice(&file_mode(&entry.metadata()?).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        out.extend_from_slice(&data);
+    }
+    Ok(())
+}
+
+/// Recree les fichiers serialises par pack() sous `dir` (repertoires en 0700 sous Unix, comme
+/// l'exige arti)
+fn unpack(mut archive: &[u8], dir: &Path) -> Result<()> {
+    create_private_dir(dir)?;
+    while !archive.is_empty() {
+        let path_len = u16::from_be_bytes(take(&mut archive, 2)?.try_into()?) as usize;
+        let relative = std::str::from_utf8(take(&mut archive, path_len)?)?;
+        let mode = u32::from_be_bytes(take(&mut archive, 4)?.try_into()?);
+        let data_len = u64::from_be_bytes(take(&mut archive, 8)?.try_into()?) as usize;
+        let data = take(&mut archive, data_len)?;
+
+        // Chemins relatifs uniquement : une archive forgee ne doit rien ecrire ailleurs
+        if relative.split('/').any(|part| part.is_empty() || part == "..") {
+            bail!("{}", crate::t!("vault.corrupt", path = relative));
+        }
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            create_private_dir(parent)?;
+        }
+        std::fs::write(&path, data).with_context(|| path.display().to_string())?;
+        set_file_mode(&path, mode)?;
+    }
+    Ok(())
+}
+
+/// Preleve `len` octets en tete de l'archive
+fn take<'a>(archive: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if archive.len() < len {
+        bail!("{}", crate::t!("vault.truncated"));
+    }
+    let (head, tail) = archive.split_at(len);
+    *archive = tail;
+    Ok(head)
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o777
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> u32 {
+    0o600
+}
+
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_file_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| dir.display().to_string())?;
+    }
+    #[cfg(not(unix))]
+    std::fs::create_dir_all(dir).with_context(|| dir.display().to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init_i18n() {
+        crate::i18n::init("en");
+    }
+
+    /// Entree d'archive au format de pack()
+    fn entry(relative: &str, mode: u32, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(relative.len() as u16).to_be_bytes());
+        out.extend_from_slice(relative.as_bytes());
+        out.extend_from_slice(&mode.to_be_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn pack_unpack_round_trip() {
+        init_i18n();
+        let source = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(source.path().join("keystore/hss")).unwrap();
+        std::fs::write(source.path().join("state.json"), b"{\"guards\":[]}").unwrap();
+        std::fs::write(source.path().join("keystore/hss/id.ed25519_private"), [0u8, 1, 2, 255]).unwrap();
+        std::fs::write(source.path().join("empty"), b"").unwrap();
+
+        let mut archive = Vec::new();
+        pack(source.path(), source.path(), &mut archive).unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let restored = target.path().join("state");
+        unpack(&archive, &restored).unwrap();
+
+        assert_eq!(std::fs::read(restored.join("state.json")).unwrap(), b"{\"guards\":[]}");
+        assert_eq!(std::fs::read(restored.join("keystore/hss/id.ed25519_private")).unwrap(), [0u8, 1, 2, 255]);
+        assert!(std::fs::read(restored.join("empty")).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unpack_restores_modes_and_private_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+        init_i18n();
+        let mut archive = entry("keys/secret", 0o600, b"k");
+        archive.extend(entry("public", 0o644, b"p"));
+        let target = tempfile::tempdir().unwrap();
+        let restored = target.path().join("state");
+        unpack(&archive, &restored).unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&restored.join("keys/secret")), 0o600);
+        assert_eq!(mode(&restored.join("public")), 0o644);
+        assert_eq!(mode(&restored.join("keys")), 0o700);
+        assert_eq!(mode(&restored), 0o700);
+    }
+
+    #[test]
+    fn unpack_rejects_paths_outside_state_dir() {
+        init_i18n();
+        let target = tempfile::tempdir().unwrap();
+        let restored = target.path().join("state");
+        for relative in ["../escape", "keys/../../escape", "/etc/passwd", "keys//secret"] {
+            assert!(unpack(&entry(relative, 0o600, b"x"), &restored).is_err(), "{}", relative);
+        }
+        assert!(!target.path().join("escape").exists());
+    }
+
+    #[test]
+    fn unpack_rejects_truncated_archive() {
+        init_i18n();
+        let archive = entry("state.json", 0o600, b"0123456789");
+        let target = tempfile::tempdir().unwrap();
+        for len in [1, 5, archive.len() - 1] {
+            assert!(unpack(&archive[..len], &target.path().join(format!("state{}", len))).is_err());
+        }
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        init_i18n();
+        let salt = [7u8; SALT_LEN];
+        let key = derive_key("correct horse", &salt).unwrap();
+        let content = encrypt(&key, &salt, b"serialized state").unwrap();
+        assert!(content.starts_with(MAGIC));
+
+        let (read_salt, _, plaintext) = decrypt(&content, "correct horse", Path::new("state.vault")).unwrap();
+        assert_eq!(read_salt, salt);
+        assert_eq!(&plaintext[..], b"serialized state");
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_passphrase() {
+        init_i18n();
+        let salt = [7u8; SALT_LEN];
+        let key = derive_key("correct horse", &salt).unwrap();
+        let content = encrypt(&key, &salt, b"serialized state").unwrap();
+        let error = decrypt(&content, "battery staple", Path::new("state.vault")).err().unwrap();
+        assert_eq!(error.to_string(), crate::t!("vault.wrong_passphrase"));
+    }
+
+    #[test]
+    fn decrypt_rejects_bad_header() {
+        init_i18n();
+        let salt = [7u8; SALT_LEN];
+        let key = derive_key("correct horse", &salt).unwrap();
+        let mut content = encrypt(&key, &salt, b"serialized state").unwrap();
+        let path = Path::new("state.vault");
+        let corrupt = crate::t!("vault.corrupt", path = path.display());
+
+        // Archive tronquee dans l'en-tete
+        let error = decrypt(&content[..MAGIC.len() + SALT_LEN], "correct horse", path).err().unwrap();
+        assert_eq!(error.to_string(), corrupt);
+        // Identifiant de format inconnu
+        content[0] = b'X';
+        let error = decrypt(&content, "correct horse", path).err().unwrap();
+        assert_eq!(error.to_string(), corrupt);
+        // Texte chiffre tronque : l'authentification echoue
+        content[0] = MAGIC[0];
+        content.truncate(content.len() - 1);
+        assert!(decrypt(&content, "correct horse", path).is_err());
+    }
+}